@@ -1,30 +1,35 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use daemon::{
     config::TranscodeConfig, 
-    job::{self, Job, JobStatus, load_all_jobs, save_job},
-    scan, ffprobe, classifier, sidecar,
+    job::{self, Job, JobStatus},
+    scan, ffprobe, classifier, sidecar, progress, control, container,
+    store::{self, JobStore},
+    load::{self, LoadMonitor, ProcessPriority},
+    EncodeProgress, JobFailure,
     FFmpegManager, CommandBuilder,
+    ffmpeg_native::Cancelled,
     quality::QualityCalculator,
+    audio::AudioAction,
+    tracks::{ExtraVideoPolicy, TrackKind},
+    dolby_vision::DolbyVisionHandling,
+    hdr10_plus::Hdr10PlusHandling,
+    interlace::{self, DeinterlacePolicy, ScanType},
     classifier::QualityTier,
-    test_clip::TestClipWorkflow,
+    test_clip::{ApprovalDecision, TestClipWorkflow},
+    config::ApprovalTimeoutPolicy,
 };
 use std::path::{Path, PathBuf};
 use std::fs;
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::{mpsc, watch};
+use tokio::task::JoinSet;
+use tokio::time::Duration;
 use chrono::{Utc, DateTime};
 use log::{info, warn, error, debug};
 
 /// Generate temp output path using configured temp_output_dir
-fn get_temp_output_path(cfg: &TranscodeConfig, source_path: &Path) -> PathBuf {
-    // Always use configured temp directory with source filename
-    let filename = source_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown");
-    cfg.temp_output_dir.join(format!("{}.tmp.av1.mkv", filename))
-}
-
 /// AV1 transcoding daemon
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -57,12 +62,13 @@ async fn main() -> Result<()> {
     info!("  Min bytes: {}", cfg.min_bytes);
     info!("  Max size ratio: {}", cfg.max_size_ratio);
     info!("  Job state dir: {}", cfg.job_state_dir.display());
+    info!("  Job store: {:?}", cfg.job_store);
     info!("  Scan interval: {}s", cfg.scan_interval_secs);
     
     // Initialize FFmpeg Manager
     info!("Initializing FFmpeg Manager...");
-    let ffmpeg_mgr = FFmpegManager::new(&cfg).await
-        .context("Failed to initialize FFmpeg Manager - ensure FFmpeg 8.0+ is installed with AV1 encoder support")?;
+    let ffmpeg_mgr = Arc::new(FFmpegManager::new(&cfg).await
+        .context("Failed to initialize FFmpeg Manager - ensure FFmpeg 8.0+ is installed with AV1 encoder support")?);
     
     info!("✅ FFmpeg Manager initialized successfully");
     info!("  FFmpeg version: {}.{}.{}", 
@@ -70,6 +76,15 @@ async fn main() -> Result<()> {
           ffmpeg_mgr.version.minor, 
           ffmpeg_mgr.version.patch);
     info!("  Selected encoder: {:?}", ffmpeg_mgr.best_encoder());
+    info!("  Concurrent jobs: {} ({} encoder thread(s) per job)",
+          cfg.max_concurrent_jobs(), cfg.thread_budget_per_job());
+    if cfg.schedule.is_restricted() {
+        info!("  Encoding schedule: {} window(s){}", cfg.schedule.windows.len(),
+              if cfg.schedule.pause_outside_windows { ", running encodes paused outside them" } else { "" });
+    }
+    if cfg.load_throttle.is_enabled() {
+        info!("  Load throttling: checked every {}s", cfg.load_throttle.check_interval_secs.max(1));
+    }
     
     // Verify library roots exist
     for root in &cfg.library_roots {
@@ -84,11 +99,21 @@ async fn main() -> Result<()> {
     fs::create_dir_all(&cfg.job_state_dir)
         .with_context(|| format!("Failed to create job state directory: {}", cfg.job_state_dir.display()))?;
 
+    // Switching to SQLite imports the existing JSON job files once
+    let migrated_count = store::migrate_json_jobs(&cfg)
+        .context("Failed to migrate JSON job files into the job database")?;
+    if migrated_count > 0 {
+        info!("📦 Migrated {} job(s) from JSON files into {}", migrated_count, cfg.job_store_path().display());
+    }
+    let store = store::open_job_store(&cfg, true)
+        .context("Failed to open job store")?;
+
     // Recovery on startup: check for stuck jobs and orphaned temp files
     info!("🔄 Starting recovery checks...");
-    let recovered_count = recover_stuck_jobs(&cfg).await
+    // No workers exist yet, so every Running job on disk is left over from a previous run
+    let recovered_count = recover_stuck_jobs(&cfg, store.as_ref(), &HashSet::new()).await
         .context("Failed to recover stuck jobs on startup")?;
-    let cleaned_count = cleanup_orphaned_temp_files(&cfg).await
+    let cleaned_count = cleanup_orphaned_temp_files(&cfg, store.as_ref()).await
         .context("Failed to cleanup orphaned temp files on startup")?;
     if recovered_count > 0 || cleaned_count > 0 {
         info!("✅ Startup recovery complete: {} job(s) recovered, {} temp file(s) cleaned", 
@@ -97,166 +122,222 @@ async fn main() -> Result<()> {
         info!("✅ Startup recovery complete: no stuck jobs or orphaned files found");
     }
 
-    // Main daemon loop
+    // Independent tasks so a long encode never blocks scanning, TUI commands or recovery:
+    // scanner creates jobs, scheduler owns the workers, command processor handles TUI
+    // requests, janitor recovers stuck jobs and cleans up temp files
+    let (scheduler_tx, scheduler_rx) = mpsc::channel(64);
+    let (active_tx, active_rx) = watch::channel(HashSet::new());
+
+    let scanner = tokio::spawn(scanner_task(cfg.clone(), Arc::clone(&store), scheduler_tx.clone()));
+    let commands = tokio::spawn(command_task(cfg.clone(), Arc::clone(&store), scheduler_tx.clone()));
+    let janitor = tokio::spawn(janitor_task(cfg.clone(), Arc::clone(&store), active_rx, scheduler_tx));
+    let scheduler = tokio::spawn(scheduler_task(cfg.clone(), store, ffmpeg_mgr, scheduler_rx, active_tx));
+
+    // Tasks run forever - if one of them stops, the daemon is in an unknown state
+    let (task_name, result) = tokio::select! {
+        r = scanner => ("scanner", r),
+        r = commands => ("command processor", r),
+        r = janitor => ("janitor", r),
+        r = scheduler => ("scheduler", r),
+    };
+    match result {
+        Ok(()) => Err(anyhow!("{} task exited unexpectedly", task_name)),
+        Err(e) => Err(anyhow!("{} task panicked: {}", task_name, e)),
+    }
+}
+
+/// Events sent to the scheduler task
+#[derive(Debug)]
+enum SchedulerEvent {
+    /// Job states changed on disk (new jobs, decisions, recoveries) - try to fill free slots
+    JobsChanged,
+    /// Stop the worker for a running job (if any) and reset the job to Pending
+    Requeue { job_id: String },
+    /// Stop the worker for a job (if any) and mark the job as cancelled
+    Cancel { job_id: String },
+    /// Pause (SIGSTOP) a running job's FFmpeg or hold a pending job
+    Pause { job_id: String },
+    /// Resume a paused job
+    Resume { job_id: String },
+    /// Stop starting new jobs and pause every running FFmpeg
+    PauseAll,
+    /// Undo `PauseAll`
+    ResumeAll,
+}
+
+/// Scanner task: periodically scan the library and create jobs for new candidates
+async fn scanner_task(cfg: TranscodeConfig, store: Arc<dyn JobStore>, scheduler_tx: mpsc::Sender<SchedulerEvent>) {
     let mut scan_count = 0u64;
     loop {
         scan_count += 1;
-        info!("Starting library scan #{}", scan_count);
-
-        let scan_results = scan::scan_library(&cfg).await
-            .context("Failed to scan library")?;
-
-        info!("Scan completed: found {} results (candidates + skipped)", scan_results.len());
-        
-        let candidates_in_results: usize = scan_results.iter()
-            .filter(|r| matches!(r, scan::ScanResult::Candidate(_, _)))
-            .count();
-        info!("Scan found {} candidates ready for processing", candidates_in_results);
-
-        // Create jobs for new candidates
-        let existing_jobs = load_all_jobs(&cfg.job_state_dir)
-            .context("Failed to load existing jobs")?;
-
-        info!("Loaded {} existing jobs", existing_jobs.len());
-
-        let existing_paths: HashSet<_> = existing_jobs
-            .iter()
-            .map(|j| &j.source_path)
-            .collect();
-
-        let mut candidates_count = 0;
-        let mut skipped_count = 0;
-        let mut new_jobs_count = 0;
-
-        for result in scan_results {
-            match result {
-                scan::ScanResult::Candidate(path, size) => {
-                    candidates_count += 1;
-                    if !existing_paths.contains(&path) {
-                        let mut job = Job::new(path.clone());
-                        job.original_bytes = Some(size);
-                        save_job(&job, &cfg.job_state_dir)
-                            .with_context(|| format!("Failed to save job for: {}", path.display()))?;
-
-                        new_jobs_count += 1;
-                        info!("Created job {} for: {} ({} bytes)", job.id, path.display(), size);
-                    } else {
-                        debug!("File already has a job: {}", path.display());
-                    }
-                }
-                scan::ScanResult::Skipped(path, reason) => {
-                    skipped_count += 1;
-                    debug!("Skipped {}: {}", path.display(), reason);
+        match scan_and_create_jobs(&cfg, &store, scan_count).await {
+            Ok(new_jobs_count) => {
+                if new_jobs_count > 0 {
+                    scheduler_tx.send(SchedulerEvent::JobsChanged).await.ok();
                 }
             }
+            Err(e) => {
+                error!("❌ Library scan #{} failed: {:#}", scan_count, e);
+            }
         }
 
-        info!("Scan summary: {} candidates, {} skipped, {} new jobs created", 
-              candidates_count, skipped_count, new_jobs_count);
+        info!("Sleeping for {} seconds before next scan", cfg.scan_interval_secs);
+        tokio::time::sleep(Duration::from_secs(cfg.scan_interval_secs)).await;
+    }
+}
 
-        // Process command files from TUI (e.g., manual requeue requests)
-        let _processed_commands = process_command_files(&cfg).await
-            .context("Failed to process command files")?;
-        
-        // Periodic stuck job check - recover any stuck jobs before processing
-        let _recovered_count = recover_stuck_jobs(&cfg).await
-            .context("Failed to check for stuck jobs")?;
-        if _recovered_count > 0 {
-            info!("⚠️  Recovered {} stuck job(s) during periodic check", _recovered_count);
-        }
-
-        // Process pending jobs
-        let mut jobs = load_all_jobs(&cfg.job_state_dir)
-            .context("Failed to load jobs")?;
-
-        let pending_count = jobs.iter().filter(|j| j.status == JobStatus::Pending).count();
-        let running_count = jobs.iter().filter(|j| j.status == JobStatus::Running).count();
-
-        // Log job counts
-        if pending_count > 0 || running_count > 0 {
-            info!("Job status: {} pending, {} running (max 1 concurrent transcoding job)", pending_count, running_count);
-        }
-
-        // Extract metadata for pending jobs in background (for EST SAVE calculation in TUI)
-        // This runs regardless of whether a job is currently transcoding
-        // Process multiple jobs in parallel (ffprobe is lightweight)
-        let pending_jobs_without_metadata: Vec<Job> = jobs.iter()
-            .filter(|j| j.status == JobStatus::Pending)
-            .filter(|j| {
-                // Check if metadata is missing
-                j.video_codec.is_none() || 
-                j.video_width.is_none() || 
-                j.video_height.is_none() || 
-                j.video_bitrate.is_none() || 
-                j.video_frame_rate.is_none()
-            })
-            .take(5) // Process up to 5 jobs per scan interval (ffprobe is lightweight)
-            .cloned()
-            .collect();
-        
-        if !pending_jobs_without_metadata.is_empty() {
-            info!("📊 Extracting metadata for {} pending job(s) in background (for EST SAVE)...", pending_jobs_without_metadata.len());
-            
-            // Spawn background tasks for each job
-            for job in pending_jobs_without_metadata {
-                let cfg_clone = cfg.clone();
-                let job_id = job.id.clone();
-                let job_path = job.source_path.clone();
-                let job_state_dir = cfg.job_state_dir.clone();
-                
-                tokio::spawn(async move {
-                    if let Err(e) = extract_metadata_for_job(&cfg_clone, &job_id, &job_path, &job_state_dir).await {
-                        warn!("Failed to extract metadata for pending job {}: {}", job_id, e);
-                    }
-                });
+/// Scan the library once, create jobs for new candidates and start background
+/// metadata extraction for pending jobs. Returns the number of jobs created.
+async fn scan_and_create_jobs(cfg: &TranscodeConfig, store: &Arc<dyn JobStore>, scan_count: u64) -> Result<usize> {
+    info!("Starting library scan #{}", scan_count);
+
+    let scan_results = scan::scan_library(cfg).await
+        .context("Failed to scan library")?;
+
+    info!("Scan completed: found {} results (candidates + skipped)", scan_results.len());
+
+    let candidates_in_results: usize = scan_results.iter()
+        .filter(|r| matches!(r, scan::ScanResult::Candidate(_, _)))
+        .count();
+    info!("Scan found {} candidates ready for processing", candidates_in_results);
+
+    // Create jobs for new candidates
+    let existing_jobs = store.load_all()
+        .context("Failed to load existing jobs")?;
+
+    info!("Loaded {} existing jobs", existing_jobs.len());
+
+    // Outputs count too: a source converted to .mkv must not get a job of its own
+    let existing_paths: HashSet<_> = existing_jobs
+        .iter()
+        .flat_map(|j| std::iter::once(&j.source_path).chain(j.output_path.as_ref()))
+        .collect();
+
+    let mut candidates_count = 0;
+    let mut skipped_count = 0;
+    let mut new_jobs_count = 0;
+
+    for result in scan_results {
+        match result {
+            scan::ScanResult::Candidate(path, size) => {
+                candidates_count += 1;
+                if !existing_paths.contains(&path) {
+                    let mut job = Job::new(path.clone());
+                    job.original_bytes = Some(size);
+                    store.save(&job)
+                        .with_context(|| format!("Failed to save job for: {}", path.display()))?;
+
+                    new_jobs_count += 1;
+                    info!("Created job {} for: {} ({} bytes)", job.id, path.display(), size);
+                } else {
+                    debug!("File already has a job: {}", path.display());
+                }
+            }
+            scan::ScanResult::Skipped(path, reason) => {
+                skipped_count += 1;
+                debug!("Skipped {}: {}", path.display(), reason);
             }
         }
+    }
 
-        // Only start a new job if no jobs are currently running
-        // This ensures only one transcoding job runs at a time (important for single GPU)
-        if running_count == 0 {
-            // Find a pending job
-            if let Some(job) = jobs.iter_mut().find(|j| j.status == JobStatus::Pending) {
-                info!("Starting transcoding job {}: {}", job.id, job.source_path.display());
-                info!("⚠️  Only one job runs at a time - GPU will be dedicated to this job");
+    info!("Scan summary: {} candidates, {} skipped, {} new jobs created", 
+          candidates_count, skipped_count, new_jobs_count);
+
+    let pending_jobs = store.find_by_status(&[JobStatus::Pending])
+        .context("Failed to load pending jobs")?;
+
+    // Extract metadata for pending jobs in background (for EST SAVE calculation in TUI)
+    // This runs regardless of whether a job is currently transcoding
+    // Process multiple jobs in parallel (ffprobe is lightweight)
+    let pending_jobs_without_metadata: Vec<Job> = pending_jobs.into_iter()
+        .filter(|j| {
+            // Check if metadata is missing
+            j.video_codec.is_none() || 
+            j.video_width.is_none() || 
+            j.video_height.is_none() || 
+            j.video_bitrate.is_none() || 
+            j.video_frame_rate.is_none()
+        })
+        .take(5) // Process up to 5 jobs per scan interval (ffprobe is lightweight)
+        .collect();
 
-                job.status = JobStatus::Running;
-                job.started_at = Some(Utc::now());
-                save_job(job, &cfg.job_state_dir)?;
+    if !pending_jobs_without_metadata.is_empty() {
+        info!("📊 Extracting metadata for {} pending job(s) in background (for EST SAVE)...", pending_jobs_without_metadata.len());
 
-                // Process the job (this will block until complete)
-                match process_job(&cfg, &ffmpeg_mgr, job).await {
-                    Ok(()) => {
-                        info!("✅ Job {} completed successfully", job.id);
-                    }
-                    Err(e) => {
-                        error!("❌ Job {} failed: {}", job.id, e);
-                        job.status = JobStatus::Failed;
-                        job.reason = Some(format!("{}", e));
-                        job.finished_at = Some(Utc::now());
-                        save_job(job, &cfg.job_state_dir)?;
-                    }
+        // Spawn background tasks for each job
+        for job in pending_jobs_without_metadata {
+            let cfg_clone = cfg.clone();
+            let store = Arc::clone(store);
+            let job_id = job.id.clone();
+            let job_path = job.source_path.clone();
+
+            tokio::spawn(async move {
+                if let Err(e) = extract_metadata_for_job(&cfg_clone, store.as_ref(), &job_id, &job_path).await {
+                    warn!("Failed to extract metadata for pending job {}: {}", job_id, e);
                 }
-            } else if pending_count == 0 {
-                debug!("No pending jobs, waiting for next scan");
+            });
+        }
+    }
+
+    Ok(new_jobs_count)
+}
+
+/// Command task: poll the command directory so TUI requests are honored within seconds,
+/// and apply the test clip approval timeout policy
+async fn command_task(cfg: TranscodeConfig, store: Arc<dyn JobStore>, scheduler_tx: mpsc::Sender<SchedulerEvent>) {
+    let poll_interval = Duration::from_secs(cfg.command_poll_interval_secs.max(1));
+    loop {
+        match process_command_files(&cfg, store.as_ref(), &scheduler_tx).await {
+            Ok(count) if count > 0 => {
+                scheduler_tx.send(SchedulerEvent::JobsChanged).await.ok();
             }
-        } else {
-            // Job is already running - wait for it to complete
-            let running_jobs: Vec<_> = jobs.iter()
-                .filter(|j| j.status == JobStatus::Running)
-                .map(|j| j.id.clone())
-                .collect();
-            info!("⏸️  Waiting for {} running job(s) to complete before starting next: {:?}", 
-                  running_count, running_jobs);
-            if pending_count > 0 {
-                info!("   {} pending job(s) will start after current job(s) finish", pending_count);
+            Ok(_) => {}
+            Err(e) => error!("❌ Failed to process command files: {:#}", e),
+        }
+
+        // Resolve test clip approvals nobody answered within the configured timeout
+        match expire_test_clip_approvals(&cfg, store.as_ref()) {
+            Ok(count) if count > 0 => {
+                info!("⏳ Applied timeout policy to {} test clip approval(s)", count);
+                scheduler_tx.send(SchedulerEvent::JobsChanged).await.ok();
+            }
+            Ok(_) => {}
+            Err(e) => error!("❌ Failed to check test clip approval timeouts: {:#}", e),
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+/// Janitor task: periodic stuck job recovery and orphaned temp file cleanup
+async fn janitor_task(
+    cfg: TranscodeConfig,
+    store: Arc<dyn JobStore>,
+    active_rx: watch::Receiver<HashSet<String>>,
+    scheduler_tx: mpsc::Sender<SchedulerEvent>,
+) {
+    let interval = Duration::from_secs(cfg.janitor_interval_secs.max(1));
+    let mut run_count = 0u64;
+    loop {
+        // Startup recovery already ran in main - wait one interval first
+        tokio::time::sleep(interval).await;
+        run_count += 1;
+
+        // Jobs owned by a live worker are never treated as stuck
+        let active_jobs = active_rx.borrow().clone();
+        match recover_stuck_jobs(&cfg, store.as_ref(), &active_jobs).await {
+            Ok(count) if count > 0 => {
+                info!("⚠️  Recovered {} stuck job(s) during periodic check", count);
+                scheduler_tx.send(SchedulerEvent::JobsChanged).await.ok();
             }
+            Ok(_) => {}
+            Err(e) => error!("❌ Failed to check for stuck jobs: {:#}", e),
         }
 
-        // Periodic cleanup of orphaned temp files (every 10 scans = ~10 minutes)
-        if scan_count % 10 == 0 {
+        // Periodic cleanup of orphaned temp files (every 10 janitor runs)
+        if run_count.is_multiple_of(10) {
             info!("🧹 Running periodic temp file cleanup...");
-            match cleanup_orphaned_temp_files(&cfg).await {
+            match cleanup_orphaned_temp_files(&cfg, store.as_ref()).await {
                 Ok(count) => {
                     if count > 0 {
                         info!("✅ Periodic cleanup: removed {} orphaned temp file(s)", count);
@@ -269,11 +350,563 @@ async fn main() -> Result<()> {
                 }
             }
         }
+    }
+}
 
-        // Sleep before next scan
-        info!("Sleeping for {} seconds before next scan", cfg.scan_interval_secs);
-        tokio::time::sleep(tokio::time::Duration::from_secs(cfg.scan_interval_secs)).await;
+/// Scheduler task: owns the worker pool, starts pending jobs when slots are free
+/// and stops workers for requeued jobs
+async fn scheduler_task(
+    cfg: TranscodeConfig,
+    store: Arc<dyn JobStore>,
+    ffmpeg_mgr: Arc<FFmpegManager>,
+    mut events: mpsc::Receiver<SchedulerEvent>,
+    active_tx: watch::Sender<HashSet<String>>,
+) {
+    let mut workers = WorkerPool::new(Arc::clone(&store));
+    // Safety net in case a job file changed without an event (e.g. edited by hand);
+    // with an encoding schedule or load limits also wake up often enough to follow them
+    let mut idle_secs = cfg.scan_interval_secs.max(1);
+    if cfg.schedule.is_restricted() {
+        idle_secs = idle_secs.min(60);
+    }
+    if cfg.load_throttle.is_enabled() {
+        idle_secs = idle_secs.min(cfg.load_throttle.check_interval_secs.max(1));
+    }
+    let idle_interval = Duration::from_secs(idle_secs);
+    let mut host_load = cfg.load_throttle.is_enabled().then(HostLoad::new);
+
+    // Pause settings survive restarts: daemon-wide in the control file, per job on the job
+    let mut daemon_state = control::load_daemon_state(&cfg.job_state_dir);
+    // Load is re-measured below; don't carry over a busy host from the last run
+    daemon_state.busy_reason = None;
+    if daemon_state.paused {
+        info!("⏸️  Daemon is paused - no jobs will start until resumed");
+    }
+    ffmpeg_mgr.set_all_paused(daemon_state.paused);
+    match store.find_by_status(&UNFINISHED_STATUSES) {
+        Ok(jobs) => {
+            for job in jobs.iter().filter(|j| j.paused) {
+                ffmpeg_mgr.set_job_paused(&job.id, true);
+            }
+        }
+        Err(e) => error!("❌ Failed to load paused jobs: {:#}", e),
+    }
+
+    loop {
+        workers.reap_finished();
+        update_schedule_window(&cfg, &ffmpeg_mgr, &mut daemon_state);
+        if let Some(host_load) = host_load.as_mut() {
+            check_host_load(&cfg, &ffmpeg_mgr, host_load, &mut daemon_state);
+        }
+        let hold_new_jobs = daemon_state.paused
+            || daemon_state.outside_window
+            || daemon_state.busy_reason.is_some();
+        if let Err(e) = schedule_pending_jobs(&cfg, store.as_ref(), &ffmpeg_mgr, &mut workers, hold_new_jobs) {
+            error!("❌ Failed to schedule pending jobs: {:#}", e);
+        }
+        active_tx.send_replace(workers.active_job_ids());
+
+        tokio::select! {
+            event = events.recv() => match event {
+                Some(SchedulerEvent::JobsChanged) => {}
+                Some(SchedulerEvent::Requeue { job_id }) => {
+                    if workers.stop_job(&ffmpeg_mgr, &job_id).await {
+                        info!("Job {}: Worker stopped for requeue", job_id);
+                    }
+                    ffmpeg_mgr.set_job_paused(&job_id, false);
+                    active_tx.send_replace(workers.active_job_ids());
+                    if let Err(e) = force_requeue_job_by_id(&cfg, store.as_ref(), &job_id).await {
+                        error!("Job {}: ❌ Failed to requeue: {:#}", job_id, e);
+                    }
+                }
+                Some(SchedulerEvent::Cancel { job_id }) => {
+                    if workers.stop_job(&ffmpeg_mgr, &job_id).await {
+                        info!("Job {}: Worker stopped for cancel", job_id);
+                    }
+                    ffmpeg_mgr.set_job_paused(&job_id, false);
+                    active_tx.send_replace(workers.active_job_ids());
+                    if let Err(e) = cancel_job_by_id(&cfg, store.as_ref(), &job_id) {
+                        error!("Job {}: ❌ Failed to cancel: {:#}", job_id, e);
+                    }
+                }
+                Some(SchedulerEvent::Pause { job_id }) => {
+                    if let Err(e) = set_job_paused_by_id(store.as_ref(), &ffmpeg_mgr, &job_id, true) {
+                        error!("Job {}: ❌ Failed to pause: {:#}", job_id, e);
+                    }
+                }
+                Some(SchedulerEvent::Resume { job_id }) => {
+                    if let Err(e) = set_job_paused_by_id(store.as_ref(), &ffmpeg_mgr, &job_id, false) {
+                        error!("Job {}: ❌ Failed to resume: {:#}", job_id, e);
+                    }
+                }
+                Some(SchedulerEvent::PauseAll) => {
+                    if let Err(e) = set_daemon_paused(&cfg, &ffmpeg_mgr, &mut daemon_state, true) {
+                        error!("❌ Failed to pause daemon: {:#}", e);
+                    }
+                }
+                Some(SchedulerEvent::ResumeAll) => {
+                    if let Err(e) = set_daemon_paused(&cfg, &ffmpeg_mgr, &mut daemon_state, false) {
+                        error!("❌ Failed to resume daemon: {:#}", e);
+                    }
+                }
+                None => {
+                    warn!("Scheduler event channel closed");
+                    return;
+                }
+            },
+            Some(result) = workers.tasks.join_next_with_id() => {
+                workers.handle_finished(result);
+            }
+            _ = tokio::time::sleep(idle_interval) => {}
+        }
+    }
+}
+
+/// Pause or resume the daemon as a whole and remember it across restarts
+fn set_daemon_paused(
+    cfg: &TranscodeConfig,
+    ffmpeg_mgr: &FFmpegManager,
+    daemon_state: &mut control::DaemonState,
+    paused: bool,
+) -> Result<()> {
+    if daemon_state.paused == paused {
+        info!("Daemon is already {}", if paused { "paused" } else { "running" });
+        return Ok(());
+    }
+
+    daemon_state.paused = paused;
+    daemon_state.paused_at = paused.then(Utc::now);
+    control::save_daemon_state(&cfg.job_state_dir, daemon_state)?;
+    ffmpeg_mgr.set_all_paused(paused);
+
+    if paused {
+        info!("⏸️  Daemon paused - running encodes stopped, no new jobs will start");
+    } else {
+        info!("▶️  Daemon resumed");
+    }
+    Ok(())
+}
+
+/// Follow the encoding schedule: note when the window opens or closes and
+/// stop/continue running encodes if `pause_outside_windows` is set
+fn update_schedule_window(
+    cfg: &TranscodeConfig,
+    ffmpeg_mgr: &FFmpegManager,
+    daemon_state: &mut control::DaemonState,
+) {
+    let outside = !cfg.schedule.is_open_now();
+    ffmpeg_mgr.set_schedule_paused(outside && cfg.schedule.pause_outside_windows);
+
+    if outside == daemon_state.outside_window {
+        return;
+    }
+    daemon_state.outside_window = outside;
+    if outside {
+        info!("🌙 Outside encoding window - no new jobs will start{}",
+              if cfg.schedule.pause_outside_windows { ", running encodes paused" } else { "" });
+    } else {
+        info!("☀️  Encoding window open");
+    }
+
+    // Let the TUI show why nothing is starting
+    if let Err(e) = control::save_daemon_state(&cfg.job_state_dir, daemon_state) {
+        error!("❌ Failed to save daemon state: {:#}", e);
+    }
+}
+
+/// Host load tracking for the scheduler (see `LoadThrottle`)
+struct HostLoad {
+    monitor: LoadMonitor,
+    next_check: std::time::Instant,
+    /// Running FFmpeg processes currently have lowered priority
+    throttled: bool,
+    /// Priority FFmpeg runs with normally (inherited from the daemon)
+    normal_priority: ProcessPriority,
+}
+
+impl HostLoad {
+    fn new() -> Self {
+        Self {
+            monitor: LoadMonitor::new(),
+            next_check: std::time::Instant::now(),
+            throttled: false,
+            normal_priority: ProcessPriority::inherited(),
+        }
+    }
+}
+
+/// Sample host load when a check is due: hold back new jobs while the host is busy
+/// and lower FFmpeg priority while other processes need the CPU
+fn check_host_load(
+    cfg: &TranscodeConfig,
+    ffmpeg_mgr: &FFmpegManager,
+    host_load: &mut HostLoad,
+    daemon_state: &mut control::DaemonState,
+) {
+    let now = std::time::Instant::now();
+    if now < host_load.next_check {
+        return;
+    }
+    host_load.next_check = now + Duration::from_secs(cfg.load_throttle.check_interval_secs.max(1));
+
+    let own_pids: HashSet<u32> = ffmpeg_mgr.running_pids().into_iter().collect();
+    let sample = host_load.monitor.sample(&own_pids);
+    let verdict = cfg.load_throttle.evaluate(&sample);
+    debug!("Host load: {:.2}/core, {} MB available, {:.0}% CPU used by other processes",
+           sample.load_per_core, sample.available_memory_mb, sample.foreign_cpu_percent);
+
+    let busy_reason = (!verdict.defer_reasons.is_empty()).then(|| verdict.defer_reasons.join(", "));
+    match (&busy_reason, daemon_state.busy_reason.is_some()) {
+        (Some(reason), false) => info!("🔥 Host busy ({}) - deferring new jobs", reason),
+        (None, true) => info!("✅ Host load back to normal"),
+        _ => {}
+    }
+    if busy_reason != daemon_state.busy_reason {
+        daemon_state.busy_reason = busy_reason;
+        // Let the TUI show why nothing is starting
+        if let Err(e) = control::save_daemon_state(&cfg.job_state_dir, daemon_state) {
+            error!("❌ Failed to save daemon state: {:#}", e);
+        }
+    }
+
+    // While busy, re-apply every check so FFmpeg started since the last one is covered
+    if !verdict.throttle_running && !host_load.throttled {
+        return;
+    }
+    let changed = verdict.throttle_running != host_load.throttled;
+    let priority = if verdict.throttle_running {
+        ProcessPriority { nice: cfg.load_throttle.busy_nice, io_idle: cfg.load_throttle.busy_ionice_idle }
+    } else {
+        host_load.normal_priority
+    };
+    if changed {
+        info!("{} FFmpeg priority: nice {}{}",
+              if verdict.throttle_running { "🐢 Lowering" } else { "🐇 Restoring" },
+              priority.nice, if priority.io_idle { ", idle I/O" } else { "" });
+    }
+    for pid in &own_pids {
+        if let Err(e) = load::set_process_priority(*pid, priority) {
+            // Restoring priority needs CAP_SYS_NICE when the daemon isn't root
+            if changed {
+                warn!("{:#}", e);
+            } else {
+                debug!("{:#}", e);
+            }
+        }
+    }
+    host_load.throttled = verdict.throttle_running;
+}
+
+/// Pause or resume a single job
+/// A running job's FFmpeg is stopped/continued; a pending job is held back from scheduling
+fn set_job_paused_by_id(store: &dyn JobStore, ffmpeg_mgr: &FFmpegManager, job_id: &str, paused: bool) -> Result<()> {
+    let job = store.get(job_id)
+        .context("Failed to load job for pause")?;
+    let Some(mut job) = job else {
+        warn!("Job {}: Not found for pause/resume", job_id);
+        return Ok(());
+    };
+    if !is_unfinished(&job.status) {
+        info!("Job {}: Not {} - status is already {:?}",
+              job.id, if paused { "paused" } else { "resumed" }, job.status);
+        return Ok(());
+    }
+
+    let signalled = ffmpeg_mgr.set_job_paused(&job.id, paused);
+    match (paused, signalled) {
+        (true, true) => info!("Job {}: ⏸️  FFmpeg stopped", job.id),
+        (true, false) => info!("Job {}: ⏸️  Paused - will not encode until resumed", job.id),
+        (false, true) => info!("Job {}: ▶️  FFmpeg continued", job.id),
+        (false, false) => info!("Job {}: ▶️  Resumed", job.id),
+    }
+
+    job.paused = paused;
+    store.save(&job)?;
+    Ok(())
+}
+
+/// Start pending jobs until every worker slot is in use
+/// Paused jobs are held back, and nothing starts while `hold_new_jobs` is set
+/// (daemon paused or outside the encoding schedule)
+fn schedule_pending_jobs(
+    cfg: &TranscodeConfig,
+    store: &dyn JobStore,
+    ffmpeg_mgr: &Arc<FFmpegManager>,
+    workers: &mut WorkerPool,
+    hold_new_jobs: bool,
+) -> Result<()> {
+    let mut jobs = store.find_by_status(&UNFINISHED_STATUSES)
+        .context("Failed to load unfinished jobs")?;
+
+    // The FFmpeg manager holds the pause settings; a worker saving its own copy of a
+    // job can drop the flag, so restore it once the worker is done with the job
+    let active_ids = workers.active_job_ids();
+    for job in jobs.iter_mut().filter(|j| !active_ids.contains(&j.id)) {
+        let paused = ffmpeg_mgr.is_job_paused(&job.id);
+        if job.paused != paused {
+            job.paused = paused;
+            store.save(job)?;
+        }
+    }
+
+    if hold_new_jobs {
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let is_startable = |j: &Job| j.status == JobStatus::Pending && !j.paused && j.is_retry_due(now);
+    let pending_count = jobs.iter().filter(|j| is_startable(j)).count();
+    let running_count = workers.len();
+    let max_jobs = cfg.max_concurrent_jobs();
+
+    if pending_count > 0 || running_count > 0 {
+        debug!("Job status: {} pending, {} running (max {} concurrent transcoding job(s))",
+               pending_count, running_count, max_jobs);
+    }
+
+    let free_slots = max_jobs.saturating_sub(running_count);
+    if free_slots == 0 {
+        if pending_count > 0 {
+            debug!("All {} worker slot(s) busy, {} pending job(s) waiting", max_jobs, pending_count);
+        }
+        return Ok(());
+    }
+
+    let mut started = 0;
+    for job in jobs.iter_mut().filter(|j| is_startable(j)) {
+        if started == free_slots {
+            break;
+        }
+
+        // Two sources with the same file name share a temp path - run them one at a time
+        let temp_output = cfg.temp_output_path(&job.source_path);
+        if workers.is_temp_output_in_use(&temp_output) {
+            debug!("Job {}: Temp output {} in use by another job, deferring",
+                   job.id, temp_output.display());
+            continue;
+        }
+
+        info!("Starting transcoding job {}: {}", job.id, job.source_path.display());
+
+        job.status = JobStatus::Running;
+        job.started_at = Some(Utc::now());
+        store.save(job)?;
+
+        workers.spawn(cfg, ffmpeg_mgr, job.clone(), temp_output);
+        started += 1;
+    }
+
+    Ok(())
+}
+
+/// Job currently owned by a worker task
+#[derive(Debug, Clone)]
+struct ActiveJob {
+    job_id: String,
+    temp_output: PathBuf,
+    abort_handle: tokio::task::AbortHandle,
+}
+
+/// Pool of worker tasks running `process_job` concurrently
+struct WorkerPool {
+    tasks: JoinSet<()>,
+    active: HashMap<tokio::task::Id, ActiveJob>,
+    store: Arc<dyn JobStore>,
+}
+
+impl WorkerPool {
+    fn new(store: Arc<dyn JobStore>) -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            active: HashMap::new(),
+            store,
+        }
+    }
+
+    /// Number of jobs currently being processed
+    fn len(&self) -> usize {
+        self.active.len()
+    }
+
+    /// IDs of jobs currently owned by a worker
+    fn active_job_ids(&self) -> HashSet<String> {
+        self.active.values().map(|a| a.job_id.clone()).collect()
+    }
+
+    fn is_temp_output_in_use(&self, temp_output: &Path) -> bool {
+        self.active.values().any(|a| a.temp_output == temp_output)
+    }
+
+    /// Spawn a worker for a job that has already been marked Running
+    fn spawn(&mut self, cfg: &TranscodeConfig, ffmpeg_mgr: &Arc<FFmpegManager>, job: Job, temp_output: PathBuf) {
+        let job_id = job.id.clone();
+        let cfg = cfg.clone();
+        let ffmpeg_mgr = Arc::clone(ffmpeg_mgr);
+
+        let store = Arc::clone(&self.store);
+
+        let abort_handle = self.tasks.spawn(run_job_worker(cfg, store, ffmpeg_mgr, job));
+        self.active.insert(abort_handle.id(), ActiveJob { job_id, temp_output, abort_handle });
+    }
+
+    /// Collect every worker that has already finished (non-blocking)
+    fn reap_finished(&mut self) {
+        while let Some(result) = self.tasks.try_join_next_with_id() {
+            self.handle_finished(result);
+        }
+    }
+
+    fn task_for_job(&self, job_id: &str) -> Option<tokio::task::Id> {
+        self.active.iter()
+            .find(|(_, a)| a.job_id == job_id)
+            .map(|(id, _)| *id)
+    }
+
+    /// Stop the worker for a job and wait until it has stopped
+    /// 
+    /// FFmpeg is cancelled gracefully first (SIGINT, then SIGKILL after the grace
+    /// period) so the worker can return on its own; the task is aborted if it has no
+    /// FFmpeg running (probing, test clip) or doesn't return in time.
+    /// Returns false if no worker owns the job
+    async fn stop_job(&mut self, ffmpeg_mgr: &FFmpegManager, job_id: &str) -> bool {
+        let Some(task_id) = self.task_for_job(job_id) else {
+            return false;
+        };
+
+        if ffmpeg_mgr.cancel_job(job_id) {
+            info!("Job {}: Cancelling FFmpeg (grace period {}s)", job_id, ffmpeg_mgr.cancel_grace().as_secs());
+            let limit = ffmpeg_mgr.cancel_grace() + Duration::from_secs(10);
+            if tokio::time::timeout(limit, self.wait_for_task(task_id)).await.is_ok() {
+                return true;
+            }
+            warn!("Job {}: Worker did not return after FFmpeg was cancelled, aborting it", job_id);
+        }
+
+        self.abort_job(job_id).await
+    }
+
+    /// Abort the worker for a job and wait until it has stopped
+    /// Returns false if no worker owns the job
+    async fn abort_job(&mut self, job_id: &str) -> bool {
+        let Some(task_id) = self.task_for_job(job_id) else {
+            return false;
+        };
+
+        // Forget the job first so the cancellation isn't recorded as a worker failure
+        if let Some(active) = self.active.remove(&task_id) {
+            active.abort_handle.abort();
+        }
+
+        self.wait_for_task(task_id).await;
+        true
+    }
+
+    /// Wait for a specific worker to finish, handling any others that finish first
+    /// The awaited worker's outcome is not recorded - the caller decides what happens to its job
+    async fn wait_for_task(&mut self, task_id: tokio::task::Id) {
+        while let Some(result) = self.tasks.join_next_with_id().await {
+            let finished_id = match &result {
+                Ok((id, ())) => *id,
+                Err(e) => e.id(),
+            };
+            if finished_id == task_id {
+                self.active.remove(&task_id);
+                break;
+            }
+            self.handle_finished(result);
+        }
+    }
+
+    fn handle_finished(&mut self, result: Result<(tokio::task::Id, ()), tokio::task::JoinError>) {
+        let task_id = match &result {
+            Ok((id, ())) => *id,
+            Err(e) => e.id(),
+        };
+        let Some(active) = self.active.remove(&task_id) else {
+            return;
+        };
+
+        if let Err(e) = result {
+            // The worker died without recording an outcome - don't leave the job Running
+            error!("❌ Worker for job {} terminated abnormally: {}", active.job_id, e);
+            if let Err(save_err) = mark_job_failed_by_id(self.store.as_ref(), &active.job_id, &format!("worker terminated: {}", e)) {
+                error!("Job {}: Failed to record worker failure: {}", active.job_id, save_err);
+            }
+        }
+    }
+}
+
+/// Worker task body: process one job as a new attempt, record unexpected errors on it
+/// and schedule a retry if the retry policy allows one
+async fn run_job_worker(cfg: TranscodeConfig, store: Arc<dyn JobStore>, ffmpeg_mgr: Arc<FFmpegManager>, mut job: Job) {
+    job.begin_attempt();
+    let result = process_job(&cfg, store.as_ref(), &ffmpeg_mgr, &mut job).await;
+    // Live progress is only meaningful while the job is running
+    progress::remove_progress(&cfg.job_state_dir, &job.id);
+
+    match result {
+        Ok(()) if job.status == JobStatus::AwaitingApproval => {
+            info!("⏳ Job {} is waiting for test clip approval", job.id);
+        }
+        Ok(()) if job.status == JobStatus::Running => {
+            // FFmpeg was cancelled - the scheduler requeues or cancels the job and closes the attempt
+            info!("⏹️  Job {} stopped", job.id);
+            return;
+        }
+        Ok(()) if job.status == JobStatus::Failed => {
+            // process_job already logged and recorded the failure
+        }
+        Ok(()) => {
+            info!("✅ Job {} completed successfully", job.id);
+        }
+        Err(e) => {
+            error!("❌ Job {} failed: {}", job.id, e);
+            job.fail(JobFailure::from_error(&e));
+        }
+    }
+
+    job.finish_attempt(job.status, job.reason.clone());
+    if job.status == JobStatus::Failed {
+        schedule_retry(&cfg, &mut job);
+    }
+    if let Err(save_err) = store.save(&job) {
+        error!("Job {}: Failed to save attempt outcome: {}", job.id, save_err);
+    }
+}
+
+/// Put a failed job back in the queue if the retry policy allows another attempt
+fn schedule_retry(cfg: &TranscodeConfig, job: &mut Job) {
+    let Some(kind) = job.failure_kind() else {
+        return;
+    };
+    let failed_attempts = job.failed_attempts();
+    let Some(delay) = cfg.retry.retry_delay(kind, failed_attempts) else {
+        if failed_attempts > 1 {
+            warn!("Job {}: Giving up after {} failed attempt(s)", job.id, failed_attempts);
+        }
+        return;
+    };
+
+    let retry_at = Utc::now() + delay;
+    info!("Job {}: 🔁 {} failure - retrying in {} (attempt {}/{})",
+          job.id, kind.label(), format_duration(delay), failed_attempts + 1, cfg.retry.max_attempts);
+    job.status = JobStatus::Pending;
+    job.retry_at = Some(retry_at);
+    job.started_at = None;
+    job.finished_at = None;
+    job.reason = Some(format!("retry {}/{} after {} failure: {}",
+                              failed_attempts + 1, cfg.retry.max_attempts, kind.label(),
+                              job.reason.as_deref().unwrap_or("unknown error")));
+}
+
+/// Mark a job as Failed by ID (used when its worker could not record the outcome itself)
+fn mark_job_failed_by_id(store: &dyn JobStore, job_id: &str, reason: &str) -> Result<()> {
+    if let Some(mut job) = store.get(job_id).context("Failed to load job")? {
+        job.fail(JobFailure::Other { message: reason.to_string() });
+        job.finish_attempt(job.status, job.reason.clone());
+        store.save(&job)?;
     }
+    Ok(())
 }
 
 /// Track progress state for stuck job detection
@@ -291,7 +924,7 @@ fn check_file_activity_with_state(
     job: &Job,
     progress_state: &mut HashMap<String, JobProgressState>
 ) -> Result<(bool, u64, Option<u64>)> {
-    let temp_output = get_temp_output_path(cfg, &job.source_path);
+    let temp_output = cfg.temp_output_path(&job.source_path);
     
     if !temp_output.exists() {
         return Ok((false, 0, None));
@@ -338,10 +971,10 @@ fn check_file_activity_with_state(
 /// Recover stuck jobs - check for jobs in Running status that are actually abandoned
 /// Uses advanced multi-signal detection: process existence, file activity, time-based
 /// Returns the number of jobs recovered
-async fn recover_stuck_jobs(cfg: &TranscodeConfig) -> Result<usize> {
+async fn recover_stuck_jobs(cfg: &TranscodeConfig, store: &dyn JobStore, active_jobs: &HashSet<String>) -> Result<usize> {
     info!("🔍 Checking for stuck jobs (advanced multi-signal detection)...");
     
-    let jobs = load_all_jobs(&cfg.job_state_dir)
+    let jobs = store.find_by_status(&[JobStatus::Running])
         .context("Failed to load jobs for recovery")?;
     
     let stuck_timeout = chrono::Duration::seconds(cfg.stuck_job_timeout_secs as i64);
@@ -353,7 +986,8 @@ async fn recover_stuck_jobs(cfg: &TranscodeConfig) -> Result<usize> {
     let mut progress_state = HashMap::<String, JobProgressState>::new();
     
     for mut job in jobs {
-        if job.status != JobStatus::Running {
+        // A live worker owns this job - long encodes are expected, not stuck
+        if active_jobs.contains(&job.id) {
             continue;
         }
         
@@ -366,7 +1000,7 @@ async fn recover_stuck_jobs(cfg: &TranscodeConfig) -> Result<usize> {
         } else {
             // Job has Running status but no started_at - definitely stuck
             stuck_reasons.push("no started_at timestamp".to_string());
-            recover_job_safely(cfg, &mut job, now, &stuck_reasons.join(", "), &mut progress_state)?;
+            recover_job_safely(cfg, store, &mut job, now, &stuck_reasons.join(", "), &mut progress_state)?;
             recovered_count += 1;
             continue;
         };
@@ -416,7 +1050,7 @@ async fn recover_stuck_jobs(cfg: &TranscodeConfig) -> Result<usize> {
         // If job is stuck, recover it
         if is_stuck {
             warn!("Job {}: ⚠️  Found stuck job - {}", job.id, stuck_reasons.join("; "));
-            recover_job_safely(cfg, &mut job, now, &stuck_reasons.join(", "), &mut progress_state)?;
+            recover_job_safely(cfg, store, &mut job, now, &stuck_reasons.join(", "), &mut progress_state)?;
             recovered_count += 1;
         }
     }
@@ -433,13 +1067,14 @@ async fn recover_stuck_jobs(cfg: &TranscodeConfig) -> Result<usize> {
 /// Helper function to safely recover a stuck job (cleanup and reset)
 fn recover_job_safely(
     cfg: &TranscodeConfig, 
+    store: &dyn JobStore,
     job: &mut Job, 
     now: DateTime<Utc>, 
     reason: &str,
     progress_state: &mut HashMap<String, JobProgressState>
 ) -> Result<()> {
-    let temp_output = get_temp_output_path(cfg, &job.source_path);
-    let orig_backup = job.source_path.with_extension("orig.mkv");
+    let temp_output = cfg.temp_output_path(&job.source_path);
+    let orig_backup = container::backup_path(&job.source_path);
     
     // Clean up temp file if it exists (abandoned transcode)
     if temp_output.exists() {
@@ -458,12 +1093,19 @@ fn recover_job_safely(
             .with_context(|| format!("Failed to restore backup: {} -> {}", 
                 orig_backup.display(), job.source_path.display()))?;
         info!("Job {}: 🔄 Restored original from backup: {}", job.id, job.source_path.display());
+        // A conversion to MKV may have left a (possibly partial) output under the new name
+        let output_plan = cfg.output_plan(&job.source_path);
+        if output_plan.renames_source(&job.source_path) && output_plan.final_path.exists() {
+            fs::remove_file(&output_plan.final_path)
+                .with_context(|| format!("Failed to delete partial output: {}", output_plan.final_path.display()))?;
+            info!("Job {}: 🗑️  Deleted partial output: {}", job.id, output_plan.final_path.display());
+        }
         job.status = JobStatus::Pending;
         job.started_at = None;
     } else if !orig_exists && !backup_exists {
         // Original missing, no backup - corrupted state
         error!("Job {}: ❌ Original file missing with no backup - marking as Failed", job.id);
-        job.status = JobStatus::Failed;
+        job.fail(JobFailure::SourceVanished { path: job.source_path.clone() });
         job.reason = Some(format!("recovery: {} (original file missing, no backup)", reason));
         job.finished_at = Some(now);
     } else if orig_exists {
@@ -472,16 +1114,19 @@ fn recover_job_safely(
         job.started_at = None;
     }
     
+    job.finish_attempt(job.status, Some(format!("recovery: {}", reason)));
+
     // Clear reason if resetting to Pending
     if job.status == JobStatus::Pending {
         job.reason = None;
     }
     
-    save_job(job, &cfg.job_state_dir)?;
+    store.save(job)?;
     info!("Job {}: 🔄 Recovered stuck job - reset to {:?}", job.id, job.status);
     
     // Clean up progress state
     progress_state.remove(&job.id);
+    progress::remove_progress(&cfg.job_state_dir, &job.id);
     
     Ok(())
 }
@@ -494,27 +1139,20 @@ fn format_duration(d: chrono::Duration) -> String {
     format!("{}h {}m {}s", hours, minutes, seconds)
 }
 
-/// Force requeue a job - stop container, clean files, reset to Pending
-/// Performs safe cleanup with proper error handling
-async fn force_requeue_job(cfg: &TranscodeConfig, job: &mut Job) -> Result<()> {
+/// Remove a stopped job's temp output and put the original back in place
+/// The job's FFmpeg must already have been stopped (see `WorkerPool::stop_job`)
+fn cleanup_stopped_job_files(cfg: &TranscodeConfig, job: &Job) -> Result<()> {
+    let temp_output = cfg.temp_output_path(&job.source_path);
+    let orig_backup = container::backup_path(&job.source_path);
     
-    info!("🔄 Force requeue requested for job {}: {}", job.id, job.source_path.display());
-    
-    let temp_output = get_temp_output_path(cfg, &job.source_path);
-    let orig_backup = job.source_path.with_extension("orig.mkv");
-    
-    // Step 1: No Docker containers to stop (native FFmpeg runs as direct subprocess)
-    // If a job is stuck, the subprocess should have already terminated or will be orphaned
-    info!("Job {}: Native FFmpeg mode - no containers to stop", job.id);
-    
-    // Step 2: Clean up temp file
+    // Clean up temp file
     if temp_output.exists() {
         fs::remove_file(&temp_output)
             .with_context(|| format!("Failed to delete temp file: {}", temp_output.display()))?;
         info!("Job {}: 🗑️  Deleted temp file: {}", job.id, temp_output.display());
     }
     
-    // Step 3: Restore original file if needed
+    // Restore original file if needed
     if !job.source_path.exists() && orig_backup.exists() {
         fs::rename(&orig_backup, &job.source_path)
             .with_context(|| format!("Failed to restore backup: {} -> {}", 
@@ -527,32 +1165,206 @@ async fn force_requeue_job(cfg: &TranscodeConfig, job: &mut Job) -> Result<()> {
         info!("Job {}: 🗑️  Deleted backup file (original exists): {}", job.id, orig_backup.display());
     }
     
-    // Step 4: Reset job state
+    progress::remove_progress(&cfg.job_state_dir, &job.id);
+    Ok(())
+}
+
+/// Force requeue a job - clean files, reset to Pending
+/// Performs safe cleanup with proper error handling
+async fn force_requeue_job(cfg: &TranscodeConfig, store: &dyn JobStore, job: &mut Job) -> Result<()> {
+    
+    info!("🔄 Force requeue requested for job {}: {}", job.id, job.source_path.display());
+    
+    // Step 1: Clean up temp file and restore the original if needed
+    cleanup_stopped_job_files(cfg, job)?;
+    
+    // Step 2: Reset job state
+    job.finish_attempt(JobStatus::Pending, Some("requeued by user".to_string()));
     job.status = JobStatus::Pending;
     job.started_at = None;
     job.finished_at = None;
     job.reason = None;
     job.output_path = None;
     job.new_bytes = None;
+    job.paused = false;
+    job.retry_at = None;
+    job.failure = None;
     
-    // Step 5: Save job state
-    save_job(job, &cfg.job_state_dir)?;
+    // Step 3: Save job state
+    store.save(job)?;
     info!("Job {}: ✅ Force requeue complete - job reset to Pending", job.id);
     
     Ok(())
 }
 
+/// Cancel a job - clean files and mark it Failed so it is not picked up again
+fn cancel_job(cfg: &TranscodeConfig, store: &dyn JobStore, job: &mut Job) -> Result<()> {
+    info!("⏹️  Cancel requested for job {}: {}", job.id, job.source_path.display());
+    
+    cleanup_stopped_job_files(cfg, job)?;
+    TestClipWorkflow::cleanup_clip_files(
+        job.test_clip_path.as_deref(),
+        job.test_clip_encoded_path.as_deref(),
+    );
+    job.test_clip_path = None;
+    job.test_clip_encoded_path = None;
+    job.approval_requested_at = None;
+    
+    job.fail(JobFailure::Cancelled);
+    job.output_path = None;
+    job.new_bytes = None;
+    job.paused = false;
+    job.retry_at = None;
+    job.finish_attempt(job.status, job.reason.clone());
+    
+    store.save(job)?;
+    info!("Job {}: ✅ Cancel complete - job marked as Failed", job.id);
+    
+    Ok(())
+}
+
 /// Command file format for TUI communication
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
 struct CommandFile {
     action: String,
+    /// Target job (not used by daemon-wide actions such as pause_all)
     job_id: String,
     reason: Option<String>,
     timestamp: String,
+    /// Step size for lower_crf / slower_preset (defaults apply when absent)
+    #[serde(default)]
+    amount: Option<u8>,
+}
+
+/// Apply a test clip decision to a job that is awaiting approval
+/// `origin` describes who made the decision and is used in logs and reasons
+fn apply_approval_decision(
+    store: &dyn JobStore,
+    job: &mut Job,
+    decision: ApprovalDecision,
+    origin: &str,
+) -> Result<()> {
+    info!("Job {}: Test clip decision {:?} ({})", job.id, decision, origin);
+    job.approval_requested_at = None;
+    job.test_clip_decisions.push(decision.clone());
+
+    match decision {
+        ApprovalDecision::Approved => {
+            // Full encode runs with the approved parameters on the next pickup
+            job.test_clip_approved = Some(true);
+            job.status = JobStatus::Pending;
+        }
+        ApprovalDecision::LowerCrf(_) | ApprovalDecision::SlowerPreset(_) => {
+            // Back to the queue - process_job re-encodes the clip with adjusted parameters
+            job.test_clip_approved = None;
+            job.status = JobStatus::Pending;
+        }
+        ApprovalDecision::Rejected => {
+            TestClipWorkflow::cleanup_clip_files(
+                job.test_clip_path.as_deref(),
+                job.test_clip_encoded_path.as_deref(),
+            );
+            job.test_clip_path = None;
+            job.test_clip_encoded_path = None;
+            job.test_clip_approved = Some(false);
+
+            let reason = format!("test clip rejected ({})", origin);
+            sidecar::write_why_txt(&job.source_path, &reason)?;
+            sidecar::write_skip_marker(&job.source_path)?;
+            job.status = JobStatus::Skipped;
+            job.reason = Some(reason);
+            job.finished_at = Some(Utc::now());
+        }
+    }
+
+    store.save(job)?;
+    Ok(())
+}
+
+/// Apply the configured timeout policy to jobs waiting too long for test clip approval
+/// Returns the number of jobs resolved
+fn expire_test_clip_approvals(cfg: &TranscodeConfig, store: &dyn JobStore) -> Result<usize> {
+    if cfg.test_clip_approval_timeout_secs == 0 {
+        return Ok(0);
+    }
+
+    let timeout = chrono::Duration::seconds(cfg.test_clip_approval_timeout_secs as i64);
+    let now = Utc::now();
+    let mut expired_count = 0;
+
+    let jobs = store.find_by_status(&[JobStatus::AwaitingApproval])
+        .context("Failed to load jobs for approval timeout check")?;
+
+    for mut job in jobs {
+        let requested_at = match job.approval_requested_at {
+            Some(t) => t,
+            None => {
+                // Parked without a timestamp (e.g. edited by hand) - start the clock now
+                job.approval_requested_at = Some(now);
+                store.save(&job)?;
+                continue;
+            }
+        };
+
+        let waited = now.signed_duration_since(requested_at);
+        if waited < timeout {
+            continue;
+        }
+
+        warn!("Job {}: Test clip approval timed out after {} - applying {:?} policy",
+              job.id, format_duration(waited), cfg.test_clip_approval_timeout_policy);
+        let decision = match cfg.test_clip_approval_timeout_policy {
+            ApprovalTimeoutPolicy::Approve => ApprovalDecision::Approved,
+            ApprovalTimeoutPolicy::Reject => ApprovalDecision::Rejected,
+        };
+        apply_approval_decision(store, &mut job, decision, "approval timeout")?;
+        expired_count += 1;
+    }
+
+    Ok(expired_count)
+}
+
+/// Requeue a job by ID once its worker (if any) has been stopped
+async fn force_requeue_job_by_id(cfg: &TranscodeConfig, store: &dyn JobStore, job_id: &str) -> Result<()> {
+    let job = store.get(job_id)
+        .context("Failed to load job for requeue")?;
+    match job {
+        Some(mut job) => force_requeue_job(cfg, store, &mut job).await,
+        None => {
+            warn!("Job {}: Not found for requeue", job_id);
+            Ok(())
+        }
+    }
+}
+
+/// Cancel a job by ID once its worker (if any) has been stopped
+fn cancel_job_by_id(cfg: &TranscodeConfig, store: &dyn JobStore, job_id: &str) -> Result<()> {
+    let job = store.get(job_id)
+        .context("Failed to load job for cancel")?;
+    match job {
+        Some(mut job) if is_unfinished(&job.status) => cancel_job(cfg, store, &mut job),
+        Some(job) => {
+            // Finished while the cancel was in flight
+            info!("Job {}: Not cancelled - status is already {:?}", job.id, job.status);
+            Ok(())
+        }
+        None => {
+            warn!("Job {}: Not found for cancel", job_id);
+            Ok(())
+        }
+    }
+}
+
+/// Statuses of jobs that haven't finished yet (can be cancelled or paused)
+const UNFINISHED_STATUSES: [JobStatus; 3] = [JobStatus::Pending, JobStatus::Running, JobStatus::AwaitingApproval];
+
+fn is_unfinished(status: &JobStatus) -> bool {
+    UNFINISHED_STATUSES.contains(status)
 }
 
 /// Process command files from TUI
-async fn process_command_files(cfg: &TranscodeConfig) -> Result<usize> {
+/// Requeues of running jobs and all cancels are handed to the scheduler, which owns the workers
+async fn process_command_files(cfg: &TranscodeConfig, store: &dyn JobStore, scheduler_tx: &mpsc::Sender<SchedulerEvent>) -> Result<usize> {
     let command_dir = cfg.command_dir();
     
     // Create command directory if it doesn't exist
@@ -605,18 +1417,24 @@ async fn process_command_files(cfg: &TranscodeConfig) -> Result<usize> {
         // Process command
         if cmd.action == "requeue" {
             // Load job
-            let jobs = load_all_jobs(&cfg.job_state_dir)
-                .context("Failed to load jobs for requeue")?;
+            let job = store.get(&cmd.job_id)
+                .context("Failed to load job for requeue")?;
             
-            if let Some(mut job) = jobs.into_iter().find(|j| j.id == cmd.job_id) {
+            if let Some(job) = job {
                 // Verify job is in a state that can be requeued
                 if job.status == JobStatus::Running || job.status == JobStatus::Pending {
                     info!("Job {}: Processing manual requeue command", job.id);
                     
                     if job.status == JobStatus::Running {
-                        // Force requeue with cleanup
-                        force_requeue_job(cfg, &mut job).await
-                            .with_context(|| format!("Failed to force requeue job {}", job.id))?;
+                        // Scheduler stops the worker, then cleans up and resets the job
+                        scheduler_tx.send(SchedulerEvent::Requeue { job_id: job.id.clone() }).await
+                            .context("Scheduler is not running")?;
+                    } else if job.retry_at.is_some() {
+                        // Waiting for a retry backoff - retry right away
+                        let mut job = job;
+                        job.retry_at = None;
+                        store.save(&job)?;
+                        info!("Job {}: Retry backoff cleared, job will start when a slot is free", job.id);
                     } else {
                         // Already pending, just log it
                         info!("Job {}: Already pending, no action needed", job.id);
@@ -629,6 +1447,72 @@ async fn process_command_files(cfg: &TranscodeConfig) -> Result<usize> {
             } else {
                 warn!("Job {}: Not found for requeue command", cmd.job_id);
             }
+        } else if cmd.action == "cancel" {
+            let job = store.get(&cmd.job_id)
+                .context("Failed to load job for cancel")?;
+
+            if let Some(job) = job {
+                if is_unfinished(&job.status) {
+                    info!("Job {}: Processing manual cancel command", job.id);
+                    // Even pending jobs go through the scheduler so it can't start them meanwhile
+                    scheduler_tx.send(SchedulerEvent::Cancel { job_id: job.id.clone() }).await
+                        .context("Scheduler is not running")?;
+                    processed_count += 1;
+                } else {
+                    warn!("Job {}: Cannot cancel - status is {:?}", job.id, job.status);
+                }
+            } else {
+                warn!("Job {}: Not found for cancel command", cmd.job_id);
+            }
+        } else if cmd.action == "pause" || cmd.action == "resume" {
+            let job = store.get(&cmd.job_id)
+                .context("Failed to load job for pause/resume")?;
+
+            if let Some(job) = job {
+                if is_unfinished(&job.status) {
+                    info!("Job {}: Processing manual {} command", job.id, cmd.action);
+                    let event = if cmd.action == "pause" {
+                        SchedulerEvent::Pause { job_id: job.id.clone() }
+                    } else {
+                        SchedulerEvent::Resume { job_id: job.id.clone() }
+                    };
+                    scheduler_tx.send(event).await
+                        .context("Scheduler is not running")?;
+                    processed_count += 1;
+                } else {
+                    warn!("Job {}: Cannot {} - status is {:?}", job.id, cmd.action, job.status);
+                }
+            } else {
+                warn!("Job {}: Not found for {} command", cmd.job_id, cmd.action);
+            }
+        } else if cmd.action == "pause_all" || cmd.action == "resume_all" {
+            // Daemon-wide - job_id is not used
+            info!("Processing manual {} command", cmd.action);
+            let event = if cmd.action == "pause_all" {
+                SchedulerEvent::PauseAll
+            } else {
+                SchedulerEvent::ResumeAll
+            };
+            scheduler_tx.send(event).await
+                .context("Scheduler is not running")?;
+            processed_count += 1;
+        } else if let Some(decision) = ApprovalDecision::from_command(&cmd.action, cmd.amount) {
+            // Test clip review decision from TUI
+            let job = store.get(&cmd.job_id)
+                .context("Failed to load job for test clip decision")?;
+
+            if let Some(mut job) = job {
+                if job.status == JobStatus::AwaitingApproval {
+                    apply_approval_decision(store, &mut job, decision, "user")
+                        .with_context(|| format!("Failed to apply test clip decision to job {}", job.id))?;
+                    processed_count += 1;
+                } else {
+                    warn!("Job {}: Ignoring {} - job is not awaiting approval (status {:?})",
+                          job.id, cmd.action, job.status);
+                }
+            } else {
+                warn!("Job {}: Not found for {} command", cmd.job_id, cmd.action);
+            }
         } else {
             warn!("Unknown command action: {}", cmd.action);
         }
@@ -648,17 +1532,16 @@ async fn process_command_files(cfg: &TranscodeConfig) -> Result<usize> {
 /// Clean up orphaned temp files that don't have active jobs
 /// Returns the number of files cleaned up
 /// Aggressively cleans both library roots AND temp_output_dir
-async fn cleanup_orphaned_temp_files(cfg: &TranscodeConfig) -> Result<usize> {
+async fn cleanup_orphaned_temp_files(cfg: &TranscodeConfig, store: &dyn JobStore) -> Result<usize> {
     info!("🔍 Checking for orphaned temp files in library and temp directory...");
     
     // Load all jobs to check against
-    let jobs = load_all_jobs(&cfg.job_state_dir)
+    let jobs = store.find_by_status(&[JobStatus::Pending, JobStatus::Running])
         .context("Failed to load jobs for cleanup")?;
     
     // Create set of filenames that have active jobs (just the stem, not full path)
     let active_filenames: HashSet<String> = jobs
         .iter()
-        .filter(|j| matches!(j.status, JobStatus::Pending | JobStatus::Running))
         .filter_map(|j| {
             j.source_path
                 .file_stem()
@@ -686,12 +1569,10 @@ async fn cleanup_orphaned_temp_files(cfg: &TranscodeConfig) -> Result<usize> {
                     
                     // Check if it's a temp file
                     if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-                        if !file_name.ends_with(".tmp.av1.mkv") {
+                        // Extract base filename (remove .tmp.av1.<ext>)
+                        let Some((base_name, _ext)) = file_name.rsplit_once(".tmp.av1.") else {
                             continue;
-                        }
-                        
-                        // Extract base filename (remove .tmp.av1.mkv)
-                        let base_name = file_name.trim_end_matches(".tmp.av1.mkv");
+                        };
                         
                         // Check if there's an active job for this file
                         if !active_names.contains(base_name) {
@@ -732,9 +1613,9 @@ async fn cleanup_orphaned_temp_files(cfg: &TranscodeConfig) -> Result<usize> {
 /// Extract metadata for a pending job (background task for EST SAVE calculation)
 async fn extract_metadata_for_job(
     cfg: &TranscodeConfig,
+    store: &dyn JobStore,
     job_id: &str,
     job_path: &PathBuf,
-    job_state_dir: &PathBuf,
 ) -> Result<()> {
     debug!("Job {}: Starting background metadata extraction for {}", job_id, job_path.display());
     
@@ -749,11 +1630,11 @@ async fn extract_metadata_for_job(
     
     debug!("Job {}: Background ffprobe completed, found {} streams", job_id, meta.streams.len());
     
-    // Load the job from disk (might have been updated by another process)
-    let all_jobs = load_all_jobs(job_state_dir)
-        .context("Failed to load jobs for metadata extraction")?;
-    let mut job = match all_jobs.iter().find(|j| j.id == job_id) {
-        Some(j) => j.clone(),
+    // Load the job again (might have been updated by another task)
+    let job = store.get(job_id)
+        .context("Failed to load job for metadata extraction")?;
+    let mut job = match job {
+        Some(j) => j,
         None => {
             warn!("Job {}: Not found in job list (might have been deleted)", job_id);
             return Ok(()); // Job doesn't exist, nothing to do
@@ -779,7 +1660,7 @@ async fn extract_metadata_for_job(
     // Check for video streams
     let video_streams: Vec<_> = meta.streams
         .iter()
-        .filter(|s| s.codec_type.as_deref() == Some("video"))
+        .filter(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture())
         .collect();
     
     if video_streams.is_empty() {
@@ -821,41 +1702,48 @@ async fn extract_metadata_for_job(
         }
         
         // Save job with metadata
-        save_job(&job, job_state_dir)
+        store.save(&job)
             .context("Failed to save job with metadata")?;
-        
-        // Verify the save worked by reloading and checking
-        if let Ok(verify_jobs) = load_all_jobs(job_state_dir) {
-            if let Some(saved_job) = verify_jobs.iter().find(|j| j.id == job_id) {
-                let saved_has_metadata = saved_job.video_codec.is_some() && 
-                                        saved_job.video_width.is_some() && 
-                                        saved_job.video_height.is_some() && 
-                                        saved_job.video_bitrate.is_some() && 
-                                        saved_job.video_frame_rate.is_some();
-                if saved_has_metadata {
-                    info!("Job {}: ✅ Background metadata extraction complete - EST SAVE now available in TUI (verified: codec={:?}, {:.0}x{:.0}, bitrate={:?} bps, fps={:?})", 
-                          job_id, 
-                          saved_job.video_codec, 
-                          saved_job.video_width.unwrap_or(0), 
-                          saved_job.video_height.unwrap_or(0),
-                          saved_job.video_bitrate,
-                          saved_job.video_frame_rate);
-                } else {
-                    warn!("Job {}: ⚠️  Background metadata extraction saved but verification failed - metadata may be incomplete", job_id);
-                }
-            } else {
-                warn!("Job {}: ⚠️  Background metadata extraction saved but job not found on reload", job_id);
-            }
-        } else {
-            warn!("Job {}: ⚠️  Background metadata extraction saved but failed to verify (could not reload jobs)", job_id);
+
+        if has_all_metadata {
+            info!("Job {}: ✅ Background metadata extraction complete - EST SAVE now available in TUI (codec={:?}, {:.0}x{:.0}, bitrate={:?} bps, fps={:?})", 
+                  job_id, 
+                  job.video_codec, 
+                  job.video_width.unwrap_or(0), 
+                  job.video_height.unwrap_or(0),
+                  job.video_bitrate,
+                  job.video_frame_rate);
         }
     }
     
     Ok(())
 }
 
+/// Longest the RPU tool may run (paused time excluded); it rewrites the whole
+/// output, so allow for large remuxes
+const RPU_TOOL_TIMEOUT_SECS: u64 = 2 * 60 * 60;
+
+/// Carry the source's Dolby Vision RPU over into the encoded file
+/// The tool is run as `<tool> <source> <output>` and rewrites the output in place.
+/// It runs under the job like FFmpeg, so pausing and cancelling the job reach it;
+/// a cancel returns `Cancelled`.
+async fn run_rpu_tool(ffmpeg_mgr: &FFmpegManager, job_id: &str, tool: &Path, source: &Path, output: &Path) -> Result<()> {
+    let args = vec![source.to_string_lossy().to_string(), output.to_string_lossy().to_string()];
+    let result = ffmpeg_mgr.run_job_tool(job_id, tool, args, Some(RPU_TOOL_TIMEOUT_SECS))
+        .await
+        .with_context(|| format!("Failed to run {}", tool.display()))?;
+    if result.cancelled {
+        return Err(Cancelled.into());
+    }
+    if !result.success {
+        let exit = result.exit_code.map_or("a signal".to_string(), |code| format!("exit code {}", code));
+        return Err(anyhow!("{} failed with {}: {}", tool.display(), exit, job::stderr_tail(&result.stderr)));
+    }
+    Ok(())
+}
+
 /// Process a single job: probe, classify, transcode, and apply size gate
-async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mut Job) -> Result<()> {
+async fn process_job(cfg: &TranscodeConfig, store: &dyn JobStore, ffmpeg_mgr: &FFmpegManager, job: &mut Job) -> Result<()> {
     info!("Job {}: Starting ffprobe for {}", job.id, job.source_path.display());
     
     // Step 1: Run ffprobe to get metadata
@@ -863,7 +1751,9 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         Ok(m) => m,
         Err(e) => {
             error!("Job {}: ffprobe failed: {}", job.id, e);
-            return Err(e).with_context(|| format!("Failed to probe file: {}", job.source_path.display()));
+            job.fail(JobFailure::ProbeFailed { message: format!("{:#}", e) });
+            store.save(job)?;
+            return Ok(());
         }
     };
     
@@ -872,7 +1762,7 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
     // Step 2: Check for video streams
     let video_streams: Vec<_> = meta.streams
         .iter()
-        .filter(|s| s.codec_type.as_deref() == Some("video"))
+        .filter(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture())
         .collect();
 
     info!("Job {}: Found {} video streams", job.id, video_streams.len());
@@ -884,7 +1774,7 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         job.status = JobStatus::Skipped;
         job.reason = Some(reason);
         job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        store.save(job)?;
         return Ok(());
     }
 
@@ -901,7 +1791,7 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         job.status = JobStatus::Skipped;
         job.reason = Some(reason);
         job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        store.save(job)?;
         return Ok(());
     }
 
@@ -982,24 +1872,9 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         
         // Save job immediately after extracting metadata so TUI can use it
         info!("Job {}: Saving job with metadata...", job.id);
-        match save_job(job, &cfg.job_state_dir) {
+        match store.save(job) {
             Ok(()) => {
                 info!("Job {}: ✅ Job saved successfully with metadata", job.id);
-                // Verify the save worked by checking if we can read it back
-                if let Ok(verify_job) = job::load_all_jobs(&cfg.job_state_dir) {
-                    if let Some(saved_job) = verify_job.iter().find(|j| j.id == job.id) {
-                        let has_meta = saved_job.video_codec.is_some() 
-                            && saved_job.video_width.is_some() 
-                            && saved_job.video_height.is_some()
-                            && saved_job.video_bitrate.is_some()
-                            && saved_job.video_frame_rate.is_some();
-                        if has_meta {
-                            info!("Job {}: ✅ Verified - metadata is saved in job file", job.id);
-                        } else {
-                            warn!("Job {}: ⚠️  WARNING - metadata not found in saved job file!", job.id);
-                        }
-                    }
-                }
             },
             Err(e) => error!("Job {}: ❌ Failed to save job: {}", job.id, e),
         }
@@ -1007,6 +1882,60 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         warn!("Job {}: ❌ No video stream found - cannot extract metadata for estimation", job.id);
     }
 
+    // Step 3b: Dolby Vision sources follow the configured policy
+    if let Some(dv) = cfg.dolby_vision_policy.decide(&meta) {
+        job.dolby_vision = Some(dv.clone());
+        match dv.handling {
+            DolbyVisionHandling::Skipped => {
+                let reason = format!("dolby vision {} (dolby_vision_policy: {:?})", dv, cfg.dolby_vision_policy);
+                info!("Job {}: Skipping - {}", job.id, reason);
+                sidecar::write_why_txt(&job.source_path, &reason)?;
+                job.status = JobStatus::Skipped;
+                job.reason = Some(reason);
+                job.finished_at = Some(Utc::now());
+                store.save(job)?;
+                return Ok(());
+            }
+            DolbyVisionHandling::BaseLayer => {
+                warn!("Job {}: ⚠️  Dolby Vision {} - the output will play as plain HDR10/SDR", job.id, dv);
+            }
+            DolbyVisionHandling::RpuPreserved => {
+                info!("Job {}: 🎞 Dolby Vision {} - RPU will be carried over after encoding", job.id, dv);
+            }
+        }
+    }
+
+    // Step 3c: HDR10+ dynamic metadata only shows up per frame, so only HDR sources are checked
+    if video_streams.first().is_some_and(|s| s.is_hdr_content()) {
+        match ffprobe::probe_frame_side_data(ffmpeg_mgr, &job.source_path, 3).await {
+            Ok(types) if types.iter().any(|t| t == ffprobe::HDR10_PLUS_SIDE_DATA) => {
+                let handling = cfg.hdr10_plus_policy.decide(ffmpeg_mgr.best_encoder());
+                job.hdr10_plus = Some(handling);
+                match handling {
+                    Hdr10PlusHandling::Skipped => {
+                        let reason = format!("hdr10+ source (hdr10_plus_policy: {:?})", cfg.hdr10_plus_policy);
+                        info!("Job {}: Skipping - {}", job.id, reason);
+                        sidecar::write_why_txt(&job.source_path, &reason)?;
+                        job.status = JobStatus::Skipped;
+                        job.reason = Some(reason);
+                        job.finished_at = Some(Utc::now());
+                        store.save(job)?;
+                        return Ok(());
+                    }
+                    Hdr10PlusHandling::MetadataLost => {
+                        warn!("Job {}: ⚠️  HDR10+ source - {:?} drops the dynamic metadata, output will be HDR10",
+                              job.id, ffmpeg_mgr.best_encoder());
+                    }
+                    Hdr10PlusHandling::Preserved => {
+                        info!("Job {}: ✨ HDR10+ source - dynamic metadata will be carried through", job.id);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Job {}: ⚠️  Could not check for HDR10+ (treating as HDR10): {:#}", job.id, e),
+        }
+    }
+
     // Step 4: Classify source using enhanced SourceClassifier
     let classifier = classifier::SourceClassifier::new();
     let classification = classifier.classify(&job.source_path, &meta.format, &meta.streams);
@@ -1033,7 +1962,7 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         job.status = JobStatus::Skipped;
         job.reason = Some(reason);
         job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        store.save(job)?;
         return Ok(());
     }
     
@@ -1050,9 +1979,23 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         }
     }
 
-    // Step 5: Generate temp output path
-    let temp_output = get_temp_output_path(cfg, &job.source_path);
+    // Step 5: Decide the output container and generate temp output path
+    let output_plan = cfg.output_plan(&job.source_path);
+    if output_plan.renames_source(&job.source_path) && output_plan.final_path.exists() {
+        let reason = format!("converting to {:?} would overwrite {}", output_plan.container, output_plan.final_path.display());
+        info!("Job {}: Skipping - {}", job.id, reason);
+        sidecar::write_why_txt(&job.source_path, &reason)?;
+        job.status = JobStatus::Skipped;
+        job.reason = Some(reason);
+        job.finished_at = Some(Utc::now());
+        store.save(job)?;
+        return Ok(());
+    }
+    let temp_output = cfg.temp_output_path(&job.source_path);
     info!("Job {}: Using fast temp directory: {}", job.id, cfg.temp_output_dir.display());
+    if output_plan.renames_source(&job.source_path) {
+        info!("Job {}: Output will be converted to {:?}: {}", job.id, output_plan.container, output_plan.final_path.display());
+    }
     
     // Ensure temp directory exists
     fs::create_dir_all(&cfg.temp_output_dir)
@@ -1061,12 +2004,119 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
     info!("Job {}: Temp output will be: {}", job.id, temp_output.display());
 
     // Step 6: Calculate encoding parameters using QualityCalculator
-    let quality_calc = QualityCalculator::new();
-    let encoding_params = quality_calc.calculate_params(
+    let quality_calc = QualityCalculator::with_profile(cfg.active_profile());
+    info!("Job {}: Using encoding profile \"{}\"", job.id, cfg.encoding_profile);
+    let test_clip_workflow = TestClipWorkflow::new(cfg.temp_output_dir.clone());
+    let mut base_params = quality_calc.calculate_params(
         &classification,
         &meta,
         ffmpeg_mgr.best_encoder(),
     );
+    base_params.threads = Some(cfg.thread_budget_per_job());
+    let track_plan = cfg.tracks.select(&meta.streams);
+    let extra_video = track_plan.dropped.iter().filter(|t| t.kind == TrackKind::Video).count();
+    if extra_video > 0 && cfg.extra_video_policy == ExtraVideoPolicy::Skip {
+        let reason = format!("{} extra video stream(s) (multi-angle?) - extra_video_policy is skip", extra_video);
+        info!("Job {}: Skipping - {}", job.id, reason);
+        sidecar::write_why_txt(&job.source_path, &reason)?;
+        job.status = JobStatus::Skipped;
+        job.reason = Some(reason);
+        job.finished_at = Some(Utc::now());
+        store.save(job)?;
+        return Ok(());
+    }
+    if extra_video > 0 {
+        warn!("Job {}: ⚠️  {} extra video stream(s) - only the main one is encoded", job.id, extra_video);
+    }
+    if !track_plan.attached_pictures.is_empty() {
+        info!("Job {}: 🖼️  Copying {} cover art stream(s)", job.id, track_plan.attached_pictures.len());
+    }
+    for track in &track_plan.dropped {
+        info!("Job {}: 🔇 Dropping track {}", job.id, track);
+    }
+    job.dropped_tracks = track_plan.dropped.clone();
+    base_params.audio = quality_calc.audio_plan(&classification.tier, &cfg.audio, &meta, &track_plan.audio);
+    for output in base_params.audio.iter().filter(|a| a.action != AudioAction::Copy) {
+        let verb = if output.compatibility { "Adding compatibility track" } else { "Transcoding" };
+        info!("Job {}: 🔊 {} from audio #{}: {}", job.id, verb, output.source_index, output.describe());
+    }
+    let subtitle_plan = cfg.subtitle_policy.plan(&meta.streams, &track_plan.subtitles, &output_plan, &temp_output);
+    for track in &subtitle_plan.dropped {
+        info!("Job {}: 🔇 Dropping track {}", job.id, track);
+    }
+    for extracted in &subtitle_plan.extracted {
+        info!("Job {}: 💬 Extracting subtitle #{} to {}", job.id, extracted.source_index, extracted.final_path.display());
+    }
+    job.dropped_tracks.extend(subtitle_plan.dropped.iter().cloned());
+    base_params.tracks = Some(track_plan);
+    base_params.subtitles = Some(subtitle_plan);
+    base_params.strip_dolby_vision = job.dolby_vision.as_ref()
+        .is_some_and(|dv| dv.handling == DolbyVisionHandling::BaseLayer);
+    job.scan_type = None;
+    if cfg.deinterlace_policy == DeinterlacePolicy::Auto {
+        if let Some(video) = video_streams.first().filter(|s| interlace::worth_checking(s)) {
+            info!("Job {}: ☰ Checking for interlacing (field order: {})...",
+                  job.id, video.field_order.as_deref().unwrap_or("unknown"));
+            match ffmpeg_mgr.detect_interlacing(&job.id, &job.source_path, meta.format.duration_secs()).await {
+                Ok(Some(counts)) => match counts.scan_type(interlace::is_ntsc_rate(video)) {
+                    Some(scan_type) => {
+                        info!("Job {}: ☰ Scan type: {} (idet TFF {} / BFF {} / progressive {} / undetermined {})",
+                              job.id, scan_type, counts.tff, counts.bff, counts.progressive, counts.undetermined);
+                        job.scan_type = Some(scan_type);
+                    }
+                    None => info!("Job {}: ☰ idet was inconclusive - encoding as progressive", job.id),
+                },
+                Ok(None) => warn!("Job {}: ⚠️  idet found nothing - encoding as progressive", job.id),
+                Err(e) if e.is::<Cancelled>() => {
+                    // Requeue or cancel from the TUI - leave the job Running for the scheduler to reset
+                    info!("Job {}: idet was cancelled", job.id);
+                    return Ok(());
+                }
+                Err(e) => warn!("Job {}: ⚠️  Interlace detection failed (encoding as progressive): {:#}", job.id, e),
+            }
+        }
+    }
+    base_params.scan_type = job.scan_type;
+    job.crop = None;
+    if cfg.auto_crop {
+        if let Some((Some(width), Some(height))) = video_streams.first().map(|s| (s.width, s.height)) {
+            info!("Job {}: ✂  Detecting crop ({} sample points)...", job.id, cfg.auto_crop_samples);
+            match ffmpeg_mgr.detect_crop(&job.id, &job.source_path, meta.format.duration_secs(), cfg.auto_crop_samples, width, height).await {
+                Ok(Some(crop)) => {
+                    info!("Job {}: ✂  Cropping {}x{} to {}", job.id, width, height, crop);
+                    job.crop = Some(crop);
+                }
+                Ok(None) => info!("Job {}: ✂  No bars found - keeping the full picture", job.id),
+                Err(e) if e.is::<Cancelled>() => {
+                    // Requeue or cancel from the TUI - leave the job Running for the scheduler to reset
+                    info!("Job {}: cropdetect was cancelled", job.id);
+                    return Ok(());
+                }
+                Err(e) => warn!("Job {}: ⚠️  Crop detection failed (encoding uncropped): {:#}", job.id, e),
+            }
+        }
+    }
+    base_params.crop = job.crop;
+    let picture = job.crop.map(|c| (c.width, c.height))
+        .or_else(|| video_streams.first().and_then(|s| s.width.zip(s.height)));
+    if let Some((width, height)) = picture {
+        let source_crf = base_params.crf;
+        quality_calc.apply_max_resolution(&mut base_params, &classification.tier, ffmpeg_mgr.best_encoder(),
+                                          &cfg.max_resolution, width, height);
+        if let Some(scale) = base_params.scale {
+            info!("Job {}: ⇲ Downscaling {}x{} to {} (max_resolution for {:?}), CRF {} → {}",
+                  job.id, width, height, scale, classification.tier, source_crf, base_params.crf);
+        }
+    }
+    job.scale = base_params.scale;
+    // Replay adjustments requested during earlier test clip reviews
+    let encoding_params = test_clip_workflow.apply_decisions(&base_params, &job.test_clip_decisions);
+    if encoding_params != base_params {
+        info!("Job {}: Applied {} test clip decision(s): CRF {} → {}, Preset {} → {}",
+              job.id, job.test_clip_decisions.len(),
+              base_params.crf, encoding_params.crf,
+              base_params.preset, encoding_params.preset);
+    }
     
     // Store encoding parameters in job
     job.crf_used = Some(encoding_params.crf);
@@ -1084,8 +2134,9 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
           if job.is_hdr.unwrap_or(false) { " HDR" } else { "" },
           job.target_bit_depth.unwrap_or(8)
     );
-    info!("Job {}: Encoder: {:?}, CRF: {}, Preset: {}", 
-          job.id, ffmpeg_mgr.best_encoder(), encoding_params.crf, encoding_params.preset);
+    info!("Job {}: Encoder: {:?}, CRF: {}, Preset: {}, Threads: {}", 
+          job.id, ffmpeg_mgr.best_encoder(), encoding_params.crf, encoding_params.preset,
+          encoding_params.threads.unwrap_or(0));
     if let Some(tune) = encoding_params.tune {
         info!("Job {}: Tune: {}", job.id, tune);
     }
@@ -1093,14 +2144,30 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         info!("Job {}: Film grain: {}", job.id, film_grain);
     }
     
+    if let Some(attempt) = job.current_attempt_mut() {
+        attempt.encoder = Some(format!("{:?}", ffmpeg_mgr.best_encoder()));
+        attempt.crf = Some(encoding_params.crf);
+        attempt.preset = Some(encoding_params.preset);
+    }
+    
     // Save job with encoding parameters before encoding starts
-    save_job(job, &cfg.job_state_dir)?;
+    store.save(job)?;
     
     // Step 7: Test clip workflow for REMUX sources
-    if matches!(classification.tier, QualityTier::Remux) && cfg.enable_test_clip_workflow {
+    // The job is parked in AwaitingApproval until the user (or the timeout policy) decides
+    let needs_test_clip = matches!(classification.tier, QualityTier::Remux)
+        && cfg.enable_test_clip_workflow
+        && job.test_clip_approved != Some(true);
+    if needs_test_clip {
         info!("Job {}: 🎬 Starting test clip workflow for REMUX source", job.id);
-        
-        let test_clip_workflow = TestClipWorkflow::new(cfg.temp_output_dir.clone());
+
+        // Drop clips from a previous review round before extracting a fresh one
+        TestClipWorkflow::cleanup_clip_files(
+            job.test_clip_path.as_deref(),
+            job.test_clip_encoded_path.as_deref(),
+        );
+        job.test_clip_path = None;
+        job.test_clip_encoded_path = None;
         
         // Extract test clip
         let test_clip_info = match test_clip_workflow.extract_test_clip(&job.source_path, &meta, ffmpeg_mgr).await {
@@ -1108,7 +2175,7 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
                 info!("Job {}: ✅ Test clip extracted: {} ({:.1}s at {:.1}s)", 
                       job.id, info.clip_path.display(), info.duration, info.start_time);
                 job.test_clip_path = Some(info.clip_path.clone());
-                save_job(job, &cfg.job_state_dir)?;
+                store.save(job)?;
                 Some(info)
             }
             Err(e) => {
@@ -1123,7 +2190,7 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         if let Some(clip_info) = test_clip_info {
             info!("Job {}: 🎬 Encoding test clip with proposed parameters...", job.id);
             
-            let test_output = match test_clip_workflow.encode_test_clip(
+            match test_clip_workflow.encode_test_clip(
                 &clip_info,
                 &encoding_params,
                 ffmpeg_mgr,
@@ -1131,35 +2198,47 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
             ).await {
                 Ok(output) => {
                     info!("Job {}: ✅ Test clip encoded: {}", job.id, output.display());
-                    Some(output)
+                    info!("Job {}: ⏳ Waiting for test clip approval (CRF: {}, Preset: {})",
+                          job.id, encoding_params.crf, encoding_params.preset);
+                    job.test_clip_encoded_path = Some(output);
+                    job.approval_requested_at = Some(Utc::now());
+                    job.status = JobStatus::AwaitingApproval;
+                    store.save(job)?;
+                    return Ok(());
                 }
                 Err(e) => {
                     warn!("Job {}: ⚠️  Test clip encoding failed (non-fatal): {}", job.id, e);
                     warn!("Job {}: Proceeding with full encode", job.id);
                     // Clean up test clip
-                    if clip_info.clip_path.exists() {
-                        fs::remove_file(&clip_info.clip_path).ok();
-                    }
-                    // Continue without test clip approval
-                    None
+                    TestClipWorkflow::cleanup_clip_files(Some(&clip_info.clip_path), None);
+                    job.test_clip_path = None;
+                    store.save(job)?;
                 }
-            };
-            
-            // If test clip encoded successfully, auto-approve for now
-            // TODO: Implement actual user approval mechanism (TUI command, file-based, etc.)
-            if test_output.is_some() {
-                info!("Job {}: ✅ Test clip encoded successfully - auto-approving for now", job.id);
-                info!("Job {}: TODO: Implement user approval mechanism", job.id);
-                job.test_clip_approved = Some(true);
-                save_job(job, &cfg.job_state_dir)?;
             }
         }
+    } else if job.test_clip_path.is_some() || job.test_clip_encoded_path.is_some() {
+        // Approved - review clips are no longer needed
+        TestClipWorkflow::cleanup_clip_files(
+            job.test_clip_path.as_deref(),
+            job.test_clip_encoded_path.as_deref(),
+        );
+        job.test_clip_path = None;
+        job.test_clip_encoded_path = None;
+        store.save(job)?;
     }
     
     // Step 8: Run full transcoding
     info!("Job {}: Starting ffmpeg transcoding with CRF: {}, Preset: {}...", 
           job.id, encoding_params.crf, encoding_params.preset);
     
+    // Subtitle files left by an earlier attempt would make FFmpeg stop at its overwrite prompt
+    for extracted in encoding_params.subtitles.iter().flat_map(|p| &p.extracted) {
+        if extracted.temp_path.exists() {
+            fs::remove_file(&extracted.temp_path)
+                .with_context(|| format!("Failed to delete stale subtitle file: {}", extracted.temp_path.display()))?;
+        }
+    }
+
     // Build FFmpeg command
     let cmd_builder = CommandBuilder::new();
     let ffmpeg_args = cmd_builder.build_encode_command(
@@ -1173,47 +2252,91 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
     info!("Job {}: FFmpeg command: ffmpeg {}", job.id, ffmpeg_args.join(" "));
     
     // Execute FFmpeg (no timeout - let it run as long as needed)
-    let ffmpeg_result = match ffmpeg_mgr.execute_ffmpeg(ffmpeg_args, None).await {
+    // Live progress goes to {job_state_dir}/progress/{id}.json for the TUI
+    let duration_secs = meta.format.duration_secs();
+    let job_id = job.id.clone();
+    let mut last_progress_error: Option<String> = None;
+    let on_progress = |p: &EncodeProgress| {
+        if let Err(e) = progress::save_progress(&cfg.job_state_dir, &job_id, p) {
+            // Log once per distinct error, not on every progress block
+            let msg = e.to_string();
+            if last_progress_error.as_deref() != Some(msg.as_str()) {
+                warn!("Job {}: Failed to write progress: {}", job_id, msg);
+                last_progress_error = Some(msg);
+            }
+        }
+    };
+    let ffmpeg_result = match ffmpeg_mgr.execute_ffmpeg_with_progress(&job.id, ffmpeg_args, None, duration_secs, on_progress).await {
         Ok(result) => result,
         Err(e) => {
-            error!("Job {}: Failed to execute ffmpeg command: {}", job.id, e);
-            let reason = format!("ffmpeg execution failed: {}", e);
-            sidecar::write_why_txt(&job.source_path, &reason)?;
+            // Non-zero exits come back as Ok, so this is FFmpeg failing to spawn (or be waited on)
+            error!("Job {}: Failed to execute ffmpeg command: {:#}", job.id, e);
+            let failure = JobFailure::EncoderLaunchFailed { message: format!("{:#}", e) };
+            sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
             // Delete temp file on failure
             if temp_output.exists() {
                 fs::remove_file(&temp_output)
                     .with_context(|| format!("Failed to delete temp file after execution failure: {}", temp_output.display()))?;
                 info!("Job {}: 🗑️  Deleted temp file after execution failure: {}", job.id, temp_output.display());
             }
-            job.status = JobStatus::Failed;
-            job.reason = Some(reason);
-            job.finished_at = Some(Utc::now());
-            save_job(job, &cfg.job_state_dir)?;
+            job.fail(failure);
+            store.save(job)?;
             return Ok(());
         }
     };
     
+    if ffmpeg_result.cancelled {
+        // Requeue or cancel from the TUI - leave the job Running for the scheduler to reset
+        info!("Job {}: ffmpeg was cancelled", job.id);
+        if temp_output.exists() {
+            fs::remove_file(&temp_output)
+                .with_context(|| format!("Failed to delete temp file after cancel: {}", temp_output.display()))?;
+            info!("Job {}: 🗑️  Deleted temp file after cancel: {}", job.id, temp_output.display());
+        }
+        return Ok(());
+    }
+    
     if !ffmpeg_result.success {
         error!("Job {}: ffmpeg failed with exit code {:?}", job.id, ffmpeg_result.exit_code);
         error!("Job {}: ffmpeg STDOUT: {}", job.id, ffmpeg_result.stdout);
         error!("Job {}: ffmpeg STDERR: {}", job.id, ffmpeg_result.stderr);
-        let reason = format!("ffmpeg exit code {:?}", ffmpeg_result.exit_code);
-        sidecar::write_why_txt(&job.source_path, &reason)?;
+        // Disk errors become a retryable Io failure, anything else an encoder crash
+        job.fail_ffmpeg_exit(ffmpeg_result.exit_code, &ffmpeg_result.stderr);
+        sidecar::write_why_txt(&job.source_path, job.reason.as_deref().unwrap_or_default())?;
         // Delete temp file on failure
         if temp_output.exists() {
             fs::remove_file(&temp_output)
                 .with_context(|| format!("Failed to delete temp file after failure: {}", temp_output.display()))?;
             info!("Job {}: 🗑️  Deleted temp file after failure: {}", job.id, temp_output.display());
         }
-        job.status = JobStatus::Failed;
-        job.reason = Some(reason);
-        job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        store.save(job)?;
         return Ok(());
     }
 
     info!("Job {}: ffmpeg completed successfully (exit code 0)", job.id);
 
+    // Step 6b: Put the Dolby Vision RPU back (preserve_rpu)
+    if job.dolby_vision.as_ref().is_some_and(|dv| dv.handling == DolbyVisionHandling::RpuPreserved) {
+        if let Some(tool) = &cfg.dolby_vision_rpu_tool {
+            info!("Job {}: 🎞 Carrying Dolby Vision RPU over with {}", job.id, tool.display());
+            if let Err(e) = run_rpu_tool(ffmpeg_mgr, &job.id, tool, &job.source_path, &temp_output).await {
+                if e.is::<Cancelled>() {
+                    // Requeue or cancel from the TUI - leave the job Running for the scheduler to reset
+                    info!("Job {}: RPU tool was cancelled", job.id);
+                    fs::remove_file(&temp_output).ok();
+                    return Ok(());
+                }
+                let failure = JobFailure::RpuToolFailed { message: format!("{:#}", e) };
+                error!("Job {}: ❌ {}", job.id, failure);
+                sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
+                fs::remove_file(&temp_output).ok();
+                job.fail(failure);
+                store.save(job)?;
+                return Ok(());
+            }
+        }
+    }
+
     // Step 7: Verify temp output file exists and is valid
     info!("Job {}: Verifying temp output file: {}", job.id, temp_output.display());
     
@@ -1237,16 +2360,16 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
     }
     
     if !temp_output.exists() {
-        let reason = format!("transcoded output file does not exist: {}", temp_output.display());
-        error!("Job {}: {}", job.id, reason);
+        let failure = JobFailure::OutputInvalid {
+            message: format!("transcoded output file does not exist: {}", temp_output.display()),
+        };
+        error!("Job {}: {}", job.id, failure);
         error!("Job {}: FFmpeg reported success but output file is missing", job.id);
         error!("Job {}: This may indicate: disk full, permission issue, or path mismatch", job.id);
         
-        sidecar::write_why_txt(&job.source_path, &reason)?;
-        job.status = JobStatus::Failed;
-        job.reason = Some(reason);
-        job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
+        job.fail(failure);
+        store.save(job)?;
         return Ok(());
     }
     
@@ -1258,13 +2381,11 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
 
     // Verify temp file is not empty
     if new_bytes == 0 {
-        let reason = "transcoded output file is empty".to_string();
-        sidecar::write_why_txt(&job.source_path, &reason)?;
+        let failure = JobFailure::OutputInvalid { message: "transcoded output file is empty".to_string() };
+        sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
         fs::remove_file(&temp_output).ok(); // Clean up empty temp file
-        job.status = JobStatus::Failed;
-        job.reason = Some(reason);
-        job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        job.fail(failure);
+        store.save(job)?;
         return Ok(());
     }
 
@@ -1279,29 +2400,56 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
             let has_av1 = output_meta.streams.iter().any(|s| s.codec_name.as_deref() == Some("av1"));
             
             if !has_video {
-                let reason = "output validation failed: no video streams found".to_string();
-                error!("Job {}: ❌ {}", job.id, reason);
-                sidecar::write_why_txt(&job.source_path, &reason)?;
+                let failure = JobFailure::OutputInvalid { message: "no video streams found".to_string() };
+                error!("Job {}: ❌ {}", job.id, failure);
+                sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
                 fs::remove_file(&temp_output).ok();
-                job.status = JobStatus::Failed;
-                job.reason = Some(reason);
-                job.finished_at = Some(Utc::now());
-                save_job(job, &cfg.job_state_dir)?;
+                job.fail(failure);
+                store.save(job)?;
                 return Ok(());
             }
             
             if !has_av1 {
-                let reason = "output validation failed: output is not AV1".to_string();
-                error!("Job {}: ❌ {}", job.id, reason);
-                sidecar::write_why_txt(&job.source_path, &reason)?;
+                let failure = JobFailure::OutputInvalid { message: "output is not AV1".to_string() };
+                error!("Job {}: ❌ {}", job.id, failure);
+                sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
                 fs::remove_file(&temp_output).ok();
-                job.status = JobStatus::Failed;
-                job.reason = Some(reason);
-                job.finished_at = Some(Utc::now());
-                save_job(job, &cfg.job_state_dir)?;
+                job.fail(failure);
+                store.save(job)?;
                 return Ok(());
             }
             
+            // The colour description must survive, or HDR plays back washed out
+            let output_video = output_meta.streams.iter()
+                .find(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture());
+            if let (Some(color), Some(output_video)) = (&encoding_params.color, output_video) {
+                let lost = color.lost_tags(output_video);
+                if !lost.is_empty() {
+                    let failure = JobFailure::OutputInvalid { message: format!("output lost {}", lost.join(", ")) };
+                    error!("Job {}: ❌ {}", job.id, failure);
+                    sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
+                    fs::remove_file(&temp_output).ok();
+                    job.fail(failure);
+                    store.save(job)?;
+                    return Ok(());
+                }
+                // Static metadata sits in the AV1 bitstream; not every container repeats it
+                let missing = color.lost_static_metadata(output_video);
+                if !missing.is_empty() {
+                    warn!("Job {}: ⚠️  {} not visible in the output container", job.id, missing.join(", "));
+                }
+            }
+
+            if job.hdr10_plus == Some(Hdr10PlusHandling::Preserved) {
+                let carried = ffprobe::probe_frame_side_data(ffmpeg_mgr, &temp_output, 3).await
+                    .map(|types| types.iter().any(|t| t == ffprobe::HDR10_PLUS_SIDE_DATA))
+                    .unwrap_or(false);
+                if !carried {
+                    warn!("Job {}: ⚠️  HDR10+ dynamic metadata did not survive the encode", job.id);
+                    job.hdr10_plus = Some(Hdr10PlusHandling::MetadataLost);
+                }
+            }
+
             info!("Job {}: ✅ Output validation passed - AV1 video stream confirmed", job.id);
             true
         }
@@ -1324,61 +2472,103 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
 
     if new_bytes as f64 > orig_bytes as f64 * cfg.max_size_ratio {
         // Rejected by size gate
-        let reason = format!(
-            "rejected: new {} GB vs orig {} GB (>{}%)",
-            new_bytes as f64 / 1_000_000_000.0,
-            orig_bytes as f64 / 1_000_000_000.0,
-            cfg.max_size_ratio * 100.0
-        );
-        sidecar::write_why_txt(&job.source_path, &reason)?;
+        let failure = JobFailure::SizeGate {
+            new_bytes,
+            original_bytes: orig_bytes,
+            max_size_ratio: cfg.max_size_ratio,
+        };
+        sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
         sidecar::write_skip_marker(&job.source_path)?;
         fs::remove_file(&temp_output).ok(); // Clean up temp file
-        job.status = JobStatus::Skipped;
-        job.reason = Some("size gate".to_string());
-        job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        job.reject(failure);
+        store.save(job)?;
         return Ok(());
     }
 
+    // Step 8b: Perceptual quality check against the source
+    job.quality_score = None;
+    if cfg.quality_check.enabled {
+        let check = &cfg.quality_check;
+        info!("Job {}: 📏 Measuring {} over {} segment(s) of {}s...",
+              job.id, check.metric.label(), check.samples, check.sample_secs);
+        let unmeasured = match ffmpeg_mgr.measure_quality(&job.id, &job.source_path, &temp_output, &encoding_params, check, duration_secs).await {
+            Ok(Some(score)) => {
+                info!("Job {}: 📏 {}", job.id, score);
+                job.quality_score = Some(score);
+                if let Some(min_score) = check.min_score.for_tier(&classification.tier).filter(|min| score.mean < *min) {
+                    let failure = JobFailure::QualityGate { metric: score.metric, score: score.mean, min_score };
+                    warn!("Job {}: ❌ {} - keeping the original", job.id, failure);
+                    sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
+                    sidecar::write_skip_marker(&job.source_path)?;
+                    fs::remove_file(&temp_output).ok(); // Clean up temp file
+                    job.reject(failure);
+                    store.save(job)?;
+                    return Ok(());
+                }
+                None
+            }
+            Ok(None) => Some("no segment could be measured".to_string()),
+            Err(e) if e.is::<Cancelled>() => {
+                // Requeue or cancel from the TUI - leave the job Running for the scheduler to reset
+                info!("Job {}: quality check was cancelled", job.id);
+                fs::remove_file(&temp_output).ok();
+                return Ok(());
+            }
+            Err(e) => Some(format!("{:#}", e)),
+        };
+        if let Some(message) = unmeasured {
+            if check.min_score.for_tier(&classification.tier).is_some() {
+                // The minimum can't be enforced without a score, so the original stays
+                let failure = JobFailure::QualityUnmeasured { metric: check.metric, message };
+                error!("Job {}: ❌ {}", job.id, failure);
+                sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
+                fs::remove_file(&temp_output).ok(); // Clean up temp file
+                job.fail(failure);
+                store.save(job)?;
+                return Ok(());
+            }
+            warn!("Job {}: ⚠️  {} could not be measured (non-fatal): {}", job.id, check.metric.label(), message);
+        }
+    }
+
     // Step 9: ALL VERIFICATIONS PASSED - Replace original file with transcoded version
     // This is the final step that ALWAYS executes if we reach here
     // Strategy: Backup original first, then replace with temp file
     
     // Verify original file still exists before backing up
     if !job.source_path.exists() {
-        let reason = format!("original file no longer exists: {}", job.source_path.display());
-        sidecar::write_why_txt(&job.source_path, &reason)?;
+        let failure = JobFailure::SourceVanished { path: job.source_path.clone() };
+        sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
         fs::remove_file(&temp_output).ok(); // Clean up temp file
-        job.status = JobStatus::Failed;
-        job.reason = Some(reason);
-        job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        job.fail(failure);
+        store.save(job)?;
         return Ok(());
     }
 
-    let orig_backup = job.source_path.with_extension("orig.mkv");
+    let orig_backup = container::backup_path(&job.source_path);
     
     // Backup original file
     fs::rename(&job.source_path, &orig_backup)
         .with_context(|| format!("Failed to backup original file: {} -> {}", 
             job.source_path.display(), orig_backup.display()))?;
 
-    // Replace with transcoded file
+    // Replace with transcoded file (under the new name if the source is converted to MKV)
+    let final_path = output_plan.final_path.clone();
     // If temp file is on different filesystem (e.g., NVMe), rename will fail - use copy instead
-    if let Err(e) = fs::rename(&temp_output, &job.source_path) {
+    if let Err(e) = fs::rename(&temp_output, &final_path) {
         info!("Job {}: Rename failed ({}), copying from temp directory instead...", job.id, e);
         
         // Copy temp file to final location
-        fs::copy(&temp_output, &job.source_path)
+        fs::copy(&temp_output, &final_path)
             .with_context(|| format!("Failed to copy transcoded file: {} -> {}", 
-                temp_output.display(), job.source_path.display()))?;
+                temp_output.display(), final_path.display()))?;
         
         info!("Job {}: ✓ Copied transcoded file from temp directory", job.id);
         
         // SAFETY: Triple-check the destination file exists and is valid before deleting temp file
         // This prevents data loss if the copy was interrupted or incomplete
-        let destination_valid = job.source_path.exists() 
-            && fs::metadata(&job.source_path)
+        let destination_valid = final_path.exists() 
+            && fs::metadata(&final_path)
                 .map(|m| m.len() > 1_000_000) // At least 1MB (sanity check)
                 .unwrap_or(false);
         
@@ -1388,7 +2578,7 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
             // DO NOT delete temp file - it's our only copy!
         } else {
             info!("Job {}: ✅ Verified destination file is valid ({} bytes)", 
-                  job.id, fs::metadata(&job.source_path).unwrap().len());
+                  job.id, fs::metadata(&final_path).unwrap().len());
             
             // Now safe to delete temp file
             if temp_output.exists() {
@@ -1410,25 +2600,23 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
     }
 
     // Verify replacement succeeded
-    if !job.source_path.exists() {
+    if !final_path.exists() {
         // Critical error: replacement failed, try to restore backup
         let _ = fs::rename(&orig_backup, &job.source_path); // Try to restore
-        let reason = "file replacement verification failed - backup restored".to_string();
-        sidecar::write_why_txt(&job.source_path, &reason)?;
+        let failure = JobFailure::ReplaceFailed { message: "verification failed - backup restored".to_string() };
+        sidecar::write_why_txt(&job.source_path, &failure.to_string())?;
         // Clean up temp file if it still exists
         if temp_output.exists() {
             fs::remove_file(&temp_output).ok();
         }
-        job.status = JobStatus::Failed;
-        job.reason = Some(reason);
-        job.finished_at = Some(Utc::now());
-        save_job(job, &cfg.job_state_dir)?;
+        job.fail(failure);
+        store.save(job)?;
         return Ok(());
     }
 
     // Step 10: ALL VERIFICATIONS PASSED - Delete original backup file
     // The transcoded file has successfully replaced the original
-    // Now delete the .orig.mkv backup since everything worked
+    // Now delete the .orig backup since everything worked
     if orig_backup.exists() {
         fs::remove_file(&orig_backup)
             .with_context(|| format!("Failed to delete original backup file: {}", orig_backup.display()))?;
@@ -1437,13 +2625,25 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
         warn!("Job {}: ⚠️  Original backup file not found (may have been deleted already): {}", job.id, orig_backup.display());
     }
 
+    // Extracted subtitles go next to the transcoded file; losing one doesn't undo the job
+    for extracted in encoding_params.subtitles.iter().flat_map(|p| &p.extracted) {
+        let moved = fs::rename(&extracted.temp_path, &extracted.final_path)
+            .or_else(|_| fs::copy(&extracted.temp_path, &extracted.final_path)
+                .and_then(|_| fs::remove_file(&extracted.temp_path)));
+        match moved {
+            Ok(()) => info!("Job {}: 💬 Wrote subtitle file: {}", job.id, extracted.final_path.display()),
+            Err(e) => warn!("Job {}: ⚠️  Failed to write subtitle file {} (non-fatal): {}",
+                            job.id, extracted.final_path.display(), e),
+        }
+    }
+
     // Step 11: Update job status to Success - ALL CHECKS PASSED, FILE REPLACED, ORIGINAL DELETED
     let end_time = Utc::now();
     job.status = JobStatus::Success;
-    job.output_path = Some(job.source_path.clone());
+    job.output_path = Some(final_path.clone());
     job.new_bytes = Some(new_bytes);
     job.finished_at = Some(end_time);
-    save_job(job, &cfg.job_state_dir)?;
+    store.save(job)?;
 
     info!("Job {}: ✅ SUCCESS - Original file deleted, transcoded file in place (CRF: {}, Preset: {})", 
           job.id, job.crf_used.unwrap_or(0), job.preset_used.unwrap_or(0));
@@ -1451,14 +2651,20 @@ async fn process_job(cfg: &TranscodeConfig, ffmpeg_mgr: &FFmpegManager, job: &mu
     // Step 12: Write simple completion marker
     info!("Job {}: 📝 Writing completion marker...", job.id);
     let completion_msg = format!(
-        "Transcoded successfully\nEncoder: {:?}\nCRF: {}\nPreset: {}\nQuality Tier: {:?}\nOriginal: {} MB\nNew: {} MB\nSavings: {:.1}%",
+        "Transcoded successfully\nEncoder: {:?}\nCRF: {}\nPreset: {}\nQuality Tier: {:?}\nOriginal: {} MB\nNew: {} MB\nSavings: {:.1}%{}{}{}{}{}{}",
         ffmpeg_mgr.best_encoder(),
         encoding_params.crf,
         encoding_params.preset,
         classification.tier,
         orig_bytes as f64 / 1_000_000.0,
         new_bytes as f64 / 1_000_000.0,
-        (1.0 - (new_bytes as f64 / orig_bytes as f64)) * 100.0
+        (1.0 - (new_bytes as f64 / orig_bytes as f64)) * 100.0,
+        job.dolby_vision.as_ref().map(|dv| format!("\nDolby Vision: {}", dv)).unwrap_or_default(),
+        job.hdr10_plus.map(|h| format!("\nHDR10+: {}", h)).unwrap_or_default(),
+        job.crop.map(|c| format!("\nCrop: {}", c)).unwrap_or_default(),
+        job.scale.map(|s| format!("\nScaled to: {}", s)).unwrap_or_default(),
+        job.quality_score.map(|q| format!("\nQuality: {}", q)).unwrap_or_default(),
+        job.scan_type.filter(|s| *s != ScanType::Progressive).map(|s| format!("\nScan: {}", s)).unwrap_or_default()
     );
     
     match sidecar::write_why_txt(&job.source_path, &completion_msg) {
@@ -1,19 +1,20 @@
 use anyhow::{Context, Result};
 use clap::Parser;
 use chrono::{Utc, DateTime};
-use daemon::{config::TranscodeConfig, job::{Job, JobStatus, load_all_jobs}};
+use daemon::{config::TranscodeConfig, container::{self, ContainerPolicy}, control::{self, DaemonState}, failure::FailureKind, job::{Job, JobStatus}, progress::{self, EncodeProgress}, store::{self, JobStore}};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Gauge, Paragraph, Row, Sparkline, Table, TableState},
+    widgets::{Block, Borders, Cell, Gauge, Paragraph, Row, Sparkline, Table, TableState},
     Frame, Terminal,
 };
 use std::io::stdout;
 use std::path::{Path, PathBuf};
 use std::time::Duration;
 use std::collections::HashMap;
+use std::sync::Arc;
 use sysinfo::System;
 use humansize::{format_size, DECIMAL};
 
@@ -49,6 +50,21 @@ enum JobFilter {
     Failed,
 }
 
+/// Display name of the active filters, e.g. "Failed/io"
+fn filter_label(ui_state: &UiState) -> String {
+    let status = match ui_state.filter {
+        JobFilter::All => "All",
+        JobFilter::Pending => "Pending",
+        JobFilter::Running => "Running",
+        JobFilter::Success => "Success",
+        JobFilter::Failed => "Failed",
+    };
+    match ui_state.failure_filter {
+        Some(kind) => format!("{}/{}", status, kind.label()),
+        None => status.to_string(),
+    }
+}
+
 /// Sort mode for job list
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum SortMode {
@@ -74,6 +90,8 @@ struct UiState {
     
     // Filtering and sorting
     filter: JobFilter,
+    /// Only show jobs that failed (or were rejected) with this kind
+    failure_filter: Option<FailureKind>,
     sort_mode: SortMode,
     
     // View mode
@@ -90,6 +108,7 @@ impl Default for UiState {
             selected_index: None,
             scroll_offset: 0,
             filter: JobFilter::All,
+            failure_filter: None,
             sort_mode: SortMode::ByDate,
             view_mode: ViewMode::Normal,
             detail_view_job_id: None,
@@ -288,6 +307,7 @@ struct ColorScheme {
     success: Color,
     failed: Color,
     skipped: Color,
+    awaiting_approval: Color,
     
     // UI element colors
     border_normal: Color,
@@ -317,6 +337,7 @@ impl Default for ColorScheme {
             success: Color::Blue,
             failed: Color::Red,
             skipped: Color::Gray,
+            awaiting_approval: Color::Magenta,
             
             border_normal: Color::DarkGray,
             border_selected: Color::Cyan,
@@ -346,6 +367,7 @@ impl ColorScheme {
             JobStatus::Success => self.success,
             JobStatus::Failed => self.failed,
             JobStatus::Skipped => self.skipped,
+            JobStatus::AwaitingApproval => self.awaiting_approval,
         }
     }
     
@@ -732,6 +754,31 @@ impl JobProgress {
             None
         }
     }
+    
+    /// Overwrite heuristic estimates with exact progress reported by the daemon
+    /// (parsed from FFmpeg's -progress output)
+    fn apply_encode_progress(&mut self, encode: &EncodeProgress, now: DateTime<Utc>) {
+        if let Some(percent) = encode.percent {
+            self.progress_percent = percent;
+            if let (Some(frame), true) = (encode.frame, percent > 0.0) {
+                self.total_frames = Some((frame as f64 * 100.0 / percent) as u64);
+            }
+        }
+        if let Some(eta) = encode.eta_secs {
+            self.estimated_completion = Some(now + chrono::Duration::seconds(eta as i64));
+        }
+        if encode.frame.is_some() {
+            self.frames_processed = encode.frame;
+        }
+        if encode.fps.is_some() {
+            self.current_fps = encode.fps;
+        }
+        self.stage = if encode.finished {
+            JobStage::Verifying
+        } else {
+            JobStage::Transcoding
+        };
+    }
 }
 
 /// Validate that job has all required metadata for estimation
@@ -844,6 +891,7 @@ fn parse_frame_rate(frame_rate_str: &str) -> Option<f64> {
 struct App {
     // Core data
     jobs: Vec<Job>,
+    daemon_state: DaemonState,
     system: System,
     
     // UI state
@@ -855,9 +903,11 @@ struct App {
     estimated_savings_cache: HashMap<String, Option<(f64, f64)>>,
     
     // Configuration
+    store: Arc<dyn JobStore>,
     job_state_dir: PathBuf,
     command_dir: PathBuf,
     temp_output_dir: PathBuf,
+    container_policy: ContainerPolicy,
     
     // Timing and status
     last_refresh: DateTime<Utc>,
@@ -875,14 +925,11 @@ struct App {
 impl App {
     /// Generate temp output path using configured temp_output_dir (same logic as daemon)
     fn get_temp_output_path(&self, source_path: &Path) -> PathBuf {
-        let filename = source_path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("unknown");
-        self.temp_output_dir.join(format!("{}.tmp.av1.mkv", filename))
+        let plan = self.container_policy.plan(source_path);
+        container::temp_output_path(&self.temp_output_dir, source_path, &plan)
     }
     
-    fn new(job_state_dir: PathBuf, temp_output_dir: PathBuf) -> Self {
+    fn new(store: Arc<dyn JobStore>, job_state_dir: PathBuf, temp_output_dir: PathBuf) -> Self {
         // Derive command_dir from job_state_dir
         let command_dir = job_state_dir.parent()
             .map(|p| p.join("commands"))
@@ -890,14 +937,17 @@ impl App {
         
         Self {
             jobs: Vec::new(),
+            daemon_state: DaemonState::default(),
             system: System::new(),
             ui_state: UiState::default(),
             job_progress: HashMap::new(),
             statistics_cache: StatisticsCache::default(),
             estimated_savings_cache: HashMap::new(),
+            store,
             job_state_dir,
             command_dir,
             temp_output_dir,
+            container_policy: ContainerPolicy::default(),
             last_refresh: Utc::now(),
             last_job_count: 0,
             last_message: None,
@@ -909,13 +959,30 @@ impl App {
     
     /// Filter jobs based on the current filter setting
     fn filter_jobs<'a>(&'a self, jobs: &'a [Job]) -> Vec<&'a Job> {
-        match self.ui_state.filter {
+        let mut filtered: Vec<&Job> = match self.ui_state.filter {
             JobFilter::All => jobs.iter().collect(),
             JobFilter::Pending => jobs.iter().filter(|j| j.status == JobStatus::Pending).collect(),
             JobFilter::Running => jobs.iter().filter(|j| j.status == JobStatus::Running).collect(),
             JobFilter::Success => jobs.iter().filter(|j| j.status == JobStatus::Success).collect(),
             JobFilter::Failed => jobs.iter().filter(|j| j.status == JobStatus::Failed).collect(),
+        };
+        if let Some(kind) = self.ui_state.failure_filter {
+            filtered.retain(|j| j.failure_kind() == Some(kind));
         }
+        filtered
+    }
+    
+    /// Cycle the failure kind filter through the kinds present in the job list, then off
+    fn cycle_failure_filter(&mut self) {
+        let present: Vec<FailureKind> = FailureKind::ALL.into_iter()
+            .filter(|kind| self.jobs.iter().any(|j| j.failure_kind() == Some(*kind)))
+            .collect();
+        let next_index = match self.ui_state.failure_filter {
+            None => 0,
+            Some(current) => present.iter().position(|k| *k == current).map_or(0, |i| i + 1),
+        };
+        self.ui_state.failure_filter = present.get(next_index).copied();
+        self.ui_state.selected_index = None;
     }
     
     /// Sort jobs based on the current sort mode
@@ -934,17 +1001,17 @@ impl App {
                 });
             }
             SortMode::ByStatus => {
-                // Sort by status: Running > Failed > Pending > Success > Skipped
+                // Sort by status: Running/AwaitingApproval > Failed > Pending > Success > Skipped
                 jobs.sort_by(|a, b| {
                     let a_priority = match a.status {
-                        JobStatus::Running => 0,
+                        JobStatus::Running | JobStatus::AwaitingApproval => 0,
                         JobStatus::Failed => 1,
                         JobStatus::Pending => 2,
                         JobStatus::Success => 3,
                         JobStatus::Skipped => 4,
                     };
                     let b_priority = match b.status {
-                        JobStatus::Running => 0,
+                        JobStatus::Running | JobStatus::AwaitingApproval => 0,
                         JobStatus::Failed => 1,
                         JobStatus::Pending => 2,
                         JobStatus::Success => 3,
@@ -1122,31 +1189,7 @@ impl App {
         
         match running_job {
             Some(job) => {
-                // Create command directory if it doesn't exist
-                if !self.command_dir.exists() {
-                    std::fs::create_dir_all(&self.command_dir)
-                        .with_context(|| format!("Failed to create command directory: {}", self.command_dir.display()))?;
-                }
-                
-                // Create command file
-                let command_file = self.command_dir.join(format!("requeue-{}.json", job.id));
-                
-                // Use atomic write (write to temp file, then rename)
-                let temp_file = self.command_dir.join(format!(".requeue-{}.json.tmp", job.id));
-                
-                let command = serde_json::json!({
-                    "action": "requeue",
-                    "job_id": job.id,
-                    "reason": "manual_requeue_from_tui",
-                    "timestamp": Utc::now().to_rfc3339(),
-                });
-                
-                std::fs::write(&temp_file, serde_json::to_string_pretty(&command)?)
-                    .with_context(|| format!("Failed to write command file: {}", temp_file.display()))?;
-                
-                std::fs::rename(&temp_file, &command_file)
-                    .with_context(|| format!("Failed to rename command file: {} -> {}", 
-                        temp_file.display(), command_file.display()))?;
+                self.send_command("requeue", &job.id, "manual_requeue_from_tui", None)?;
                 
                 self.last_message = Some(format!("✅ Requeue command sent for job: {}", 
                     job.source_path.file_name().and_then(|n| n.to_str()).unwrap_or("?")));
@@ -1163,6 +1206,142 @@ impl App {
         }
     }
     
+    /// Write a command file for the daemon (atomic: temp file, then rename)
+    fn send_command(&self, action: &str, job_id: &str, reason: &str, amount: Option<u8>) -> Result<()> {
+        // Create command directory if it doesn't exist
+        if !self.command_dir.exists() {
+            std::fs::create_dir_all(&self.command_dir)
+                .with_context(|| format!("Failed to create command directory: {}", self.command_dir.display()))?;
+        }
+        
+        // Create command file
+        let command_file = self.command_dir.join(format!("{}-{}.json", action, job_id));
+        
+        // Use atomic write (write to temp file, then rename)
+        let temp_file = self.command_dir.join(format!(".{}-{}.json.tmp", action, job_id));
+        
+        let mut command = serde_json::json!({
+            "action": action,
+            "job_id": job_id,
+            "reason": reason,
+            "timestamp": Utc::now().to_rfc3339(),
+        });
+        if let Some(amount) = amount {
+            command["amount"] = serde_json::json!(amount);
+        }
+        
+        std::fs::write(&temp_file, serde_json::to_string_pretty(&command)?)
+            .with_context(|| format!("Failed to write command file: {}", temp_file.display()))?;
+        
+        std::fs::rename(&temp_file, &command_file)
+            .with_context(|| format!("Failed to rename command file: {} -> {}", 
+                temp_file.display(), command_file.display()))?;
+        
+        Ok(())
+    }
+    
+    /// Job in the detail view, or the selected row in the table view
+    fn focused_job(&self) -> Option<&Job> {
+        let focused_id = if self.ui_state.view_mode == ViewMode::DetailView {
+            self.ui_state.detail_view_job_id.clone()
+        } else {
+            self.ui_state.selected_index.and_then(|idx| {
+                let mut filtered_jobs = self.filter_jobs(&self.jobs);
+                self.sort_jobs(&mut filtered_jobs);
+                filtered_jobs.get(idx).map(|j| j.id.clone())
+            })
+        };
+        
+        focused_id.and_then(|id| self.jobs.iter().find(|j| j.id == id))
+    }
+    
+    /// Send a cancel command for the focused job if it hasn't finished
+    fn cancel_focused_job(&mut self) -> Result<()> {
+        let target = match self.focused_job() {
+            Some(j) if matches!(j.status, JobStatus::Pending | JobStatus::Running | JobStatus::AwaitingApproval) => {
+                Ok((j.id.clone(), j.source_path.file_name().and_then(|n| n.to_str()).unwrap_or("?").to_string()))
+            }
+            Some(j) => Err(format!("⚠️  Focused job is {:?} and can't be cancelled", j.status)),
+            None => Err("⚠️  No job selected".to_string()),
+        };
+        
+        match target {
+            Ok((job_id, file_name)) => {
+                self.send_command("cancel", &job_id, "manual_cancel_from_tui", None)?;
+                self.last_message = Some(format!("✅ Cancel command sent for job: {}", file_name));
+                self.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+            }
+            Err(message) => {
+                self.last_message = Some(message);
+                self.message_timeout = Some(Utc::now() + chrono::Duration::seconds(3));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Pause the focused job (or the running job), or resume it if it is paused
+    fn toggle_pause_focused_job(&mut self) -> Result<()> {
+        let unfinished = |j: &&Job| matches!(
+            j.status,
+            JobStatus::Pending | JobStatus::Running | JobStatus::AwaitingApproval
+        );
+        let target = self.focused_job()
+            .filter(unfinished)
+            .or_else(|| self.jobs.iter().find(|j| j.status == JobStatus::Running))
+            .map(|j| (j.id.clone(), j.paused, j.source_path.file_name().and_then(|n| n.to_str()).unwrap_or("?").to_string()));
+        
+        match target {
+            Some((job_id, paused, file_name)) => {
+                let action = if paused { "resume" } else { "pause" };
+                self.send_command(action, &job_id, "manual_pause_from_tui", None)?;
+                self.last_message = Some(format!("✅ {} command sent for job: {}", 
+                    if paused { "Resume" } else { "Pause" }, file_name));
+                self.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+            }
+            None => {
+                self.last_message = Some("⚠️  No unfinished job to pause".to_string());
+                self.message_timeout = Some(Utc::now() + chrono::Duration::seconds(3));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Pause the whole daemon (no new jobs, running encodes stopped), or resume it
+    fn toggle_pause_daemon(&mut self) -> Result<()> {
+        let action = if self.daemon_state.paused { "resume_all" } else { "pause_all" };
+        // Daemon-wide action - the job id only names the command file
+        self.send_command(action, "all", "manual_pause_from_tui", None)?;
+        self.last_message = Some(format!("✅ {} command sent", 
+            if self.daemon_state.paused { "Resume daemon" } else { "Pause daemon" }));
+        self.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+        Ok(())
+    }
+    
+    /// Send a test clip decision (approve, lower_crf, slower_preset, reject) to the daemon
+    /// for the focused job; never for a job the user isn't looking at
+    fn send_test_clip_decision(&mut self, action: &str) -> Result<()> {
+        let target = match self.focused_job() {
+            Some(j) if j.status == JobStatus::AwaitingApproval => {
+                Ok((j.id.clone(), j.source_path.file_name().and_then(|n| n.to_str()).unwrap_or("?").to_string()))
+            }
+            Some(_) => Err("⚠️  Focused job is not awaiting approval"),
+            None => Err("⚠️  No job selected"),
+        };
+        
+        match target {
+            Ok((job_id, file_name)) => {
+                self.send_command(action, &job_id, "test_clip_review_from_tui", None)?;
+                self.last_message = Some(format!("✅ Test clip decision '{}' sent for job: {}", action, file_name));
+                self.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+            }
+            Err(message) => {
+                self.last_message = Some(message.to_string());
+                self.message_timeout = Some(Utc::now() + chrono::Duration::seconds(3));
+            }
+        }
+        Ok(())
+    }
+    
     /// Clear message if timeout expired
     fn update_message(&mut self) {
         if let Some(timeout) = self.message_timeout {
@@ -1346,7 +1525,7 @@ impl App {
         
         let now = Utc::now();
         let temp_output = self.get_temp_output_path(&job.source_path);
-        let orig_backup = job.source_path.with_extension("orig.mkv");
+        let orig_backup = container::backup_path(&job.source_path);
         
         // Get original size
         let original_size = job.original_bytes.unwrap_or(0);
@@ -1455,7 +1634,7 @@ impl App {
         self.system.refresh_all();
         
         // Reload jobs
-        match load_all_jobs(&self.job_state_dir) {
+        match self.store.load_all() {
             Ok(jobs) => {
                 self.jobs = jobs;
                 // Sort by creation time (newest first)
@@ -1469,6 +1648,7 @@ impl App {
                 self.last_job_count = 0;
             }
         }
+        self.daemon_state = control::load_daemon_state(&self.job_state_dir);
 
         // Collect all running job data before iterating to avoid borrow checker issues
         let now = Utc::now();
@@ -1625,6 +1805,13 @@ impl App {
                         self.job_progress.insert(job_id.clone(), progress);
                     }
                 }
+                
+                // Exact progress from the daemon takes precedence over the temp file heuristics
+                if let Some(encode) = progress::load_progress(&self.job_state_dir, job_id) {
+                    if let Some(job_progress) = self.job_progress.get_mut(job_id) {
+                        job_progress.apply_encode_progress(&encode, now);
+                    }
+                }
             }
         }
         
@@ -1681,6 +1868,16 @@ impl App {
     
     /// Get activity status based on job state changes and running jobs
     fn get_activity_status(&self) -> (&'static str, Color) {
+        if self.daemon_state.paused {
+            return ("⏸  Paused", self.color_scheme.pending);
+        }
+        if self.daemon_state.outside_window {
+            return ("🌙 Quiet hours", self.color_scheme.pending);
+        }
+        if self.daemon_state.busy_reason.is_some() && !self.jobs.iter().any(|j| j.status == JobStatus::Running) {
+            return ("🔥 Host busy", self.color_scheme.pending);
+        }
+        
         let running_count = self.jobs.iter()
             .filter(|j| j.status == JobStatus::Running)
             .count();
@@ -1722,6 +1919,8 @@ fn main() -> Result<()> {
     
     let cfg = TranscodeConfig::load_config(config_path)
         .context("Failed to load configuration")?;
+    let store = store::open_job_store(&cfg, false)
+        .context("Failed to open job store")?;
 
     // Setup terminal
     crossterm::terminal::enable_raw_mode()?;
@@ -1731,7 +1930,8 @@ fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Create app
-    let mut app = App::new(cfg.job_state_dir.clone(), cfg.temp_output_dir.clone());
+    let mut app = App::new(store, cfg.job_state_dir.clone(), cfg.temp_output_dir.clone());
+    app.container_policy = cfg.container_policy;
 
     // Main event loop with adaptive refresh rate
     loop {
@@ -1781,6 +1981,40 @@ fn main() -> Result<()> {
                             app.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
                         }
                     }
+                    crossterm::event::KeyCode::Char('C') => {
+                        // Cancel the focused (or running) job
+                        if let Err(e) = app.cancel_focused_job() {
+                            app.last_message = Some(format!("❌ Failed to cancel: {}", e));
+                            app.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+                        }
+                    }
+                    crossterm::event::KeyCode::Char('p') => {
+                        // Pause/resume the focused (or running) job
+                        if let Err(e) = app.toggle_pause_focused_job() {
+                            app.last_message = Some(format!("❌ Failed to pause/resume: {}", e));
+                            app.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+                        }
+                    }
+                    crossterm::event::KeyCode::Char('P') => {
+                        // Pause/resume the whole daemon
+                        if let Err(e) = app.toggle_pause_daemon() {
+                            app.last_message = Some(format!("❌ Failed to pause/resume daemon: {}", e));
+                            app.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+                        }
+                    }
+                    // Test clip review keys (jobs awaiting approval)
+                    crossterm::event::KeyCode::Char(c @ ('a' | 'c' | 'e' | 'x')) => {
+                        let action = match c {
+                            'a' => "approve",
+                            'c' => "lower_crf",
+                            'e' => "slower_preset",
+                            _ => "reject",
+                        };
+                        if let Err(e) = app.send_test_clip_decision(action) {
+                            app.last_message = Some(format!("❌ Failed to send test clip decision: {}", e));
+                            app.message_timeout = Some(Utc::now() + chrono::Duration::seconds(5));
+                        }
+                    }
                     // Filter keys (1-5)
                     crossterm::event::KeyCode::Char('1') => {
                         app.ui_state.filter = JobFilter::All;
@@ -1797,6 +2031,9 @@ fn main() -> Result<()> {
                     crossterm::event::KeyCode::Char('5') => {
                         app.ui_state.filter = JobFilter::Failed;
                     }
+                    crossterm::event::KeyCode::Char('f') => {
+                        app.cycle_failure_filter();
+                    }
                     // Sort key (s)
                     crossterm::event::KeyCode::Char('s') => {
                         app.cycle_sort_mode();
@@ -2346,13 +2583,76 @@ fn render_detail_view(f: &mut Frame, app: &App, area: Rect) {
         JobStatus::Success => "✓",
         JobStatus::Failed => "✗",
         JobStatus::Skipped => "⊘",
+        JobStatus::AwaitingApproval => "⏳",
     };
     lines.push(format!("   {} Status: {:?}", status_symbol, job.status));
+    if job.paused {
+        lines.push("   ⏸  Paused: yes (p to resume)".to_string());
+    }
     if let Some(reason) = &job.reason {
         lines.push(format!("   ℹ  Reason: {}", reason));
     }
+    if let Some(kind) = job.failure_kind() {
+        lines.push(format!("   ⚠  Failure: {}", kind.label()));
+    }
+    if !job.dropped_tracks.is_empty() {
+        let dropped: Vec<String> = job.dropped_tracks.iter().map(|t| t.to_string()).collect();
+        lines.push(format!("   🔇 Dropped tracks: {}", dropped.join(", ")));
+    }
+    if let Some(dv) = &job.dolby_vision {
+        lines.push(format!("   🎞 Dolby Vision: {}", dv));
+    }
+    if let Some(hdr10_plus) = &job.hdr10_plus {
+        lines.push(format!("   ✨ HDR10+: {}", hdr10_plus));
+    }
+    if let Some(crop) = &job.crop {
+        lines.push(format!("   ✂  Crop: {}", crop));
+    }
+    if let Some(scan_type) = &job.scan_type {
+        lines.push(format!("   ☰  Scan: {}", scan_type));
+    }
+    if let Some(scale) = &job.scale {
+        lines.push(format!("   ⇲  Scaled to: {}", scale));
+    }
+    if let Some(score) = &job.quality_score {
+        lines.push(format!("   📏 Quality: {}", score));
+    }
+    if !job.attempts.is_empty() {
+        lines.push(format!("   🔁 Attempts: {} ({} failed)", job.attempts.len(), job.failed_attempts()));
+    }
+    if let Some(retry_at) = job.retry_at {
+        lines.push(format!("   ⏲  Retry at: {}", retry_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")));
+    }
     lines.push("".to_string());
     
+    // Test clip review (REMUX sources) - shown while a clip exists or decisions were made
+    if job.test_clip_path.is_some() || job.test_clip_encoded_path.is_some() || !job.test_clip_decisions.is_empty() {
+        lines.push("🎞  TEST CLIP:".to_string());
+        if let Some(clip) = &job.test_clip_path {
+            lines.push(format!("   Original: {}", clip.display()));
+        }
+        if let Some(encoded) = &job.test_clip_encoded_path {
+            lines.push(format!("   Encoded:  {}", encoded.display()));
+        }
+        if let (Some(crf), Some(preset)) = (job.crf_used, job.preset_used) {
+            lines.push(format!("   Proposed: CRF {} / Preset {}", crf, preset));
+        }
+        if let Some(requested) = job.approval_requested_at {
+            let waiting = (Utc::now() - requested).num_minutes();
+            lines.push(format!("   Waiting:  {}m (since {})", waiting, requested.format("%Y-%m-%d %H:%M:%S UTC")));
+        }
+        if !job.test_clip_decisions.is_empty() {
+            let decisions: Vec<String> = job.test_clip_decisions.iter()
+                .map(|d| format!("{:?}", d))
+                .collect();
+            lines.push(format!("   Decisions: {}", decisions.join(" → ")));
+        }
+        if job.status == JobStatus::AwaitingApproval {
+            lines.push("   Review: a=approve c=lower CRF e=slower preset x=reject".to_string());
+        }
+        lines.push("".to_string());
+    }
+    
     // Job history (Task 9.3) with Unicode symbols and improved spacing
     lines.push("🕐 JOB HISTORY:".to_string());
     lines.push(format!("   Created:  {}", job.created_at.format("%Y-%m-%d %H:%M:%S UTC")));
@@ -2707,11 +3007,13 @@ fn render_job_table(f: &mut Frame, app: &mut App, area: Rect) {
             .map(|(row_idx, job)| {
                 // Pre-calculate all possible cell values with Unicode symbols
                 let status_str = match job.status {
+                    _ if job.paused => "⏸ HOLD",
                     JobStatus::Pending => "⏸ PEND",
                     JobStatus::Running => "⚙ RUN",
                     JobStatus::Success => "✓ OK",
                     JobStatus::Failed => "✗ FAIL",
                     JobStatus::Skipped => "⊘ SKIP",
+                    JobStatus::AwaitingApproval => "⏳ WAIT",
                 };
 
                 let file_name = job.source_path
@@ -2840,10 +3142,14 @@ fn render_job_table(f: &mut Frame, app: &mut App, area: Rect) {
                 };
 
                 // Build row cells dynamically based on visible columns
-                let cells: Vec<String> = layout_config.table_columns.iter()
+                // (the status cell is coloured by job status)
+                let status_color = app.color_scheme.status_color(&job.status);
+                let cells: Vec<Cell> = layout_config.table_columns.iter()
                     .map(|col| {
-                        match col {
-                            TableColumn::Status => status_str.to_string(),
+                        let text = match col {
+                            TableColumn::Status => {
+                                return Cell::from(status_str).style(Style::default().fg(status_color));
+                            }
                             TableColumn::File => file_name.clone(),
                             TableColumn::Resolution => resolution.clone(),
                             TableColumn::Codec => codec.clone(),
@@ -2857,11 +3163,17 @@ fn render_job_table(f: &mut Frame, app: &mut App, area: Rect) {
                             TableColumn::Savings => savings.clone(),
                             TableColumn::Time => duration.clone(),
                             TableColumn::Reason => reason.clone(),
-                        }
+                        };
+                        Cell::from(text)
                     })
                     .collect();
 
                 let mut row = Row::new(cells).height(1);
+
+                // Jobs waiting for test clip approval stand out from the rest
+                if job.status == JobStatus::AwaitingApproval {
+                    row = row.style(Style::default().fg(app.color_scheme.awaiting_approval));
+                }
                 
                 // Apply selection highlighting
                 if let Some(selected_idx) = app.ui_state.selected_index {
@@ -2883,13 +3195,7 @@ fn render_job_table(f: &mut Frame, app: &mut App, area: Rect) {
         .map(|col| col.width())
         .collect();
 
-    let filter_name = match app.ui_state.filter {
-        JobFilter::All => "All",
-        JobFilter::Pending => "Pending",
-        JobFilter::Running => "Running",
-        JobFilter::Success => "Success",
-        JobFilter::Failed => "Failed",
-    };
+    let filter_name = filter_label(&app.ui_state);
     
     let sort_name = match app.ui_state.sort_mode {
         SortMode::ByDate => "Date",
@@ -3179,13 +3485,7 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
     };
     
     // Task 12.2: Display current filter and sort mode with distinct formatting
-    let filter_name = match app.ui_state.filter {
-        JobFilter::All => "All",
-        JobFilter::Pending => "Pending",
-        JobFilter::Running => "Running",
-        JobFilter::Success => "Success",
-        JobFilter::Failed => "Failed",
-    };
+    let filter_name = filter_label(&app.ui_state);
     
     let sort_name = match app.ui_state.sort_mode {
         SortMode::ByDate => "Date",
@@ -3220,7 +3520,7 @@ fn render_status_bar(f: &mut Frame, app: &App, area: Rect) {
     
     // Task 12.1: Group shortcuts by category with clear separators
     let line2 = format!(
-        "  Navigation: ↑↓=move PgUp/PgDn=page │ Filters: 1=all 2=pend 3=run 4=ok 5=fail │ Actions: s=sort Enter=details r=refresh R=requeue q=quit │ Dir: {}",
+        "  Navigation: ↑↓=move PgUp/PgDn=page │ Filters: 1=all 2=pend 3=run 4=ok 5=fail f=failure kind │ Actions: s=sort Enter=details r=refresh R=requeue C=cancel p=pause P=pause all q=quit │ Clip: a=approve c=crf- e=slower x=reject │ Dir: {}",
         dir_short
     );
     
@@ -3253,6 +3553,7 @@ fn truncate_string(s: &str, max_len: usize) -> String {
 mod tests {
     use super::*;
     use proptest::prelude::*;
+    use daemon::store::JsonDirStore;
     use std::path::PathBuf;
     
     // Unit tests for statistics calculation
@@ -3457,6 +3758,20 @@ mod tests {
             encoder_used: None,
             test_clip_path: None,
             test_clip_approved: None,
+            test_clip_encoded_path: None,
+            approval_requested_at: None,
+            test_clip_decisions: Vec::new(),
+            paused: false,
+            attempts: Vec::new(),
+            retry_at: None,
+            failure: None,
+            dropped_tracks: Vec::new(),
+            dolby_vision: None,
+            hdr10_plus: None,
+            crop: None,
+            scan_type: None,
+            scale: None,
+            quality_score: None,
         }
     }
     
@@ -3468,6 +3783,7 @@ mod tests {
             Just(JobStatus::Success),
             Just(JobStatus::Failed),
             Just(JobStatus::Skipped),
+            Just(JobStatus::AwaitingApproval),
         ]
     }
     
@@ -3507,6 +3823,20 @@ mod tests {
                 encoder_used: None,
                 test_clip_path: None,
                 test_clip_approved: None,
+                test_clip_encoded_path: None,
+                approval_requested_at: None,
+                test_clip_decisions: Vec::new(),
+                paused: false,
+                attempts: Vec::new(),
+                retry_at: None,
+                failure: None,
+                dropped_tracks: Vec::new(),
+                dolby_vision: None,
+                hdr10_plus: None,
+                crop: None,
+                scan_type: None,
+                scale: None,
+                quality_score: None,
             }
         })
     }
@@ -3523,11 +3853,15 @@ mod tests {
             ("q", "Quit application"),
             ("r", "Refresh data"),
             ("R", "Requeue running job"),
+            ("C", "Cancel job"),
+            ("p", "Pause/resume job"),
+            ("P", "Pause/resume daemon"),
             ("1", "Filter: All jobs"),
             ("2", "Filter: Pending jobs"),
             ("3", "Filter: Running jobs"),
             ("4", "Filter: Success jobs"),
             ("5", "Filter: Failed jobs"),
+            ("f", "Cycle failure kind filter"),
             ("s", "Cycle sort mode"),
             ("Up", "Move selection up"),
             ("Down", "Move selection down"),
@@ -3535,6 +3869,10 @@ mod tests {
             ("PageDown", "Move selection page down"),
             ("Enter", "Open/close detail view"),
             ("Esc", "Close detail view"),
+            ("a", "Approve test clip"),
+            ("c", "Test clip: lower CRF"),
+            ("e", "Test clip: slower preset"),
+            ("x", "Reject test clip"),
         ];
         
         // Verify that all shortcuts are unique
@@ -3649,7 +3987,7 @@ mod tests {
             filter_choice in 0..5usize,
         ) {
             // Create app with test data
-            let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+            let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
             app.jobs = jobs;
             
             // Set filter based on choice
@@ -3712,7 +4050,7 @@ mod tests {
             sort_choice in 0..4usize,
         ) {
             // Create app with test data
-            let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+            let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
             app.jobs = jobs;
             
             // Pre-populate estimated savings cache for BySavings sort
@@ -3760,16 +4098,16 @@ mod tests {
                         );
                     }
                     SortMode::ByStatus => {
-                        // Running > Failed > Pending > Success > Skipped
+                        // Running/AwaitingApproval > Failed > Pending > Success > Skipped
                         let current_priority = match current.status {
-                            JobStatus::Running => 0,
+                            JobStatus::Running | JobStatus::AwaitingApproval => 0,
                             JobStatus::Failed => 1,
                             JobStatus::Pending => 2,
                             JobStatus::Success => 3,
                             JobStatus::Skipped => 4,
                         };
                         let next_priority = match next.status {
-                            JobStatus::Running => 0,
+                            JobStatus::Running | JobStatus::AwaitingApproval => 0,
                             JobStatus::Failed => 1,
                             JobStatus::Pending => 2,
                             JobStatus::Success => 3,
@@ -3819,7 +4157,7 @@ mod tests {
             jobs in prop::collection::vec(job_strategy(), 0..50),
         ) {
             // Create app with test data
-            let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+            let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
             
             // Set up jobs with actual savings data
             let mut modified_jobs = jobs;
@@ -3885,7 +4223,7 @@ mod tests {
             operations in prop::collection::vec(0..4usize, 0..20),
         ) {
             // Create app with test data
-            let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+            let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
             app.jobs = jobs;
             
             // Get filtered job count
@@ -4026,7 +4364,7 @@ mod tests {
             jobs in prop::collection::vec(job_strategy(), 1..10),
         ) {
             // Create app with test data
-            let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+            let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
             
             // Create a job with all metadata fields populated
             let mut complete_job = create_test_job("complete_job", JobStatus::Success);
@@ -4512,6 +4850,7 @@ mod tests {
                 JobStatus::Success => color_scheme.success,
                 JobStatus::Failed => color_scheme.failed,
                 JobStatus::Skipped => color_scheme.skipped,
+                JobStatus::AwaitingApproval => color_scheme.awaiting_approval,
             };
             
             prop_assert_eq!(
@@ -4527,13 +4866,14 @@ mod tests {
                 JobStatus::Running,
                 JobStatus::Success,
                 JobStatus::Failed,
+                JobStatus::AwaitingApproval,
             ];
             
             let colors: Vec<Color> = all_statuses.iter()
                 .map(|s| color_scheme.status_color(s))
                 .collect();
             
-            // Check that primary statuses (Pending, Running, Success, Failed) have distinct colors
+            // Check that primary statuses (Pending, Running, Success, Failed, AwaitingApproval) have distinct colors
             for i in 0..colors.len() {
                 for j in (i+1)..colors.len() {
                     prop_assert!(
@@ -4552,7 +4892,7 @@ mod tests {
     #[test]
     fn test_full_rendering_pipeline() {
         // Create app with test data
-        let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+        let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
         
         // Test 1: Empty job list
         app.jobs = vec![];
@@ -4623,7 +4963,7 @@ mod tests {
     /// Test all keyboard shortcuts and their effects on app state
     #[test]
     fn test_keyboard_shortcuts() {
-        let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+        let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
         
         // Create test jobs
         let mut jobs = vec![];
@@ -4729,7 +5069,7 @@ mod tests {
     /// Test state transitions between different view modes and filters
     #[test]
     fn test_state_transitions() {
-        let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+        let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
         
         // Create test jobs with various statuses
         let mut jobs = vec![];
@@ -4819,7 +5159,7 @@ mod tests {
     /// Test refresh cycle and data updates
     #[test]
     fn test_refresh_cycle() {
-        let mut app = App::new(PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+        let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
         
         // Initial state
         assert_eq!(app.jobs.len(), 0);
@@ -4916,4 +5256,81 @@ mod tests {
         // Cache should be cleaned up
         assert!(!app.estimated_savings_cache.contains_key(&pending_job.id));
     }
+
+    #[test]
+    fn test_failure_kind_filter() {
+        use daemon::failure::JobFailure;
+
+        let mut app = App::new(Arc::new(JsonDirStore::new("/tmp/test")), PathBuf::from("/tmp/test"), PathBuf::from("/tmp/test/output"));
+        let mut io_failed = create_test_job("io", JobStatus::Failed);
+        io_failed.failure = Some(JobFailure::Io { message: "disk full".to_string() });
+        let mut rejected = create_test_job("gate", JobStatus::Skipped);
+        rejected.failure = Some(JobFailure::SizeGate { new_bytes: 2, original_bytes: 1, max_size_ratio: 0.9 });
+        app.jobs = vec![io_failed, rejected, create_test_job("ok", JobStatus::Success)];
+
+        // Cycles through the kinds present, then back to no filter
+        app.cycle_failure_filter();
+        assert_eq!(app.ui_state.failure_filter, Some(FailureKind::Io));
+        assert_eq!(app.filter_jobs(&app.jobs).len(), 1);
+        assert_eq!(filter_label(&app.ui_state), "All/io");
+
+        app.cycle_failure_filter();
+        assert_eq!(app.ui_state.failure_filter, Some(FailureKind::SizeGate));
+        let filtered = app.filter_jobs(&app.jobs);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].id, "gate");
+
+        // Combines with the status filter
+        app.ui_state.filter = JobFilter::Failed;
+        assert!(app.filter_jobs(&app.jobs).is_empty());
+
+        app.cycle_failure_filter();
+        assert_eq!(app.ui_state.failure_filter, None);
+    }
+
+    #[test]
+    fn test_test_clip_decision_only_targets_focused_job() {
+        let state_dir = std::env::temp_dir().join(format!("av1top-decision-test-{}", std::process::id())).join("jobs");
+        let mut app = App::new(Arc::new(JsonDirStore::new(&state_dir)), state_dir.clone(), PathBuf::from("/tmp/test/output"));
+        app.jobs = vec![
+            create_test_job("waiting", JobStatus::AwaitingApproval),
+            create_test_job("done", JobStatus::Success),
+        ];
+
+        // The focused job isn't waiting, so the other one must not be rejected instead
+        app.ui_state.view_mode = ViewMode::DetailView;
+        app.ui_state.detail_view_job_id = Some("done".to_string());
+        app.send_test_clip_decision("reject").unwrap();
+        assert_eq!(app.last_message.as_deref(), Some("⚠️  Focused job is not awaiting approval"));
+        assert!(!app.command_dir.exists());
+
+        app.ui_state.detail_view_job_id = Some("waiting".to_string());
+        app.send_test_clip_decision("reject").unwrap();
+        assert!(app.command_dir.join("reject-waiting.json").exists());
+
+        std::fs::remove_dir_all(state_dir.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn test_cancel_only_targets_focused_job() {
+        let state_dir = std::env::temp_dir().join(format!("av1top-cancel-test-{}", std::process::id())).join("jobs");
+        let mut app = App::new(Arc::new(JsonDirStore::new(&state_dir)), state_dir.clone(), PathBuf::from("/tmp/test/output"));
+        app.jobs = vec![
+            create_test_job("running", JobStatus::Running),
+            create_test_job("done", JobStatus::Success),
+        ];
+
+        // A finished focused job must not cancel the running one instead
+        app.ui_state.view_mode = ViewMode::DetailView;
+        app.ui_state.detail_view_job_id = Some("done".to_string());
+        app.cancel_focused_job().unwrap();
+        assert_eq!(app.last_message.as_deref(), Some("⚠️  Focused job is Success and can't be cancelled"));
+        assert!(!app.command_dir.exists());
+
+        app.ui_state.detail_view_job_id = Some("running".to_string());
+        app.cancel_focused_job().unwrap();
+        assert!(app.command_dir.join("cancel-running.json").exists());
+
+        std::fs::remove_dir_all(state_dir.parent().unwrap()).ok();
+    }
 }
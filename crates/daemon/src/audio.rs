@@ -0,0 +1,337 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::classifier::QualityTier;
+use crate::ffprobe::FFProbeStream;
+
+/// What happens to lossless audio tracks (TrueHD, DTS-HD MA, FLAC, PCM, ...)
+///
+/// Lossy tracks (AC3, DTS core, AAC, ...) are always copied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AudioMode {
+    /// Copy every audio track as-is
+    #[default]
+    Copy,
+    /// Replace lossless tracks with a lossy encode
+    TranscodeLossless,
+    /// Keep lossless tracks and add a lossy compatibility track for each of them
+    AddCompatibility,
+}
+
+/// Lossy codec used for transcoded and compatibility tracks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioCodec {
+    #[default]
+    Opus,
+    Eac3,
+}
+
+impl AudioCodec {
+    /// FFmpeg encoder name
+    pub fn ffmpeg_name(self) -> &'static str {
+        match self {
+            AudioCodec::Opus => "libopus",
+            AudioCodec::Eac3 => "eac3",
+        }
+    }
+
+    /// Most channels the encoder takes; wider sources are downmixed
+    pub fn max_channels(self) -> i32 {
+        match self {
+            AudioCodec::Opus => 8,
+            AudioCodec::Eac3 => 6,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AudioCodec::Opus => "Opus",
+            AudioCodec::Eac3 => "E-AC-3",
+        }
+    }
+}
+
+/// Target bitrate in kbps per channel layout
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioBitrates {
+    /// 1 channel (default: 96)
+    #[serde(default = "default_mono")]
+    pub mono: u32,
+    /// 2 channels (default: 160)
+    #[serde(default = "default_stereo")]
+    pub stereo: u32,
+    /// 3-6 channels, e.g. 5.1 (default: 384)
+    #[serde(default = "default_surround")]
+    pub surround: u32,
+    /// 7-8 channels, e.g. 7.1 (default: 512)
+    #[serde(default = "default_surround_7_1")]
+    pub surround_7_1: u32,
+}
+
+fn default_mono() -> u32 {
+    96
+}
+
+fn default_stereo() -> u32 {
+    160
+}
+
+fn default_surround() -> u32 {
+    384
+}
+
+fn default_surround_7_1() -> u32 {
+    512
+}
+
+impl Default for AudioBitrates {
+    fn default() -> Self {
+        Self {
+            mono: default_mono(),
+            stereo: default_stereo(),
+            surround: default_surround(),
+            surround_7_1: default_surround_7_1(),
+        }
+    }
+}
+
+impl AudioBitrates {
+    /// Bitrate for an output with the given channel count
+    pub fn for_channels(&self, channels: i32) -> u32 {
+        match channels {
+            ..=1 => self.mono,
+            2 => self.stereo,
+            3..=6 => self.surround,
+            _ => self.surround_7_1,
+        }
+    }
+}
+
+/// Audio handling for one quality tier
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioRule {
+    #[serde(default)]
+    pub mode: AudioMode,
+    #[serde(default)]
+    pub codec: AudioCodec,
+    #[serde(default)]
+    pub bitrates: AudioBitrates,
+}
+
+/// Per-tier audio rules (default: copy all audio in every tier)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AudioPolicy {
+    #[serde(default)]
+    pub remux: AudioRule,
+    #[serde(default)]
+    pub web_dl: AudioRule,
+    #[serde(default)]
+    pub low_quality: AudioRule,
+}
+
+impl AudioPolicy {
+    /// Rule for a quality tier
+    pub fn rule(&self, tier: &QualityTier) -> &AudioRule {
+        match tier {
+            QualityTier::Remux => &self.remux,
+            QualityTier::WebDl => &self.web_dl,
+            QualityTier::LowQuality => &self.low_quality,
+        }
+    }
+
+    /// Check that every bitrate is usable
+    pub fn validate(&self) -> Result<()> {
+        for (name, rule) in [("remux", &self.remux), ("web_dl", &self.web_dl), ("low_quality", &self.low_quality)] {
+            let b = &rule.bitrates;
+            if [b.mono, b.stereo, b.surround, b.surround_7_1].contains(&0) {
+                return Err(anyhow!("{}: audio bitrates must be greater than 0", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How one output audio stream is produced
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AudioAction {
+    Copy,
+    Encode {
+        codec: AudioCodec,
+        bitrate_kbps: u32,
+        /// Output channel count (the source's, capped at what the codec takes)
+        channels: i32,
+    },
+}
+
+/// One output audio stream, in output order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioOutput {
+    /// Stream index in the source file
+    pub source_index: i32,
+    pub action: AudioAction,
+    /// Extra lossy track added next to a kept lossless original
+    pub compatibility: bool,
+}
+
+impl AudioOutput {
+    /// Short description for logs and track titles (e.g. "Opus 5.1 384k")
+    pub fn describe(&self) -> String {
+        match &self.action {
+            AudioAction::Copy => "copy".to_string(),
+            AudioAction::Encode { codec, bitrate_kbps, channels } => {
+                format!("{} {} {}k", codec.label(), layout_name(*channels), bitrate_kbps)
+            }
+        }
+    }
+}
+
+fn layout_name(channels: i32) -> String {
+    match channels {
+        1 => "mono".to_string(),
+        2 => "stereo".to_string(),
+        6 => "5.1".to_string(),
+        8 => "7.1".to_string(),
+        n => format!("{}ch", n),
+    }
+}
+
+/// Whether an audio stream is lossless (TrueHD/MLP, DTS-HD MA, FLAC, ALAC, PCM)
+pub fn is_lossless(stream: &FFProbeStream) -> bool {
+    match stream.codec_name.as_deref() {
+        Some("truehd" | "mlp" | "flac" | "alac") => true,
+        Some(codec) if codec.starts_with("pcm_") => true,
+        Some("dts") => stream.profile.as_deref().is_some_and(|p| p.starts_with("DTS-HD MA")),
+        _ => false,
+    }
+}
+
+impl AudioRule {
+    /// Output audio streams for the kept source tracks
+    ///
+    /// Returns an empty plan when every track is simply copied, so the encode
+    /// command keeps using a plain `-c:a copy`. Compatibility tracks follow the
+    /// original tracks.
+    pub fn plan(&self, streams: &[FFProbeStream], kept_audio: &[i32]) -> Vec<AudioOutput> {
+        let kept: Vec<&FFProbeStream> = kept_audio.iter()
+            .filter_map(|index| streams.iter().find(|s| s.index == *index))
+            .collect();
+        if self.mode == AudioMode::Copy || !kept.iter().any(|s| is_lossless(s)) {
+            return Vec::new();
+        }
+
+        let encode = |stream: &FFProbeStream, compatibility: bool| {
+            let channels = stream.channels.unwrap_or(2).clamp(1, self.codec.max_channels());
+            AudioOutput {
+                source_index: stream.index,
+                action: AudioAction::Encode {
+                    codec: self.codec,
+                    bitrate_kbps: self.bitrates.for_channels(channels),
+                    channels,
+                },
+                compatibility,
+            }
+        };
+        let copy = |stream: &FFProbeStream| AudioOutput {
+            source_index: stream.index,
+            action: AudioAction::Copy,
+            compatibility: false,
+        };
+
+        let mut outputs: Vec<AudioOutput> = kept.iter()
+            .map(|s| if self.mode == AudioMode::TranscodeLossless && is_lossless(s) {
+                encode(s, false)
+            } else {
+                copy(s)
+            })
+            .collect();
+        if self.mode == AudioMode::AddCompatibility {
+            outputs.extend(kept.iter().filter(|s| is_lossless(s)).map(|s| encode(s, true)));
+        }
+        outputs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn audio(index: i32, codec: &str, profile: Option<&str>, channels: i32) -> FFProbeStream {
+        FFProbeStream {
+            index,
+            codec_type: Some("audio".to_string()),
+            codec_name: Some(codec.to_string()),
+            width: None,
+            height: None,
+            avg_frame_rate: None,
+            r_frame_rate: None,
+            tags: None,
+            bit_rate: None,
+            disposition: None,
+            pix_fmt: None,
+            bits_per_raw_sample: None,
+            color_transfer: None,
+            color_primaries: None,
+            color_space: None,
+            field_order: None,
+            channels: Some(channels),
+            channel_layout: None,
+            profile: profile.map(str::to_string),
+            side_data_list: None,
+        }
+    }
+
+    #[test]
+    fn test_lossless_detection() {
+        assert!(is_lossless(&audio(1, "truehd", None, 8)));
+        assert!(is_lossless(&audio(1, "pcm_s24le", None, 2)));
+        assert!(is_lossless(&audio(1, "dts", Some("DTS-HD MA"), 6)));
+        assert!(!is_lossless(&audio(1, "dts", Some("DTS"), 6)));
+        assert!(!is_lossless(&audio(1, "ac3", None, 6)));
+    }
+
+    #[test]
+    fn test_copy_mode_has_no_plan() {
+        let streams = vec![audio(1, "truehd", None, 8)];
+        assert!(AudioRule::default().plan(&streams, &[1]).is_empty());
+
+        // Nothing lossless to transcode
+        let rule = AudioRule { mode: AudioMode::TranscodeLossless, ..Default::default() };
+        assert!(rule.plan(&[audio(1, "ac3", None, 6)], &[1]).is_empty());
+    }
+
+    #[test]
+    fn test_transcode_lossless() {
+        let streams = vec![audio(1, "truehd", None, 8), audio(2, "ac3", None, 6), audio(3, "flac", None, 2)];
+        let rule = AudioRule { mode: AudioMode::TranscodeLossless, codec: AudioCodec::Eac3, ..Default::default() };
+        let plan = rule.plan(&streams, &[1, 2]);
+        assert_eq!(plan.len(), 2);
+        // E-AC-3 tops out at 5.1
+        assert_eq!(plan[0].action, AudioAction::Encode { codec: AudioCodec::Eac3, bitrate_kbps: 384, channels: 6 });
+        assert_eq!(plan[1].action, AudioAction::Copy);
+        assert_eq!(plan[0].describe(), "E-AC-3 5.1 384k");
+    }
+
+    #[test]
+    fn test_add_compatibility_tracks() {
+        let streams = vec![audio(1, "truehd", None, 8), audio(2, "ac3", None, 6)];
+        let rule = AudioRule { mode: AudioMode::AddCompatibility, ..Default::default() };
+        let plan = rule.plan(&streams, &[1, 2]);
+        let summary: Vec<(i32, bool)> = plan.iter().map(|o| (o.source_index, o.compatibility)).collect();
+        assert_eq!(summary, vec![(1, false), (2, false), (1, true)]);
+        assert_eq!(plan[2].action, AudioAction::Encode { codec: AudioCodec::Opus, bitrate_kbps: 512, channels: 8 });
+    }
+
+    #[test]
+    fn test_policy_from_json() {
+        let policy: AudioPolicy = serde_json::from_str(
+            r#"{"low_quality": {"mode": "transcode_lossless", "codec": "eac3", "bitrates": {"surround": 448}}}"#
+        ).unwrap();
+        assert_eq!(policy.rule(&QualityTier::Remux).mode, AudioMode::Copy);
+        let rule = policy.rule(&QualityTier::LowQuality);
+        assert_eq!(rule.codec, AudioCodec::Eac3);
+        assert_eq!(rule.bitrates.surround, 448);
+        assert_eq!(rule.bitrates.stereo, 160);
+        assert!(policy.validate().is_ok());
+    }
+}
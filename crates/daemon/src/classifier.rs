@@ -66,7 +66,7 @@ impl SourceClassifier {
 
         // Get video stream for analysis
         let video_stream = streams.iter()
-            .find(|s| s.codec_type.as_deref() == Some("video"));
+            .find(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture());
 
         // Calculate bitrate per pixel if possible
         let bitrate_per_pixel = self.calculate_bitrate_per_pixel(format, video_stream);
@@ -299,7 +299,7 @@ impl SourceClassifier {
 
         // Check if video codec is already modern and efficient
         let video_stream = streams.iter()
-            .find(|s| s.codec_type.as_deref() == Some("video"));
+            .find(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture());
 
         if let Some(vs) = video_stream {
             if let Some(ref codec) = vs.codec_name {
@@ -475,7 +475,7 @@ pub fn classify_web_source(
         if let Ok(bitrate) = bitrate_str.parse::<u64>() {
             // Get video resolution for bitrate-per-pixel analysis
             let video_stream = streams.iter()
-                .find(|s| s.codec_type.as_deref() == Some("video"));
+                .find(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture());
             
             if let Some(vs) = video_stream {
                 if let (Some(w), Some(h)) = (vs.width, vs.height) {
@@ -584,6 +584,11 @@ mod tests {
             color_transfer: None,
             color_primaries: None,
             color_space: None,
+            field_order: None,
+            channels: None,
+            channel_layout: None,
+            profile: None,
+            side_data_list: None,
         }
     }
 
@@ -605,6 +610,11 @@ mod tests {
             color_transfer: None,
             color_primaries: None,
             color_space: None,
+            field_order: None,
+            channels: None,
+            channel_layout: None,
+            profile: None,
+            side_data_list: None,
         }
     }
 
@@ -616,6 +626,7 @@ mod tests {
             tags: None,
             muxing_app: None,
             writing_library: None,
+            duration: None,
         }
     }
 
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use crate::ffprobe::FFProbeStream;
+
+/// A value ffprobe reports as a fraction (e.g. "34000/50000")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    pub num: i64,
+    pub den: i64,
+}
+
+impl Rational {
+    /// Parse "num/den", a plain integer, or a JSON number
+    pub fn parse(value: &serde_json::Value) -> Option<Self> {
+        if let Some(num) = value.as_i64() {
+            return Some(Rational { num, den: 1 });
+        }
+        let (num, den) = value.as_str()?.split_once('/')?;
+        let den: i64 = den.trim().parse().ok()?;
+        if den == 0 {
+            return None;
+        }
+        Some(Rational { num: num.trim().parse().ok()?, den })
+    }
+
+    pub fn value(self) -> f64 {
+        self.num as f64 / self.den as f64
+    }
+}
+
+/// CIE 1931 xy coordinates of a display primary or white point
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chromaticity {
+    pub x: Rational,
+    pub y: Rational,
+}
+
+/// SMPTE ST 2086 mastering display colour volume
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MasteringDisplay {
+    pub red: Chromaticity,
+    pub green: Chromaticity,
+    pub blue: Chromaticity,
+    pub white_point: Chromaticity,
+    /// cd/m²
+    pub min_luminance: Rational,
+    /// cd/m²
+    pub max_luminance: Rational,
+}
+
+impl MasteringDisplay {
+    /// Parse ffprobe's "Mastering display metadata" side data
+    pub fn from_side_data(sd: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        let field = |name: &str| sd.get(name).and_then(Rational::parse);
+        let xy = |prefix: &str| Some(Chromaticity {
+            x: field(&format!("{}_x", prefix))?,
+            y: field(&format!("{}_y", prefix))?,
+        });
+        Some(MasteringDisplay {
+            red: xy("red")?,
+            green: xy("green")?,
+            blue: xy("blue")?,
+            white_point: xy("white_point")?,
+            min_luminance: field("min_luminance")?,
+            max_luminance: field("max_luminance")?,
+        })
+    }
+
+    /// SVT-AV1 `mastering-display` value: `G(x,y)B(x,y)R(x,y)WP(x,y)L(max,min)`
+    pub fn svt_param(&self) -> String {
+        let xy = |c: &Chromaticity| format!("({:.4},{:.4})", c.x.value(), c.y.value());
+        format!("G{}B{}R{}WP{}L({:.4},{:.4})",
+                xy(&self.green), xy(&self.blue), xy(&self.red), xy(&self.white_point),
+                self.max_luminance.value(), self.min_luminance.value())
+    }
+}
+
+/// CTA-861.3 content light level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentLightLevel {
+    /// MaxCLL in cd/m²
+    pub max_cll: u32,
+    /// MaxFALL in cd/m²
+    pub max_fall: u32,
+}
+
+impl ContentLightLevel {
+    /// Parse ffprobe's "Content light level metadata" side data
+    pub fn from_side_data(sd: &HashMap<String, serde_json::Value>) -> Option<Self> {
+        let field = |name: &str| sd.get(name)?.as_u64().and_then(|v| u32::try_from(v).ok());
+        Some(ContentLightLevel {
+            max_cll: field("max_content")?,
+            max_fall: field("max_average")?,
+        })
+    }
+}
+
+/// Colour description of the source video carried into the encode
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColorMetadata {
+    /// FFmpeg `-color_primaries` name (e.g. "bt2020")
+    pub primaries: Option<String>,
+    /// FFmpeg `-color_trc` name (e.g. "smpte2084")
+    pub transfer: Option<String>,
+    /// FFmpeg `-colorspace` name (e.g. "bt2020nc")
+    pub matrix: Option<String>,
+    pub mastering_display: Option<MasteringDisplay>,
+    pub content_light: Option<ContentLightLevel>,
+}
+
+/// ffprobe colour property, unless it is unset
+fn known(value: &Option<String>) -> Option<String> {
+    value.as_deref()
+        .filter(|v| !matches!(*v, "unknown" | "unspecified" | "reserved" | ""))
+        .map(str::to_string)
+}
+
+impl ColorMetadata {
+    /// Colour description of a video stream (None if ffprobe reported none)
+    pub fn from_stream(stream: &FFProbeStream) -> Option<Self> {
+        let color = ColorMetadata {
+            primaries: known(&stream.color_primaries),
+            transfer: known(&stream.color_transfer),
+            matrix: known(&stream.color_space),
+            mastering_display: stream.mastering_display(),
+            content_light: stream.content_light_level(),
+        };
+        (color != ColorMetadata::default()).then_some(color)
+    }
+
+    /// Colour tags (primaries, transfer, matrix) the output stream lost or changed
+    pub fn lost_tags(&self, output: &FFProbeStream) -> Vec<&'static str> {
+        let mut lost = Vec::new();
+        for (name, expected, actual) in [
+            ("color primaries", &self.primaries, &output.color_primaries),
+            ("transfer", &self.transfer, &output.color_transfer),
+            ("matrix", &self.matrix, &output.color_space),
+        ] {
+            if expected.is_some() && known(actual) != *expected {
+                lost.push(name);
+            }
+        }
+        lost
+    }
+
+    /// HDR10 static metadata missing from the output stream
+    pub fn lost_static_metadata(&self, output: &FFProbeStream) -> Vec<&'static str> {
+        let mut lost = Vec::new();
+        if self.mastering_display.is_some() && output.mastering_display().is_none() {
+            lost.push("mastering display");
+        }
+        if self.content_light.is_some() && output.content_light_level().is_none() {
+            lost.push("content light level");
+        }
+        lost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn hdr10_stream() -> FFProbeStream {
+        let side_data = vec![
+            HashMap::from([
+                ("side_data_type".to_string(), json!("Mastering display metadata")),
+                ("red_x".to_string(), json!("34000/50000")),
+                ("red_y".to_string(), json!("16000/50000")),
+                ("green_x".to_string(), json!("13250/50000")),
+                ("green_y".to_string(), json!("34500/50000")),
+                ("blue_x".to_string(), json!("7500/50000")),
+                ("blue_y".to_string(), json!("3000/50000")),
+                ("white_point_x".to_string(), json!("15635/50000")),
+                ("white_point_y".to_string(), json!("16450/50000")),
+                ("min_luminance".to_string(), json!("50/10000")),
+                ("max_luminance".to_string(), json!("10000000/10000")),
+            ]),
+            HashMap::from([
+                ("side_data_type".to_string(), json!("Content light level metadata")),
+                ("max_content".to_string(), json!(1000)),
+                ("max_average".to_string(), json!(400)),
+            ]),
+        ];
+        FFProbeStream {
+            index: 0,
+            codec_type: Some("video".to_string()),
+            codec_name: Some("hevc".to_string()),
+            width: Some(3840),
+            height: Some(2160),
+            avg_frame_rate: None,
+            r_frame_rate: None,
+            tags: None,
+            bit_rate: None,
+            disposition: None,
+            pix_fmt: Some("yuv420p10le".to_string()),
+            bits_per_raw_sample: None,
+            color_transfer: Some("smpte2084".to_string()),
+            color_primaries: Some("bt2020".to_string()),
+            color_space: Some("bt2020nc".to_string()),
+            field_order: None,
+            channels: None,
+            channel_layout: None,
+            profile: None,
+            side_data_list: Some(side_data),
+        }
+    }
+
+    #[test]
+    fn test_hdr10_metadata_from_stream() {
+        let color = ColorMetadata::from_stream(&hdr10_stream()).unwrap();
+        assert_eq!(color.transfer.as_deref(), Some("smpte2084"));
+        assert_eq!(color.content_light, Some(ContentLightLevel { max_cll: 1000, max_fall: 400 }));
+        assert_eq!(color.mastering_display.unwrap().svt_param(),
+                   "G(0.2650,0.6900)B(0.1500,0.0600)R(0.6800,0.3200)WP(0.3127,0.3290)L(1000.0000,0.0050)");
+    }
+
+    #[test]
+    fn test_unset_colors_are_ignored() {
+        let mut stream = hdr10_stream();
+        stream.color_transfer = Some("unknown".to_string());
+        stream.color_primaries = None;
+        stream.color_space = None;
+        stream.side_data_list = None;
+        assert_eq!(ColorMetadata::from_stream(&stream), None);
+    }
+
+    #[test]
+    fn test_lost_metadata() {
+        let color = ColorMetadata::from_stream(&hdr10_stream()).unwrap();
+        assert!(color.lost_tags(&hdr10_stream()).is_empty());
+
+        let mut output = hdr10_stream();
+        output.color_transfer = Some("bt709".to_string());
+        output.side_data_list = None;
+        assert_eq!(color.lost_tags(&output), vec!["transfer"]);
+        assert_eq!(color.lost_static_metadata(&output), vec!["mastering display", "content light level"]);
+    }
+}
@@ -1,6 +1,21 @@
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
+use crate::container::{self, ContainerPolicy};
+use crate::load::LoadThrottle;
+use crate::retry::RetryPolicy;
+use crate::schedule::EncodingSchedule;
+use crate::store::JobStoreBackend;
+use crate::tracks::{ExtraVideoPolicy, TrackSelection};
+use crate::audio::AudioPolicy;
+use crate::subtitles::SubtitlePolicy;
+use crate::dolby_vision::DolbyVisionPolicy;
+use crate::hdr10_plus::Hdr10PlusPolicy;
+use crate::interlace::DeinterlacePolicy;
+use crate::scale::ResolutionPolicy;
+use crate::profile::{self, EncodingProfile, DEFAULT_PROFILE};
+use crate::verify::QualityCheck;
 
 /// Expand tilde (~) in a path to the user's home directory
 fn expand_tilde(path: &Path) -> PathBuf {
@@ -22,6 +37,16 @@ fn expand_tilde(path: &Path) -> PathBuf {
     path.to_path_buf()
 }
 
+/// What to do with a test clip that nobody reviewed before the approval timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalTimeoutPolicy {
+    /// Proceed with the full encode using the current parameters
+    Approve,
+    /// Skip the file as if the user had rejected the clip
+    Reject,
+}
+
 /// Configuration for the AV1 transcoding daemon
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TranscodeConfig {
@@ -31,8 +56,14 @@ pub struct TranscodeConfig {
     pub min_bytes: u64,
     /// Maximum size ratio for accepting transcoded output (e.g., 0.90 = 90% of original)
     pub max_size_ratio: f64,
-    /// Directory where job state JSON files are stored
+    /// Directory where job state is stored (JSON files, or the database plus progress/control files)
     pub job_state_dir: PathBuf,
+    /// Job store backend: "json" (one file per job, default) or "sqlite"
+    #[serde(default)]
+    pub job_store: JobStoreBackend,
+    /// SQLite database path (default: {job_state_dir}/jobs.sqlite3)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_store_path: Option<PathBuf>,
     /// Interval in seconds between library scans
     pub scan_interval_secs: u64,
     /// Directory for temporary output files (e.g., fast NVMe drive)
@@ -62,6 +93,63 @@ pub struct TranscodeConfig {
     /// Minimum FFmpeg version required (default: "8.0")
     #[serde(default = "default_ffmpeg_version")]
     pub require_ffmpeg_version: String,
+    /// Container of transcoded files: "keep" the source container where AV1 is supported,
+    /// or always convert to "mkv" (default: keep)
+    #[serde(default)]
+    pub container_policy: ContainerPolicy,
+    /// Which audio/subtitle languages to keep or drop (default: keep all tracks)
+    #[serde(default)]
+    pub tracks: TrackSelection,
+    /// Sources with more than one real video stream: "warn" and encode the main one,
+    /// or "skip" them (default: warn)
+    #[serde(default)]
+    pub extra_video_policy: ExtraVideoPolicy,
+    /// Per-tier audio handling: copy, transcode lossless tracks, or add a compatibility
+    /// track (default: copy all audio)
+    #[serde(default)]
+    pub audio: AudioPolicy,
+    /// Subtitles the output container can't hold: "convert" text and drop bitmap tracks,
+    /// or "extract" them to .srt/.sup files next to the media (default: convert)
+    #[serde(default)]
+    pub subtitle_policy: SubtitlePolicy,
+    /// Dolby Vision sources: "skip", encode the "base_layer" (profile 5 is skipped),
+    /// or "preserve_rpu" with `dolby_vision_rpu_tool` (default: base_layer)
+    #[serde(default)]
+    pub dolby_vision_policy: DolbyVisionPolicy,
+    /// Run as `<tool> <source> <encoded output>` after encoding; must rewrite the
+    /// output in place with the source's RPU (required for preserve_rpu)
+    #[serde(default)]
+    pub dolby_vision_rpu_tool: Option<PathBuf>,
+    /// HDR10+ sources: "skip", "warn" and encode as HDR10, or "preserve" the dynamic
+    /// metadata where the encoder can (default: warn)
+    #[serde(default)]
+    pub hdr10_plus_policy: Hdr10PlusPolicy,
+    /// Detect letterbox/pillarbox bars with cropdetect and crop them away (default: false)
+    #[serde(default = "default_false")]
+    pub auto_crop: bool,
+    /// Points spread over the source that cropdetect samples (default: 6)
+    #[serde(default = "default_auto_crop_samples")]
+    pub auto_crop_samples: u32,
+    /// Interlaced/telecined sources: "auto" checks them with idet and deinterlaces
+    /// (bwdif) or inverse telecines (fieldmatch/decimate) them, "off" encodes as-is (default: auto)
+    #[serde(default)]
+    pub deinterlace_policy: DeinterlacePolicy,
+    /// Per-tier output height cap, e.g. `low_quality = 1080` to bring 4K rips down to
+    /// 1080p (default: native resolution in every tier)
+    #[serde(default)]
+    pub max_resolution: ResolutionPolicy,
+    /// Named encoding profiles: per-tier resolution bands with crf, preset, film_grain,
+    /// tune and extra encoder options
+    #[serde(default)]
+    pub profiles: HashMap<String, EncodingProfile>,
+    /// Profile to encode with ("default" = the built-in CRF/preset tables, unless a
+    /// profile of that name is defined)
+    #[serde(default = "default_encoding_profile")]
+    pub encoding_profile: String,
+    /// Measure finished encodes against the source with VMAF, SSIM or XPSNR and keep the
+    /// original when the tier's minimum score isn't met (default: off)
+    #[serde(default)]
+    pub quality_check: QualityCheck,
     /// Force re-encoding even for clean WEB-DL sources with modern codecs (default: false)
     #[serde(default = "default_false")]
     pub force_reencode: bool,
@@ -71,6 +159,38 @@ pub struct TranscodeConfig {
     /// Test clip duration in seconds (default: 45)
     #[serde(default = "default_test_clip_duration")]
     pub test_clip_duration: u64,
+    /// Seconds a job may wait for a test clip decision before the timeout policy applies
+    /// (default: 86400 = 24 hours, 0 = wait forever)
+    #[serde(default = "default_test_clip_approval_timeout_secs")]
+    pub test_clip_approval_timeout_secs: u64,
+    /// Action taken when a test clip approval times out (default: approve)
+    #[serde(default = "default_test_clip_approval_timeout_policy")]
+    pub test_clip_approval_timeout_policy: ApprovalTimeoutPolicy,
+    /// Interval in seconds between checks for TUI command files (default: 2)
+    #[serde(default = "default_command_poll_interval_secs")]
+    pub command_poll_interval_secs: u64,
+    /// Interval in seconds between stuck job checks (default: 60)
+    /// Orphaned temp file cleanup runs every 10th check
+    #[serde(default = "default_janitor_interval_secs")]
+    pub janitor_interval_secs: u64,
+    /// Maximum number of transcoding jobs run at the same time (default: 1)
+    #[serde(default = "default_max_concurrent_jobs")]
+    pub max_concurrent_jobs: usize,
+    /// Encoder threads per job (default: available cores / max_concurrent_jobs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub threads_per_job: Option<u32>,
+    /// Seconds to wait after SIGINT before killing a cancelled FFmpeg process (default: 10)
+    #[serde(default = "default_cancel_grace_secs")]
+    pub cancel_grace_secs: u64,
+    /// Time-of-day windows for encoding (default: no restriction)
+    #[serde(default)]
+    pub schedule: EncodingSchedule,
+    /// Back off while the host is busy (default: no limits)
+    #[serde(default)]
+    pub load_throttle: LoadThrottle,
+    /// Automatic retries for failed jobs (default: 3 attempts for I/O failures)
+    #[serde(default)]
+    pub retry: RetryPolicy,
     /// Preferred AV1 encoder (optional, auto-detect if not specified)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub preferred_encoder: Option<String>,
@@ -104,10 +224,42 @@ fn default_false() -> bool {
     false
 }
 
+fn default_encoding_profile() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+fn default_auto_crop_samples() -> u32 {
+    6
+}
+
 fn default_test_clip_duration() -> u64 {
     45
 }
 
+fn default_command_poll_interval_secs() -> u64 {
+    2
+}
+
+fn default_janitor_interval_secs() -> u64 {
+    60
+}
+
+fn default_max_concurrent_jobs() -> usize {
+    1
+}
+
+fn default_cancel_grace_secs() -> u64 {
+    10
+}
+
+fn default_test_clip_approval_timeout_secs() -> u64 {
+    86400 // 24 hours
+}
+
+fn default_test_clip_approval_timeout_policy() -> ApprovalTimeoutPolicy {
+    ApprovalTimeoutPolicy::Approve
+}
+
 impl Default for TranscodeConfig {
     fn default() -> Self {
         Self::default_config()
@@ -122,6 +274,8 @@ impl TranscodeConfig {
             min_bytes: 2 * 1024 * 1024 * 1024, // 2GB
             max_size_ratio: 0.90,
             job_state_dir: PathBuf::from("/tmp/av1d-jobs"),
+            job_store: JobStoreBackend::Json,
+            job_store_path: None, // Will be derived from job_state_dir
             scan_interval_secs: 60,
             stuck_job_timeout_secs: 3600, // 1 hour
             stuck_job_file_inactivity_secs: 600, // 10 minutes
@@ -132,12 +286,54 @@ impl TranscodeConfig {
             ffmpeg_bin: PathBuf::from("ffmpeg"),
             ffprobe_bin: PathBuf::from("ffprobe"),
             require_ffmpeg_version: "8.0".to_string(),
+            container_policy: ContainerPolicy::Keep,
+            tracks: TrackSelection::default(), // Keep every track
+            extra_video_policy: ExtraVideoPolicy::Warn,
+            audio: AudioPolicy::default(), // Copy all audio
+            subtitle_policy: SubtitlePolicy::Convert,
+            dolby_vision_policy: DolbyVisionPolicy::BaseLayer,
+            dolby_vision_rpu_tool: None,
+            hdr10_plus_policy: Hdr10PlusPolicy::Warn,
+            auto_crop: false,
+            auto_crop_samples: 6,
+            deinterlace_policy: DeinterlacePolicy::Auto,
+            max_resolution: ResolutionPolicy::default(), // Native resolution
+            profiles: HashMap::new(),
+            encoding_profile: DEFAULT_PROFILE.to_string(),
+            quality_check: QualityCheck::default(), // Off
             force_reencode: false,
             enable_test_clip_workflow: true,
             test_clip_duration: 45,
+            test_clip_approval_timeout_secs: 86400, // 24 hours
+            test_clip_approval_timeout_policy: ApprovalTimeoutPolicy::Approve,
+            command_poll_interval_secs: 2,
+            janitor_interval_secs: 60,
+            max_concurrent_jobs: 1,
+            threads_per_job: None, // Derived from available cores
+            cancel_grace_secs: 10,
+            schedule: EncodingSchedule::default(), // Encode around the clock
+            load_throttle: LoadThrottle::default(), // Ignore host load
+            retry: RetryPolicy::default(),
             preferred_encoder: None,
         }
     }
+
+    /// Number of jobs the scheduler may run at once (never less than 1)
+    pub fn max_concurrent_jobs(&self) -> usize {
+        self.max_concurrent_jobs.max(1)
+    }
+
+    /// Encoder thread budget for each job
+    /// Uses threads_per_job if set, otherwise splits the available cores evenly across job slots
+    pub fn thread_budget_per_job(&self) -> u32 {
+        if let Some(threads) = self.threads_per_job {
+            return threads.max(1);
+        }
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        (cores / self.max_concurrent_jobs()).max(1) as u32
+    }
     
     /// Get the command directory path, deriving from job_state_dir if not explicitly set
     pub fn command_dir(&self) -> PathBuf {
@@ -149,6 +345,22 @@ impl TranscodeConfig {
         })
     }
 
+    /// Output container and final path for a source file
+    pub fn output_plan(&self, source_path: &Path) -> container::OutputPlan {
+        self.container_policy.plan(source_path)
+    }
+
+    /// Temp file a source is encoded into (in temp_output_dir, with the output container's extension)
+    pub fn temp_output_path(&self, source_path: &Path) -> PathBuf {
+        container::temp_output_path(&self.temp_output_dir, source_path, &self.output_plan(source_path))
+    }
+
+    /// Get the SQLite job database path, deriving from job_state_dir if not explicitly set
+    pub fn job_store_path(&self) -> PathBuf {
+        self.job_store_path.clone()
+            .unwrap_or_else(|| self.job_state_dir.join("jobs.sqlite3"))
+    }
+
     /// Load configuration from a file, or return defaults if path is None or file doesn't exist
     pub fn load_config(path: Option<&Path>) -> Result<Self> {
         let mut config = Self::default_config();
@@ -174,9 +386,42 @@ impl TranscodeConfig {
         // Expand tilde (~) in paths after loading
         config.expand_tilde_in_paths();
 
+        config.validate()?;
+
         Ok(config)
     }
 
+    /// Check settings that deserialize fine but can't be used as given
+    pub fn validate(&self) -> Result<()> {
+        self.schedule.validate()
+            .context("Invalid schedule configuration")?;
+        self.load_throttle.validate()
+            .context("Invalid load_throttle configuration")?;
+        self.retry.validate()
+            .context("Invalid retry configuration")?;
+        self.tracks.validate()
+            .context("Invalid tracks configuration")?;
+        self.audio.validate()
+            .context("Invalid audio configuration")?;
+        profile::validate_profiles(&self.profiles, &self.encoding_profile)
+            .context("Invalid encoding profile configuration")?;
+        self.quality_check.validate()
+            .context("Invalid quality_check configuration")?;
+        self.max_resolution.validate()
+            .context("Invalid max_resolution configuration")?;
+        self.dolby_vision_policy.validate(self.dolby_vision_rpu_tool.as_ref())
+            .context("Invalid Dolby Vision configuration")?;
+        if self.auto_crop && self.auto_crop_samples == 0 {
+            return Err(anyhow!("auto_crop_samples must be at least 1"));
+        }
+        Ok(())
+    }
+
+    /// The encoding profile named by `encoding_profile` (the built-in one if it isn't configured)
+    pub fn active_profile(&self) -> EncodingProfile {
+        self.profiles.get(&self.encoding_profile).cloned().unwrap_or_default()
+    }
+
     /// Expand tilde (~) in all PathBuf fields to the user's home directory
     fn expand_tilde_in_paths(&mut self) {
         self.library_roots = self.library_roots.iter().map(|p| expand_tilde(p)).collect();
@@ -187,6 +432,9 @@ impl TranscodeConfig {
         if let Some(ref cmd_dir) = self.command_dir {
             self.command_dir = Some(expand_tilde(cmd_dir));
         }
+        if let Some(ref store_path) = self.job_store_path {
+            self.job_store_path = Some(expand_tilde(store_path));
+        }
     }
 }
 
@@ -0,0 +1,155 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+/// Which container transcoded files are written in
+///
+/// Sidecar files (`.why.txt`, `.av1skip`, reports) are named after the file stem,
+/// so they keep matching when a file is renamed to `.mkv`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContainerPolicy {
+    /// Keep the source container if it can hold AV1 (MKV, MP4/M4V, WebM);
+    /// other sources (AVI, MOV, ...) are converted to MKV
+    #[default]
+    Keep,
+    /// Always write Matroska and rename non-MKV sources to `.mkv`
+    Mkv,
+}
+
+/// Container format of an encode's output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Container {
+    Matroska,
+    Mp4,
+    WebM,
+}
+
+impl Container {
+    /// Container for a file extension, if AV1 can be written in it
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "mkv" => Some(Container::Matroska),
+            "mp4" | "m4v" => Some(Container::Mp4),
+            "webm" => Some(Container::WebM),
+            _ => None,
+        }
+    }
+
+    /// Container for a path based on its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// FFmpeg muxer name (`-f`)
+    pub fn muxer(self) -> &'static str {
+        match self {
+            Container::Matroska => "matroska",
+            Container::Mp4 => "mp4",
+            Container::WebM => "webm",
+        }
+    }
+}
+
+/// Where a job's output ends up and in which container
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputPlan {
+    pub container: Container,
+    /// Final location of the transcoded file (the source path unless it is renamed to `.mkv`)
+    pub final_path: PathBuf,
+}
+
+impl OutputPlan {
+    /// The output replaces the source under a different name
+    pub fn renames_source(&self, source: &Path) -> bool {
+        self.final_path != source
+    }
+}
+
+impl ContainerPolicy {
+    /// Decide the output container and final path for a source file
+    pub fn plan(self, source: &Path) -> OutputPlan {
+        let keep = match self {
+            ContainerPolicy::Keep => Container::from_path(source),
+            ContainerPolicy::Mkv => None,
+        };
+        match keep {
+            Some(container) => OutputPlan {
+                container,
+                final_path: source.to_path_buf(),
+            },
+            None => OutputPlan {
+                container: Container::Matroska,
+                final_path: source.with_extension("mkv"),
+            },
+        }
+    }
+}
+
+/// Temp file for encoding `source` (`{stem}.tmp.av1.{ext}` with the output's extension)
+pub fn temp_output_path(temp_dir: &Path, source: &Path, plan: &OutputPlan) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("unknown");
+    let ext = plan.final_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("mkv");
+    temp_dir.join(format!("{}.tmp.av1.{}", stem, ext))
+}
+
+/// Backup of the original taken while it is being replaced (`movie.mp4` -> `movie.orig.mp4`)
+pub fn backup_path(source: &Path) -> PathBuf {
+    let ext = source.extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("mkv");
+    source.with_extension(format!("orig.{}", ext))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_policy() {
+        let plan = ContainerPolicy::Keep.plan(Path::new("/media/movie.mp4"));
+        assert_eq!(plan.container, Container::Mp4);
+        assert_eq!(plan.final_path, PathBuf::from("/media/movie.mp4"));
+        assert!(!plan.renames_source(Path::new("/media/movie.mp4")));
+
+        let plan = ContainerPolicy::Keep.plan(Path::new("/media/Show.S01E01.M4V"));
+        assert_eq!(plan.container, Container::Mp4);
+
+        // AVI and MOV can't hold AV1 here - converted to MKV
+        let plan = ContainerPolicy::Keep.plan(Path::new("/media/old.avi"));
+        assert_eq!(plan.container, Container::Matroska);
+        assert_eq!(plan.final_path, PathBuf::from("/media/old.mkv"));
+        assert!(plan.renames_source(Path::new("/media/old.avi")));
+    }
+
+    #[test]
+    fn test_mkv_policy() {
+        let plan = ContainerPolicy::Mkv.plan(Path::new("/media/movie.mp4"));
+        assert_eq!(plan.container, Container::Matroska);
+        assert_eq!(plan.final_path, PathBuf::from("/media/movie.mkv"));
+
+        let plan = ContainerPolicy::Mkv.plan(Path::new("/media/movie.mkv"));
+        assert_eq!(plan.final_path, PathBuf::from("/media/movie.mkv"));
+    }
+
+    #[test]
+    fn test_temp_and_backup_paths() {
+        let source = Path::new("/media/movie.m4v");
+        let plan = ContainerPolicy::Keep.plan(source);
+        assert_eq!(temp_output_path(Path::new("/tmp/av1"), source, &plan),
+                   PathBuf::from("/tmp/av1/movie.tmp.av1.m4v"));
+        let plan = ContainerPolicy::Mkv.plan(source);
+        assert_eq!(temp_output_path(Path::new("/tmp/av1"), source, &plan),
+                   PathBuf::from("/tmp/av1/movie.tmp.av1.mkv"));
+
+        assert_eq!(backup_path(source), PathBuf::from("/media/movie.orig.m4v"));
+        assert_eq!(backup_path(Path::new("/media/movie.mkv")), PathBuf::from("/media/movie.orig.mkv"));
+    }
+}
@@ -0,0 +1,83 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Daemon-wide control state shared with the TUI
+///
+/// Persisted to `{job_state_dir}/control/daemon.json` so a pause survives
+/// daemon restarts and the TUI can show it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DaemonState {
+    /// No new jobs are started and running encodes are stopped (SIGSTOP)
+    #[serde(default)]
+    pub paused: bool,
+    /// When the daemon was paused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused_at: Option<DateTime<Utc>>,
+    /// The configured encoding schedule window is closed (see `EncodingSchedule`)
+    #[serde(default)]
+    pub outside_window: bool,
+    /// Why new jobs are held back because the host is busy (see `LoadThrottle`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub busy_reason: Option<String>,
+}
+
+/// Directory holding control files (a subdirectory so job loading ignores it)
+pub fn control_dir(job_state_dir: &Path) -> PathBuf {
+    job_state_dir.join("control")
+}
+
+/// Path of the daemon state file
+pub fn daemon_state_path(job_state_dir: &Path) -> PathBuf {
+    control_dir(job_state_dir).join("daemon.json")
+}
+
+/// Load the daemon state (defaults to running if the file is missing or unreadable)
+pub fn load_daemon_state(job_state_dir: &Path) -> DaemonState {
+    let path = daemon_state_path(job_state_dir);
+    let Ok(content) = fs::read_to_string(&path) else {
+        return DaemonState::default();
+    };
+    match serde_json::from_str(&content) {
+        Ok(state) => state,
+        Err(e) => {
+            log::warn!("Ignoring invalid daemon state file {}: {}", path.display(), e);
+            DaemonState::default()
+        }
+    }
+}
+
+/// Write the daemon state atomically
+pub fn save_daemon_state(job_state_dir: &Path, state: &DaemonState) -> Result<()> {
+    let dir = control_dir(job_state_dir);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create control directory: {}", dir.display()))?;
+
+    let path = daemon_state_path(job_state_dir);
+    let tmp_path = dir.join(".daemon.json.tmp");
+    let json = serde_json::to_string_pretty(state)
+        .context("Failed to serialize daemon state")?;
+    fs::write(&tmp_path, json)
+        .with_context(|| format!("Failed to write daemon state file: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to rename daemon state file: {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daemon_state_defaults() {
+        let state: DaemonState = serde_json::from_str("{}").unwrap();
+        assert_eq!(state, DaemonState::default());
+        assert!(!state.paused);
+
+        // Missing file means the daemon is running
+        let missing = Path::new("/nonexistent/av1d-state");
+        assert!(!load_daemon_state(missing).paused);
+    }
+}
@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+/// Area of the picture kept by the `crop` filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub width: i32,
+    pub height: i32,
+    /// Left edge
+    pub x: i32,
+    /// Top edge
+    pub y: i32,
+}
+
+impl CropRect {
+    /// FFmpeg filter for this rectangle (`crop=w:h:x:y`)
+    pub fn filter(&self) -> String {
+        format!("crop={}:{}:{}:{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+impl std::fmt::Display for CropRect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{} at {},{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// Crops smaller than this many pixels per edge are left alone (encoder padding, noise)
+const MIN_CROP_PX: i32 = 8;
+
+/// Last rectangle suggested by `cropdetect` in FFmpeg's stderr
+///
+/// cropdetect prints a `crop=w:h:x:y` suggestion per frame, each covering
+/// every frame seen so far, so the last one sums up the sample.
+pub fn parse_cropdetect(stderr: &str) -> Option<CropRect> {
+    stderr.lines().rev()
+        .filter_map(|line| line.rsplit_once("crop=").map(|(_, value)| value))
+        .find_map(|value| {
+            let fields: Vec<i32> = value.split_whitespace().next()?
+                .split(':')
+                .map(|v| v.parse().ok())
+                .collect::<Option<_>>()?;
+            match fields[..] {
+                [width, height, x, y] if width > 0 && height > 0 => Some(CropRect { width, height, x, y }),
+                _ => None,
+            }
+        })
+}
+
+/// Combine the samples into one crop that cuts no picture from any of them
+///
+/// Takes the union of the sampled rectangles (a dark scene must not shrink the
+/// crop), rounded to even sizes for 4:2:0. Returns None when nothing was sampled
+/// or the bars are too thin to be worth cropping.
+pub fn combine(samples: &[CropRect], source_width: i32, source_height: i32) -> Option<CropRect> {
+    let left = samples.iter().map(|c| c.x).min()?.max(0);
+    let top = samples.iter().map(|c| c.y).min()?.max(0);
+    let right = samples.iter().map(|c| c.x + c.width).max()?.min(source_width);
+    let bottom = samples.iter().map(|c| c.y + c.height).max()?.min(source_height);
+
+    // Round inwards-facing edges outwards to even positions so no picture is lost
+    let x = left & !1;
+    let y = top & !1;
+    let width = ((right - x + 1) & !1).min(source_width - x);
+    let height = ((bottom - y + 1) & !1).min(source_height - y);
+    if width <= 0 || height <= 0 {
+        return None;
+    }
+
+    let trims_enough = source_width - width >= MIN_CROP_PX * 2 || source_height - height >= MIN_CROP_PX * 2;
+    trims_enough.then_some(CropRect { width, height, x, y })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cropdetect() {
+        let stderr = "\
+[Parsed_cropdetect_0 @ 0x55] x1:0 x2:1919 y1:140 y2:939 w:1920 h:800 x:0 y:140 pts:1001 t:0.041 limit:0.094118 crop=1920:800:0:140
+[Parsed_cropdetect_0 @ 0x55] x1:0 x2:1919 y1:138 y2:941 w:1920 h:800 x:0 y:140 pts:2002 t:0.083 limit:0.094118 crop=1920:804:0:138
+frame=   24 fps=0.0 q=-0.0 Lsize=N/A time=00:00:01.00 bitrate=N/A speed=4.1x";
+        assert_eq!(parse_cropdetect(stderr), Some(CropRect { width: 1920, height: 804, x: 0, y: 138 }));
+        assert_eq!(parse_cropdetect("no detection here"), None);
+    }
+
+    #[test]
+    fn test_combine_takes_union_of_samples() {
+        let samples = [
+            CropRect { width: 1920, height: 800, x: 0, y: 140 },
+            // Brighter scene reaching further into the bars
+            CropRect { width: 1920, height: 808, x: 0, y: 136 },
+        ];
+        let crop = combine(&samples, 1920, 1080).unwrap();
+        assert_eq!(crop, CropRect { width: 1920, height: 808, x: 0, y: 136 });
+        assert_eq!(crop.filter(), "crop=1920:808:0:136");
+    }
+
+    #[test]
+    fn test_combine_keeps_even_sizes() {
+        let crop = combine(&[CropRect { width: 1437, height: 1080, x: 241, y: 0 }], 1920, 1080).unwrap();
+        assert_eq!(crop, CropRect { width: 1438, height: 1080, x: 240, y: 0 });
+    }
+
+    #[test]
+    fn test_negligible_crop_is_ignored() {
+        assert_eq!(combine(&[CropRect { width: 1920, height: 1072, x: 0, y: 4 }], 1920, 1080), None);
+        assert_eq!(combine(&[], 1920, 1080), None);
+    }
+}
@@ -0,0 +1,160 @@
+use std::path::PathBuf;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::ffprobe::FFProbeData;
+
+/// What to do with Dolby Vision sources
+///
+/// The AV1 encoders only see the base layer: profile 8 carries HDR10 (or SDR/HLG)
+/// there, profile 7 an HDR10 base layer with an enhancement layer, while
+/// profile 5 has no backwards compatible base layer at all (IPTPQc2 colours).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DolbyVisionPolicy {
+    /// Leave Dolby Vision files untouched
+    Skip,
+    /// Encode the base layer and drop the RPU; profile 5 is skipped since its
+    /// base layer would come out with wrong colours
+    #[default]
+    BaseLayer,
+    /// Encode the base layer, then let `dolby_vision_rpu_tool` carry the RPU over
+    /// into the output
+    PreserveRpu,
+}
+
+/// How a Dolby Vision source was (or will be) handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DolbyVisionHandling {
+    Skipped,
+    BaseLayer,
+    RpuPreserved,
+}
+
+/// Dolby Vision decision recorded on the job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DolbyVisionInfo {
+    /// DV profile from the stream's configuration record (None if only tags hinted at DV)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<u8>,
+    pub handling: DolbyVisionHandling,
+}
+
+impl std::fmt::Display for DolbyVisionInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.profile {
+            Some(profile) => write!(f, "profile {}", profile)?,
+            None => write!(f, "unknown profile")?,
+        }
+        let handling = match self.handling {
+            DolbyVisionHandling::Skipped => "skipped",
+            DolbyVisionHandling::BaseLayer => "base layer encoded, RPU dropped",
+            DolbyVisionHandling::RpuPreserved => "base layer encoded, RPU preserved",
+        };
+        write!(f, " - {}", handling)
+    }
+}
+
+impl DolbyVisionPolicy {
+    /// Check that the policy can be carried out
+    pub fn validate(self, rpu_tool: Option<&PathBuf>) -> Result<()> {
+        if self == DolbyVisionPolicy::PreserveRpu && rpu_tool.is_none() {
+            return Err(anyhow!("dolby_vision_policy \"preserve_rpu\" needs dolby_vision_rpu_tool"));
+        }
+        Ok(())
+    }
+
+    /// Decide how to handle a source (None if it has no Dolby Vision)
+    pub fn decide(self, meta: &FFProbeData) -> Option<DolbyVisionInfo> {
+        let video = meta.streams.iter()
+            .find(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture())?;
+        let profile = video.dolby_vision_profile();
+        if profile.is_none() && !video.has_dolby_vision() {
+            return None;
+        }
+
+        let handling = match self {
+            DolbyVisionPolicy::Skip => DolbyVisionHandling::Skipped,
+            DolbyVisionPolicy::BaseLayer if profile == Some(5) => DolbyVisionHandling::Skipped,
+            DolbyVisionPolicy::BaseLayer => DolbyVisionHandling::BaseLayer,
+            DolbyVisionPolicy::PreserveRpu => DolbyVisionHandling::RpuPreserved,
+        };
+        Some(DolbyVisionInfo { profile, handling })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::ffprobe::{FFProbeFormat, FFProbeStream};
+
+    fn dv_meta(profile: Option<u64>) -> FFProbeData {
+        let side_data = profile.map(|p| vec![HashMap::from([
+            ("side_data_type".to_string(), serde_json::json!("DOVI configuration record")),
+            ("dv_profile".to_string(), serde_json::json!(p)),
+        ])]);
+        FFProbeData {
+            streams: vec![FFProbeStream {
+                index: 0,
+                codec_type: Some("video".to_string()),
+                codec_name: Some("hevc".to_string()),
+                width: Some(3840),
+                height: Some(2160),
+                avg_frame_rate: None,
+                r_frame_rate: None,
+                tags: None,
+                bit_rate: None,
+                disposition: None,
+                pix_fmt: Some("yuv420p10le".to_string()),
+                bits_per_raw_sample: None,
+                color_transfer: Some("smpte2084".to_string()),
+                color_primaries: Some("bt2020".to_string()),
+                color_space: None,
+                field_order: None,
+                channels: None,
+                channel_layout: None,
+                profile: None,
+                side_data_list: side_data,
+            }],
+            format: FFProbeFormat {
+                format_name: "matroska,webm".to_string(),
+                bit_rate: None,
+                tags: None,
+                muxing_app: None,
+                writing_library: None,
+                duration: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_profile_from_side_data() {
+        let meta = dv_meta(Some(8));
+        assert_eq!(meta.streams[0].dolby_vision_profile(), Some(8));
+        assert_eq!(dv_meta(None).streams[0].dolby_vision_profile(), None);
+    }
+
+    #[test]
+    fn test_non_dv_source_has_no_decision() {
+        assert_eq!(DolbyVisionPolicy::Skip.decide(&dv_meta(None)), None);
+    }
+
+    #[test]
+    fn test_base_layer_skips_profile_5() {
+        let policy = DolbyVisionPolicy::BaseLayer;
+        assert_eq!(policy.decide(&dv_meta(Some(8))).unwrap().handling, DolbyVisionHandling::BaseLayer);
+        let info = policy.decide(&dv_meta(Some(5))).unwrap();
+        assert_eq!(info.handling, DolbyVisionHandling::Skipped);
+        assert_eq!(info.to_string(), "profile 5 - skipped");
+        assert_eq!(DolbyVisionPolicy::PreserveRpu.decide(&dv_meta(Some(5))).unwrap().handling,
+                   DolbyVisionHandling::RpuPreserved);
+    }
+
+    #[test]
+    fn test_preserve_rpu_needs_tool() {
+        assert!(DolbyVisionPolicy::PreserveRpu.validate(None).is_err());
+        assert!(DolbyVisionPolicy::PreserveRpu.validate(Some(&PathBuf::from("/usr/local/bin/dv-rpu"))).is_ok());
+        assert!(DolbyVisionPolicy::BaseLayer.validate(None).is_ok());
+    }
+}
@@ -0,0 +1,241 @@
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use crate::verify::QualityMetric;
+
+/// Why a job failed or was rejected, persisted on the job and its attempts
+///
+/// `Display` gives the human readable reason shown in `job.reason`.
+#[derive(Debug, Clone, PartialEq, Error, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum JobFailure {
+    /// ffprobe could not read the source file
+    #[error("ffprobe failed: {message}")]
+    ProbeFailed { message: String },
+    /// FFmpeg could not be run at all
+    #[error("ffmpeg execution failed: {message}")]
+    EncoderLaunchFailed { message: String },
+    /// FFmpeg exited with an error
+    #[error("ffmpeg exit code {}", exit_code_display(.exit_code))]
+    EncoderCrashed { exit_code: Option<i32> },
+    /// Disk full, quota exceeded or another filesystem error
+    #[error("I/O error: {message}")]
+    Io { message: String },
+    /// FFmpeg finished but the output is missing, empty or not AV1
+    #[error("output validation failed: {message}")]
+    OutputInvalid { message: String },
+    /// The output was not small enough compared to the original
+    #[error("rejected: new {:.2} GB vs orig {:.2} GB (>{}%)", gb(.new_bytes), gb(.original_bytes), .max_size_ratio * 100.0)]
+    SizeGate { new_bytes: u64, original_bytes: u64, max_size_ratio: f64 },
+    /// The output's perceptual quality score was below the tier's minimum
+    #[error("rejected: {} {} below minimum {}", .metric.label(), format_score(.metric, .score), format_score(.metric, .min_score))]
+    QualityGate { metric: QualityMetric, score: f64, min_score: f64 },
+    /// A quality minimum applies but no score could be measured
+    #[error("quality check failed: {} could not be measured: {message}", .metric.label())]
+    QualityUnmeasured { metric: QualityMetric, message: String },
+    /// The source file disappeared while the job was running
+    #[error("original file no longer exists: {}", .path.display())]
+    SourceVanished { path: PathBuf },
+    /// The Dolby Vision RPU tool failed to carry the RPU over into the output
+    #[error("Dolby Vision RPU tool failed: {message}")]
+    RpuToolFailed { message: String },
+    /// Swapping the output in for the original failed (the original was restored)
+    #[error("file replacement failed: {message}")]
+    ReplaceFailed { message: String },
+    /// Cancelled by the user
+    #[error("cancelled by user")]
+    Cancelled,
+    /// Anything else (daemon errors, interrupted jobs, ...)
+    #[error("{message}")]
+    Other { message: String },
+}
+
+fn exit_code_display(exit_code: &Option<i32>) -> String {
+    match exit_code {
+        Some(code) => code.to_string(),
+        None => "none (killed by signal)".to_string(),
+    }
+}
+
+/// Whether FFmpeg failed because of the disk rather than the encode itself
+fn is_disk_error(stderr: &str) -> bool {
+    ["No space left on device", "Disk quota exceeded", "Input/output error"]
+        .iter()
+        .any(|msg| stderr.contains(msg))
+}
+
+fn gb(bytes: &u64) -> f64 {
+    *bytes as f64 / 1_000_000_000.0
+}
+
+fn format_score(metric: &QualityMetric, value: &f64) -> String {
+    metric.format_score(*value)
+}
+
+/// Kind of a `JobFailure` without its details, used for filtering and retry rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    ProbeFailed,
+    EncoderLaunchFailed,
+    EncoderCrashed,
+    Io,
+    OutputInvalid,
+    SizeGate,
+    QualityGate,
+    QualityUnmeasured,
+    SourceVanished,
+    RpuToolFailed,
+    ReplaceFailed,
+    Cancelled,
+    Other,
+}
+
+impl FailureKind {
+    /// Every kind, in the order the TUI cycles through them
+    pub const ALL: [FailureKind; 13] = [
+        FailureKind::ProbeFailed,
+        FailureKind::EncoderLaunchFailed,
+        FailureKind::EncoderCrashed,
+        FailureKind::Io,
+        FailureKind::OutputInvalid,
+        FailureKind::SizeGate,
+        FailureKind::QualityGate,
+        FailureKind::QualityUnmeasured,
+        FailureKind::SourceVanished,
+        FailureKind::RpuToolFailed,
+        FailureKind::ReplaceFailed,
+        FailureKind::Cancelled,
+        FailureKind::Other,
+    ];
+
+    /// Short name, same as the config/JSON spelling
+    pub fn label(self) -> &'static str {
+        match self {
+            FailureKind::ProbeFailed => "probe_failed",
+            FailureKind::EncoderLaunchFailed => "encoder_launch_failed",
+            FailureKind::EncoderCrashed => "encoder_crashed",
+            FailureKind::Io => "io",
+            FailureKind::OutputInvalid => "output_invalid",
+            FailureKind::SizeGate => "size_gate",
+            FailureKind::QualityGate => "quality_gate",
+            FailureKind::QualityUnmeasured => "quality_unmeasured",
+            FailureKind::SourceVanished => "source_vanished",
+            FailureKind::RpuToolFailed => "rpu_tool_failed",
+            FailureKind::ReplaceFailed => "replace_failed",
+            FailureKind::Cancelled => "cancelled",
+            FailureKind::Other => "other",
+        }
+    }
+
+    /// Decisions that must never be undone by an automatic retry
+    pub fn is_permanent(self) -> bool {
+        matches!(self, FailureKind::SizeGate | FailureKind::QualityGate | FailureKind::Cancelled)
+    }
+}
+
+impl JobFailure {
+    pub fn kind(&self) -> FailureKind {
+        match self {
+            JobFailure::ProbeFailed { .. } => FailureKind::ProbeFailed,
+            JobFailure::EncoderLaunchFailed { .. } => FailureKind::EncoderLaunchFailed,
+            JobFailure::EncoderCrashed { .. } => FailureKind::EncoderCrashed,
+            JobFailure::Io { .. } => FailureKind::Io,
+            JobFailure::OutputInvalid { .. } => FailureKind::OutputInvalid,
+            JobFailure::SizeGate { .. } => FailureKind::SizeGate,
+            JobFailure::QualityGate { .. } => FailureKind::QualityGate,
+            JobFailure::QualityUnmeasured { .. } => FailureKind::QualityUnmeasured,
+            JobFailure::SourceVanished { .. } => FailureKind::SourceVanished,
+            JobFailure::RpuToolFailed { .. } => FailureKind::RpuToolFailed,
+            JobFailure::ReplaceFailed { .. } => FailureKind::ReplaceFailed,
+            JobFailure::Cancelled => FailureKind::Cancelled,
+            JobFailure::Other { .. } => FailureKind::Other,
+        }
+    }
+
+    /// Classify a non-zero FFmpeg exit: disk trouble is `Io` (retried by
+    /// default), anything else a crash of the encoder
+    pub fn from_ffmpeg_exit(exit_code: Option<i32>, stderr: &str) -> Self {
+        if is_disk_error(stderr) {
+            JobFailure::Io { message: format!("ffmpeg exit code {}: disk error", exit_code_display(&exit_code)) }
+        } else {
+            JobFailure::EncoderCrashed { exit_code }
+        }
+    }
+
+    /// Classify an error that escaped the job pipeline
+    pub fn from_error(e: &anyhow::Error) -> Self {
+        let message = format!("{:#}", e);
+        if e.chain().any(|cause| cause.is::<std::io::Error>()) {
+            JobFailure::Io { message }
+        } else {
+            JobFailure::Other { message }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_failure_json_roundtrip() {
+        let failure = JobFailure::EncoderCrashed { exit_code: Some(1) };
+        let json = serde_json::to_string(&failure).unwrap();
+        assert_eq!(json, r#"{"kind":"encoder_crashed","exit_code":1}"#);
+        assert_eq!(serde_json::from_str::<JobFailure>(&json).unwrap(), failure);
+        assert_eq!(failure.to_string(), "ffmpeg exit code 1");
+
+        let cancelled: JobFailure = serde_json::from_str(r#"{"kind":"cancelled"}"#).unwrap();
+        assert_eq!(cancelled.kind(), FailureKind::Cancelled);
+    }
+
+    #[test]
+    fn test_size_gate_message() {
+        let failure = JobFailure::SizeGate {
+            new_bytes: 1_500_000_000,
+            original_bytes: 2_000_000_000,
+            max_size_ratio: 0.5,
+        };
+        assert_eq!(failure.to_string(), "rejected: new 1.50 GB vs orig 2.00 GB (>50%)");
+        assert!(failure.kind().is_permanent());
+    }
+
+    #[test]
+    fn test_quality_gate_message() {
+        let failure = JobFailure::QualityGate { metric: QualityMetric::Vmaf, score: 91.234, min_score: 93.0 };
+        assert_eq!(failure.to_string(), "rejected: VMAF 91.23 below minimum 93.00");
+        assert!(failure.kind().is_permanent());
+
+        // Not measuring is worth a retry
+        let unmeasured = JobFailure::QualityUnmeasured { metric: QualityMetric::Ssim, message: "no segment could be measured".to_string() };
+        assert_eq!(unmeasured.to_string(), "quality check failed: SSIM could not be measured: no segment could be measured");
+        assert!(!unmeasured.kind().is_permanent());
+    }
+
+    #[test]
+    fn test_kind_labels_match_serde() {
+        for kind in FailureKind::ALL {
+            let json = serde_json::to_string(&kind).unwrap();
+            assert_eq!(json, format!("\"{}\"", kind.label()));
+        }
+    }
+
+    #[test]
+    fn test_ffmpeg_exits_are_classified() {
+        let crash = JobFailure::from_ffmpeg_exit(Some(-11), "[libsvtav1 @ 0x55d1] Svt[error]: ...\nConversion failed!");
+        assert_eq!(crash, JobFailure::EncoderCrashed { exit_code: Some(-11) });
+        assert_eq!(JobFailure::from_ffmpeg_exit(None, "").kind(), FailureKind::EncoderCrashed);
+
+        let disk = JobFailure::from_ffmpeg_exit(Some(1), "av_interleaved_write_frame(): Disk quota exceeded");
+        assert_eq!(disk.kind(), FailureKind::Io);
+        assert_eq!(disk.to_string(), "I/O error: ffmpeg exit code 1: disk error");
+    }
+
+    #[test]
+    fn test_io_errors_are_classified() {
+        let io = anyhow::Error::new(std::io::Error::other("disk full")).context("Failed to copy");
+        assert_eq!(JobFailure::from_error(&io).kind(), FailureKind::Io);
+        assert_eq!(JobFailure::from_error(&anyhow::anyhow!("boom")).kind(), FailureKind::Other);
+    }
+}
@@ -1,9 +1,19 @@
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
 use anyhow::{Context, Result, anyhow};
 use tokio::process::Command;
+use tokio::sync::oneshot;
+use crate::audio::{AudioAction, AudioCodec};
+use crate::container::Container;
+use crate::crop::{self, CropRect};
+use crate::interlace::IdetCounts;
+use crate::verify::{QualityCheck, QualityMetric, QualityScore};
 use crate::config::TranscodeConfig;
 use crate::quality::EncodingParams;
 use crate::ffprobe::FFProbeData;
+use crate::progress::{EncodeProgress, ProgressParser};
 
 /// Validation result for output file
 #[derive(Debug, Clone)]
@@ -13,6 +23,18 @@ pub struct ValidationResult {
     pub warnings: Vec<String>,
 }
 
+/// Frames decoded per `cropdetect` sample point
+const CROPDETECT_FRAMES: u32 = 48;
+
+/// Upper bound for one `cropdetect` or `idet` sample (seeking and decoding a few frames)
+const ANALYSIS_TIMEOUT_SECS: u64 = 120;
+
+/// Frames idet looks at per sample point
+const IDET_FRAMES: u32 = 250;
+
+/// Points spread over the source that idet samples
+const IDET_SAMPLES: u32 = 3;
+
 /// Available AV1 software encoders in priority order
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AV1Encoder {
@@ -32,6 +54,12 @@ impl AV1Encoder {
             AV1Encoder::LibRav1e => "librav1e",
         }
     }
+
+    /// Whether FFmpeg's wrapper forwards HDR10+ frame side data into the AV1 bitstream
+    /// (libaom writes it as ITU-T T.35 metadata OBUs; SVT-AV1 and rav1e drop it)
+    pub fn passes_hdr10_plus(&self) -> bool {
+        matches!(self, AV1Encoder::LibAom)
+    }
 }
 
 /// FFmpeg version information
@@ -84,12 +112,79 @@ impl FFmpegVersion {
     }
 }
 
+/// An FFmpeg child process registered under a job id
+struct RunningFFmpeg {
+    pid: Option<u32>,
+    /// Fires the graceful cancel sequence inside `run_ffmpeg` (taken on first cancel)
+    cancel_tx: Option<oneshot::Sender<()>>,
+    /// Process is currently stopped with SIGSTOP
+    stopped: bool,
+}
+
+/// FFmpeg children started for jobs and the pause settings applied to them
+#[derive(Default)]
+struct Registry {
+    running: HashMap<String, RunningFFmpeg>,
+    /// Jobs paused individually (kept while no FFmpeg runs so a later one starts paused)
+    paused_jobs: HashSet<String>,
+    /// Every job is paused
+    all_paused: bool,
+    /// Every job is paused because the encoding schedule window is closed
+    schedule_paused: bool,
+}
+
+impl Registry {
+    /// Stop or continue a job's FFmpeg so it matches the pause settings
+    /// Returns true if the process was signalled
+    fn sync_job(&mut self, job_id: &str) -> bool {
+        let should_stop = self.all_paused || self.schedule_paused || self.paused_jobs.contains(job_id);
+        let Some(entry) = self.running.get_mut(job_id) else {
+            return false;
+        };
+        let Some(pid) = entry.pid.filter(|_| entry.stopped != should_stop) else {
+            return false;
+        };
+        let signal = if should_stop { Signal::Stop } else { Signal::Continue };
+        if signal_process(pid, signal) {
+            entry.stopped = should_stop;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn sync_all(&mut self) {
+        let job_ids: Vec<String> = self.running.keys().cloned().collect();
+        for job_id in job_ids {
+            self.sync_job(&job_id);
+        }
+    }
+}
+
+/// Removes a job's registry entry when its FFmpeg run ends, however it ends
+struct RunningGuard<'a> {
+    registry: &'a Mutex<Registry>,
+    job_id: String,
+}
+
+impl Drop for RunningGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut registry) = self.registry.lock() {
+            registry.running.remove(&self.job_id);
+        }
+    }
+}
+
 /// Manager for FFmpeg binary and encoder detection
 pub struct FFmpegManager {
     pub ffmpeg_bin: PathBuf,
     pub ffprobe_bin: PathBuf,
     pub version: FFmpegVersion,
     pub available_encoders: Vec<AV1Encoder>,
+    /// Time a cancelled FFmpeg gets to exit after SIGINT before it is killed
+    cancel_grace: Duration,
+    /// FFmpeg children started for jobs, keyed by job id, plus pause settings
+    registry: Mutex<Registry>,
 }
 
 impl FFmpegManager {
@@ -120,6 +215,16 @@ impl FFmpegManager {
             ));
         }
         
+        // The quality check's metric filter isn't in every FFmpeg build (libvmaf is optional)
+        let check = &config.quality_check;
+        if check.enabled && !Self::has_filter(&ffmpeg_bin, check.metric.filter()).await? {
+            return Err(anyhow!(
+                "quality_check uses {}, but this FFmpeg has no '{}' filter. \
+                 Pick another metric or disable quality_check.",
+                check.metric.label(), check.metric.filter()
+            ));
+        }
+        
         // Log selected encoder
         use log::info;
         info!("🎬 Selected AV1 encoder: {:?} ({})", 
@@ -131,6 +236,8 @@ impl FFmpegManager {
             ffprobe_bin,
             version,
             available_encoders,
+            cancel_grace: Duration::from_secs(config.cancel_grace_secs),
+            registry: Mutex::new(Registry::default()),
         })
     }
     
@@ -191,6 +298,22 @@ impl FFmpegManager {
         Ok(encoders)
     }
     
+    /// Whether FFmpeg lists a filter in `-filters`
+    async fn has_filter(ffmpeg_bin: &Path, name: &str) -> Result<bool> {
+        let output = Command::new(ffmpeg_bin)
+            .arg("-hide_banner")
+            .arg("-filters")
+            .output()
+            .await
+            .with_context(|| format!("Failed to query FFmpeg filters at: {}", ffmpeg_bin.display()))?;
+        
+        if !output.status.success() {
+            return Err(anyhow!("FFmpeg filter query failed"));
+        }
+        
+        Ok(filter_listed(&String::from_utf8_lossy(&output.stdout), name))
+    }
+    
     /// Get the best available encoder (first in priority list)
     pub fn best_encoder(&self) -> &AV1Encoder {
         &self.available_encoders[0]
@@ -199,33 +322,397 @@ impl FFmpegManager {
     /// Execute FFmpeg command directly with proper error handling and timeout
     /// 
     /// This method spawns FFmpeg as a subprocess, captures stdout/stderr,
-    /// and handles timeouts for stuck processes.
+    /// and handles timeouts for stuck processes. A non-zero exit is an error.
     pub async fn execute_ffmpeg(
         &self,
         args: Vec<String>,
         timeout_secs: Option<u64>,
     ) -> Result<FFmpegResult> {
-        use log::{info, debug};
+        let cmd_str = format!("{} {}", self.ffmpeg_bin.display(), args.join(" "));
+        let result = self.run_ffmpeg(args, timeout_secs, None, None).await?;
+        if !result.success {
+            // FFmpeg failed - return detailed error
+            return Err(anyhow!(
+                "FFmpeg encoding failed (exit code: {})\nCommand: {}\nSTDERR:\n{}",
+                result.exit_code.unwrap_or(-1),
+                cmd_str,
+                result.stderr
+            ));
+        }
+        Ok(result)
+    }
+
+    /// Detect letterbox/pillarbox bars with `cropdetect`
+    /// 
+    /// Decodes `CROPDETECT_FRAMES` frames at `samples` points spread evenly over
+    /// the source (only the start when the duration is unknown) and combines the
+    /// suggestions with [`crop::combine`]. Returns None when there is nothing
+    /// worth cropping; a failing sample point is logged and left out. FFmpeg
+    /// runs under the job, so it pauses and cancels with it; a cancel returns `Cancelled`.
+    pub async fn detect_crop(
+        &self,
+        job_id: &str,
+        input: &Path,
+        duration_secs: Option<f64>,
+        samples: u32,
+        source_width: i32,
+        source_height: i32,
+    ) -> Result<Option<CropRect>> {
+        use log::{debug, warn};
+
+        let points: Vec<f64> = match duration_secs.filter(|d| *d > 0.0) {
+            Some(duration) => (1..=samples)
+                .map(|i| duration * i as f64 / (samples as f64 + 1.0))
+                .collect(),
+            None => vec![0.0],
+        };
+
+        let mut found = Vec::new();
+        for point in &points {
+            let args = vec![
+                "-hide_banner".to_string(),
+                "-ss".to_string(),
+                format!("{:.3}", point),
+                "-i".to_string(),
+                input.to_string_lossy().to_string(),
+                "-map".to_string(),
+                "0:V:0".to_string(), // main video, not cover art
+                "-frames:v".to_string(),
+                CROPDETECT_FRAMES.to_string(),
+                "-vf".to_string(),
+                "cropdetect=limit=0.094:round=2:reset=0".to_string(),
+                "-f".to_string(),
+                "null".to_string(),
+                "-".to_string(),
+            ];
+            let what = format!("cropdetect at {:.0}s of {}", point, input.display());
+            let run = self.run_ffmpeg(args, Some(ANALYSIS_TIMEOUT_SECS), Some(job_id), None).await;
+            let Some(result) = sample_run(run, &what)? else {
+                continue;
+            };
+            match crop::parse_cropdetect(&result.stderr) {
+                Some(rect) => {
+                    debug!("cropdetect at {:.0}s: {}", point, rect);
+                    found.push(rect);
+                }
+                None => warn!("{} found nothing", what),
+            }
+        }
+
+        Ok(crop::combine(&found, source_width, source_height))
+    }
+
+    /// Count interlaced and progressive frames with `idet`
+    /// 
+    /// Runs `IDET_FRAMES` frames through idet at `IDET_SAMPLES` points spread
+    /// over the source (only the start when the duration is unknown) and sums
+    /// the counts. A failing sample is logged and left out; returns None if no
+    /// sample produced counts. FFmpeg runs under the job, so it pauses and
+    /// cancels with it; a cancel returns `Cancelled`.
+    pub async fn detect_interlacing(
+        &self,
+        job_id: &str,
+        input: &Path,
+        duration_secs: Option<f64>,
+    ) -> Result<Option<IdetCounts>> {
+        use log::warn;
+
+        let points: Vec<f64> = match duration_secs.filter(|d| *d > 0.0) {
+            Some(duration) => (1..=IDET_SAMPLES)
+                .map(|i| duration * i as f64 / (IDET_SAMPLES as f64 + 1.0))
+                .collect(),
+            None => vec![0.0],
+        };
+
+        let mut total: Option<IdetCounts> = None;
+        for point in &points {
+            let args = vec![
+                "-hide_banner".to_string(),
+                "-ss".to_string(),
+                format!("{:.3}", point),
+                "-i".to_string(),
+                input.to_string_lossy().to_string(),
+                "-map".to_string(),
+                "0:V:0".to_string(),
+                "-frames:v".to_string(),
+                IDET_FRAMES.to_string(),
+                "-vf".to_string(),
+                "idet".to_string(),
+                "-f".to_string(),
+                "null".to_string(),
+                "-".to_string(),
+            ];
+            let what = format!("idet at {:.0}s of {}", point, input.display());
+            let run = self.run_ffmpeg(args, Some(ANALYSIS_TIMEOUT_SECS), Some(job_id), None).await;
+            let Some(result) = sample_run(run, &what)? else {
+                continue;
+            };
+            match IdetCounts::parse(&result.stderr) {
+                Some(counts) => total.get_or_insert_with(IdetCounts::default).add(&counts),
+                None => warn!("{} found nothing", what),
+            }
+        }
+
+        Ok(total)
+    }
+
+    /// Score an encode against its source with the configured metric
+    /// 
+    /// Measures `check.samples` segments of `check.sample_secs` seconds. The
+    /// source goes through the encode's filter chain first (deinterlace, crop,
+    /// scale, format) so both sides line up frame for frame. A segment that can't
+    /// be measured is logged and left out; returns None if none could be. FFmpeg
+    /// runs under the job, so it pauses and cancels with it; a cancel returns `Cancelled`.
+    pub async fn measure_quality(
+        &self,
+        job_id: &str,
+        source: &Path,
+        output: &Path,
+        params: &EncodingParams,
+        check: &QualityCheck,
+        duration_secs: Option<f64>,
+    ) -> Result<Option<QualityScore>> {
+        let reference_filters = CommandBuilder::new().video_filters(params).join(",");
+        let filter_graph = format!(
+            "[0:V:0]format={},setpts=PTS-STARTPTS[dist];[1:V:0]{},setpts=PTS-STARTPTS[ref];[dist][ref]{}",
+            params.pixel_format, reference_filters, check.metric.filter());
+        let timeout_secs = ANALYSIS_TIMEOUT_SECS + 30 * u64::from(check.sample_secs);
+
+        let mut scores = Vec::new();
+        for start in check.sample_starts(duration_secs) {
+            let mut args = Vec::new();
+            // Distorted (the encode) first, reference second, as the metric filters expect
+            for input in [output, source] {
+                args.extend([
+                    "-ss".to_string(),
+                    format!("{:.3}", start),
+                    "-t".to_string(),
+                    check.sample_secs.to_string(),
+                    "-i".to_string(),
+                    input.to_string_lossy().to_string(),
+                ]);
+            }
+            args.extend([
+                "-hide_banner".to_string(),
+                "-filter_complex".to_string(),
+                filter_graph.clone(),
+                "-f".to_string(),
+                "null".to_string(),
+                "-".to_string(),
+            ]);
+
+            let what = format!("{} at {:.0}s of {}", check.metric.label(), start, output.display());
+            let run = self.run_ffmpeg(args, Some(timeout_secs), Some(job_id), None).await;
+            scores.extend(segment_score(check.metric, run, &what)?);
+        }
+
+        Ok(QualityScore::from_samples(check.metric, &scores))
+    }
+
+    /// Execute FFmpeg with machine-readable progress reporting
+    /// 
+    /// Adds `-progress pipe:1 -nostats` so FFmpeg writes key=value progress blocks
+    /// to stdout, and calls `on_progress` for every completed block. `duration_secs`
+    /// (source duration) enables percent-complete and ETA. Progress lines are not
+    /// retained in the returned stdout.
+    /// 
+    /// The child is registered under `job_id` while it runs so it can be stopped
+    /// with [`FFmpegManager::cancel_job`]; a cancelled run returns `Ok` with
+    /// `cancelled` set rather than an error. So does a non-zero exit, with
+    /// `success` unset and the exit code and stderr kept for diagnosis; `Err`
+    /// means FFmpeg couldn't be launched or supervised.
+    pub async fn execute_ffmpeg_with_progress<F>(
+        &self,
+        job_id: &str,
+        args: Vec<String>,
+        timeout_secs: Option<u64>,
+        duration_secs: Option<f64>,
+        mut on_progress: F,
+    ) -> Result<FFmpegResult>
+    where
+        F: FnMut(&EncodeProgress) + Send,
+    {
+        let mut progress_args = vec![
+            "-progress".to_string(),
+            "pipe:1".to_string(),
+            "-nostats".to_string(),
+        ];
+        progress_args.extend(args);
+
+        let mut parser = ProgressParser::new(duration_secs);
+        let mut on_line = |line: &str| {
+            if let Some(progress) = parser.feed_line(line) {
+                on_progress(&progress);
+            }
+        };
+
+        self.run_ffmpeg(progress_args, timeout_secs, Some(job_id), Some(&mut on_line)).await
+    }
+
+    /// Gracefully stop the FFmpeg process running for a job
+    /// 
+    /// FFmpeg receives SIGINT so it can flush and exit; if it is still running after
+    /// the configured grace period it is killed with SIGKILL. Returns false if no
+    /// FFmpeg process is running for the job (or it is already being cancelled).
+    /// The cancelled `execute_ffmpeg_with_progress` call returns once the process exits.
+    pub fn cancel_job(&self, job_id: &str) -> bool {
+        let Ok(mut registry) = self.registry.lock() else {
+            return false;
+        };
+        match registry.running.get_mut(job_id).and_then(|entry| entry.cancel_tx.take()) {
+            Some(cancel_tx) => cancel_tx.send(()).is_ok(),
+            None => false,
+        }
+    }
+
+    /// PID of the FFmpeg process currently running for a job
+    pub fn job_pid(&self, job_id: &str) -> Option<u32> {
+        self.registry.lock().ok()?.running.get(job_id)?.pid
+    }
+
+    /// PIDs of all FFmpeg processes currently running for jobs
+    pub fn running_pids(&self) -> Vec<u32> {
+        self.registry.lock()
+            .map(|registry| registry.running.values().filter_map(|entry| entry.pid).collect())
+            .unwrap_or_default()
+    }
+
+    /// Pause (SIGSTOP) or resume (SIGCONT) the FFmpeg process of a job
+    /// 
+    /// The setting is kept until changed, so FFmpeg started for the job later
+    /// starts paused. Returns true if a running process was signalled.
+    pub fn set_job_paused(&self, job_id: &str, paused: bool) -> bool {
+        let Ok(mut registry) = self.registry.lock() else {
+            return false;
+        };
+        if paused {
+            registry.paused_jobs.insert(job_id.to_string());
+        } else {
+            registry.paused_jobs.remove(job_id);
+        }
+        registry.sync_job(job_id)
+    }
+
+    /// Whether a job has been paused with `set_job_paused`
+    pub fn is_job_paused(&self, job_id: &str) -> bool {
+        self.registry.lock()
+            .map(|registry| registry.paused_jobs.contains(job_id))
+            .unwrap_or(false)
+    }
+
+    /// Whether a job's FFmpeg process is currently stopped by a pause
+    fn is_job_stopped(&self, job_id: &str) -> bool {
+        self.registry.lock()
+            .map(|registry| registry.running.get(job_id).is_some_and(|entry| entry.stopped))
+            .unwrap_or(false)
+    }
+
+    /// Pause or resume every job's FFmpeg process (individually paused jobs stay paused)
+    pub fn set_all_paused(&self, paused: bool) {
+        let Ok(mut registry) = self.registry.lock() else {
+            return;
+        };
+        registry.all_paused = paused;
+        registry.sync_all();
+    }
+
+    /// Pause or resume every job's FFmpeg process for the encoding schedule
+    /// Independent of `set_all_paused`, so closing a window never undoes a user pause
+    pub fn set_schedule_paused(&self, paused: bool) {
+        let Ok(mut registry) = self.registry.lock() else {
+            return;
+        };
+        registry.schedule_paused = paused;
+        registry.sync_all();
+    }
+
+    /// Time a cancelled FFmpeg gets between SIGINT and SIGKILL
+    pub fn cancel_grace(&self) -> Duration {
+        self.cancel_grace
+    }
+
+    /// Run a helper tool for a job, such as the Dolby Vision RPU tool
+    /// 
+    /// The tool is registered under `job_id` like the job's FFmpeg runs, so it is
+    /// paused, resumed and cancelled (SIGINT, then SIGKILL) along with the job.
+    /// Same result semantics as `execute_ffmpeg_with_progress`.
+    pub async fn run_job_tool(
+        &self,
+        job_id: &str,
+        program: &Path,
+        args: Vec<String>,
+        timeout_secs: Option<u64>,
+    ) -> Result<FFmpegResult> {
+        self.run_process(program, args, timeout_secs, Some(job_id), None).await
+    }
+
+    /// Spawn FFmpeg and wait for it to exit (see `run_process`)
+    async fn run_ffmpeg(
+        &self,
+        args: Vec<String>,
+        timeout_secs: Option<u64>,
+        job_id: Option<&str>,
+        on_stdout_line: Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<FFmpegResult> {
+        self.run_process(&self.ffmpeg_bin, args, timeout_secs, job_id, on_stdout_line).await
+    }
+
+    /// Spawn FFmpeg (or another tool taking part in a job) and wait for it to exit
+    /// 
+    /// Stdout lines go to `on_stdout_line` when given, otherwise they are collected
+    /// into the result. Stderr is always collected. When `job_id` is given the child
+    /// is registered for cancellation until it exits. A non-zero exit is returned
+    /// as `Ok` with `success` unset.
+    async fn run_process(
+        &self,
+        program: &Path,
+        args: Vec<String>,
+        timeout_secs: Option<u64>,
+        job_id: Option<&str>,
+        on_stdout_line: Option<&mut (dyn FnMut(&str) + Send)>,
+    ) -> Result<FFmpegResult> {
+        use log::{info, debug, warn};
         use tokio::io::{AsyncBufReadExt, BufReader};
         use tokio::process::Command;
-        use tokio::time::{timeout, Duration};
+        use tokio::time::{sleep, timeout};
         
         // Log the command being executed
-        let cmd_str = format!("{} {}", self.ffmpeg_bin.display(), args.join(" "));
-        debug!("Executing FFmpeg: {}", cmd_str);
+        let cmd_str = format!("{} {}", program.display(), args.join(" "));
+        debug!("Executing: {}", cmd_str);
         
         // Build command
-        let mut cmd = Command::new(&self.ffmpeg_bin);
+        let mut cmd = Command::new(program);
         cmd.args(&args);
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
+        // If the owning task is aborted (e.g. job requeued), don't leave FFmpeg running
+        cmd.kill_on_drop(true);
         
         // Spawn the process
         let mut child = cmd.spawn()
             .with_context(|| format!(
-                "Failed to spawn FFmpeg process at: {}. Ensure FFmpeg is installed and accessible.",
-                self.ffmpeg_bin.display()
+                "Failed to spawn process at: {}. Ensure it is installed and accessible.",
+                program.display()
             ))?;
+        let pid = child.id();
+        
+        // Register the child so the job can be cancelled while it runs
+        let mut cancel_rx = None;
+        let _guard = job_id.map(|job_id| {
+            let (cancel_tx, rx) = oneshot::channel();
+            cancel_rx = Some(rx);
+            if let Ok(mut registry) = self.registry.lock() {
+                registry.running.insert(
+                    job_id.to_string(),
+                    RunningFFmpeg { pid, cancel_tx: Some(cancel_tx), stopped: false },
+                );
+                // A paused job's FFmpeg is stopped right away
+                if registry.sync_job(job_id) {
+                    info!("FFmpeg (pid {:?}) started paused", pid);
+                }
+            }
+            RunningGuard { registry: &self.registry, job_id: job_id.to_string() }
+        });
         
         // Get stdout and stderr handles
         let stdout = child.stdout.take()
@@ -233,10 +720,10 @@ impl FFmpegManager {
         let stderr = child.stderr.take()
             .ok_or_else(|| anyhow!("Failed to capture FFmpeg stderr"))?;
         
-        // Spawn tasks to read stdout and stderr
-        let stdout_handle = tokio::spawn(async move {
+        // Spawn task to read stderr
+        let stderr_handle = tokio::spawn(async move {
             let mut lines = Vec::new();
-            let reader = BufReader::new(stdout);
+            let reader = BufReader::new(stderr);
             let mut line_stream = reader.lines();
             
             while let Ok(Some(line)) = line_stream.next_line().await {
@@ -246,38 +733,89 @@ impl FFmpegManager {
             lines.join("\n")
         });
         
-        let stderr_handle = tokio::spawn(async move {
+        // Read stdout on this task (the line callback may borrow local state), then wait for exit
+        let run = async {
             let mut lines = Vec::new();
-            let reader = BufReader::new(stderr);
-            let mut line_stream = reader.lines();
+            let mut line_stream = BufReader::new(stdout).lines();
+            let mut on_stdout_line = on_stdout_line;
             
             while let Ok(Some(line)) = line_stream.next_line().await {
-                lines.push(line);
+                match on_stdout_line.as_mut() {
+                    Some(callback) => callback(&line),
+                    None => lines.push(line),
+                }
             }
             
-            lines.join("\n")
-        });
+            let status = child.wait().await.context("Failed to wait for FFmpeg process")?;
+            Ok::<_, anyhow::Error>((status, lines.join("\n")))
+        };
+        tokio::pin!(run);
         
-        // Wait for process with optional timeout
-        let status = if let Some(timeout_secs) = timeout_secs {
-            match timeout(Duration::from_secs(timeout_secs), child.wait()).await {
-                Ok(result) => result.context("Failed to wait for FFmpeg process")?,
-                Err(_) => {
-                    // Timeout occurred - kill the process
-                    child.kill().await.context("Failed to kill stuck FFmpeg process")?;
-                    return Err(anyhow!(
-                        "FFmpeg process timed out after {} seconds. Process was killed.",
-                        timeout_secs
-                    ));
+        let deadline = async {
+            let Some(secs) = timeout_secs else {
+                return std::future::pending().await;
+            };
+            // Time spent paused doesn't count towards the timeout
+            let mut remaining = Duration::from_secs(secs);
+            while !remaining.is_zero() {
+                let tick = remaining.min(Duration::from_secs(1));
+                sleep(tick).await;
+                if !job_id.is_some_and(|job_id| self.is_job_stopped(job_id)) {
+                    remaining -= tick;
+                }
+            }
+        };
+        let cancel_requested = async {
+            // A dropped sender is not a cancel request
+            let requested = match cancel_rx {
+                Some(rx) => rx.await.is_ok(),
+                None => false,
+            };
+            if !requested {
+                std::future::pending::<()>().await;
+            }
+        };
+        
+        // Wait for exit, a timeout or a cancel request
+        let mut cancelled = false;
+        let (status, stdout) = tokio::select! {
+            result = &mut run => result?,
+            _ = deadline => {
+                // Timeout occurred - kill the process (dropping the child also kills it)
+                if let Some(pid) = pid {
+                    signal_process(pid, Signal::Kill);
+                }
+                return Err(anyhow!(
+                    "FFmpeg process timed out after {} seconds. Process was killed.",
+                    timeout_secs.unwrap_or_default()
+                ));
+            }
+            _ = cancel_requested => {
+                cancelled = true;
+                info!("Cancelling FFmpeg (pid {:?}): sending SIGINT", pid);
+                if let Some(pid) = pid {
+                    signal_process(pid, Signal::Interrupt);
+                    // A paused FFmpeg must run again to act on SIGINT
+                    signal_process(pid, Signal::Continue);
+                }
+                match timeout(self.cancel_grace, &mut run).await {
+                    Ok(result) => result?,
+                    Err(_) => {
+                        warn!(
+                            "FFmpeg (pid {:?}) still running {}s after SIGINT, sending SIGKILL",
+                            pid,
+                            self.cancel_grace.as_secs()
+                        );
+                        if let Some(pid) = pid {
+                            signal_process(pid, Signal::Kill);
+                        }
+                        run.await?
+                    }
                 }
             }
-        } else {
-            child.wait().await.context("Failed to wait for FFmpeg process")?
         };
         
         // Collect output
-        let stdout = stdout_handle.await
-            .context("Failed to read FFmpeg stdout")?;
         let stderr = stderr_handle.await
             .context("Failed to read FFmpeg stderr")?;
         
@@ -285,23 +823,29 @@ impl FFmpegManager {
         let success = status.success();
         let exit_code = status.code();
         
-        if !success {
-            // FFmpeg failed - return detailed error
-            return Err(anyhow!(
-                "FFmpeg encoding failed (exit code: {})\nCommand: {}\nSTDERR:\n{}",
-                exit_code.unwrap_or(-1),
-                cmd_str,
-                stderr
-            ));
+        if cancelled {
+            info!("FFmpeg cancelled (exit code: {:?})", exit_code);
+            return Ok(FFmpegResult {
+                success: false,
+                exit_code,
+                stdout,
+                stderr,
+                cancelled,
+            });
         }
         
-        info!("FFmpeg execution completed successfully");
+        if success {
+            info!("FFmpeg execution completed successfully");
+        } else {
+            warn!("FFmpeg exited with code {:?}: {}", exit_code, cmd_str);
+        }
         
         Ok(FFmpegResult {
             success,
             exit_code,
             stdout,
             stderr,
+            cancelled,
         })
     }
     
@@ -411,6 +955,91 @@ pub struct FFmpegResult {
     pub exit_code: Option<i32>,
     pub stdout: String,
     pub stderr: String,
+    /// FFmpeg was stopped by `FFmpegManager::cancel_job` (output is incomplete)
+    pub cancelled: bool,
+}
+
+/// Whether `ffmpeg -filters` output lists a filter
+///
+/// Lines look like ` T.. libvmaf           VV->V      Calculate the VMAF ...`.
+fn filter_listed(filters: &str, name: &str) -> bool {
+    filters.lines().any(|line| line.split_whitespace().nth(1) == Some(name))
+}
+
+/// Keep the run of one analysis sample if it succeeded
+///
+/// Only a cancel is an error (ending the whole pass); a run that couldn't
+/// start, timed out or exited non-zero is logged as `what` and gives None so
+/// the pass carries on with its other samples.
+fn sample_run(result: Result<FFmpegResult>, what: &str) -> Result<Option<FFmpegResult>> {
+    use log::warn;
+
+    match result {
+        Ok(result) if result.cancelled => Err(Cancelled.into()),
+        Ok(result) if result.success => Ok(Some(result)),
+        Ok(result) => {
+            warn!("{} failed (exit code {:?}): {}", what, result.exit_code,
+                       result.stderr.lines().last().unwrap_or(""));
+            Ok(None)
+        }
+        Err(e) => {
+            warn!("{} failed: {:#}", what, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Score of one measured quality segment (None if it couldn't be measured,
+/// `Err` only on a cancel; see `sample_run`)
+fn segment_score(metric: QualityMetric, run: Result<FFmpegResult>, what: &str) -> Result<Option<f64>> {
+    use log::{debug, warn};
+
+    let Some(result) = sample_run(run, what)? else {
+        return Ok(None);
+    };
+    let score = metric.parse_score(&result.stderr);
+    match score {
+        Some(score) => debug!("{}: {}", what, score),
+        None => warn!("{} reported no score: {}", what, result.stderr.lines().last().unwrap_or("")),
+    }
+    Ok(score)
+}
+
+/// Error from a job's analysis pass whose FFmpeg was stopped by `FFmpegManager::cancel_job`
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("FFmpeg was cancelled")]
+pub struct Cancelled;
+
+/// Signals used to stop FFmpeg processes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Signal {
+    /// SIGINT: FFmpeg stops encoding and finalizes the output
+    Interrupt,
+    /// SIGKILL: immediate termination
+    Kill,
+    /// SIGSTOP: suspend the process (pause)
+    Stop,
+    /// SIGCONT: continue a suspended process (resume)
+    Continue,
+}
+
+/// Send a signal to a process, returning false if it could not be delivered
+#[cfg(unix)]
+fn signal_process(pid: u32, signal: Signal) -> bool {
+    let signal = match signal {
+        Signal::Interrupt => libc::SIGINT,
+        Signal::Kill => libc::SIGKILL,
+        Signal::Stop => libc::SIGSTOP,
+        Signal::Continue => libc::SIGCONT,
+    };
+    // SAFETY: kill(2) has no memory-safety preconditions
+    unsafe { libc::kill(pid as libc::pid_t, signal) == 0 }
+}
+
+#[cfg(not(unix))]
+fn signal_process(_pid: u32, _signal: Signal) -> bool {
+    // No signals here; cancelled children are killed when dropped (kill_on_drop)
+    false
 }
 
 /// Command builder for generating FFmpeg command lines
@@ -422,21 +1051,39 @@ impl CommandBuilder {
         CommandBuilder
     }
 
+    /// Video filter chain for the encode: deinterlace/IVTC, crop away detected
+    /// bars, downscale, then format conversion
+    pub fn video_filters(&self, params: &EncodingParams) -> Vec<String> {
+        let mut filters = Vec::new();
+        if let Some(scan_type) = params.scan_type {
+            filters.extend(scan_type.filters());
+        }
+        if let Some(crop) = &params.crop {
+            filters.push(crop.filter());
+        }
+        if let Some(scale) = &params.scale {
+            filters.push(scale.filter());
+        }
+        filters.push(format!("format={}", params.pixel_format));
+        filters
+    }
+
     /// Build full encode command for software AV1 encoding
     /// 
     /// Generates FFmpeg command with:
     /// - Input file mapping
-    /// - Video/audio/subtitle stream selection
+    /// - Video/audio/subtitle stream selection (explicit maps from `params.tracks`)
     /// - Format conversion filter chain
     /// - Encoder-specific parameters (CRF, preset, tune, film-grain)
     /// - Audio/subtitle stream copying
+    /// - Muxer flags for the container implied by the output extension (MKV, MP4/M4V, WebM)
     pub fn build_encode_command(
         &self,
         input: &Path,
         output: &Path,
         params: &EncodingParams,
         encoder: &AV1Encoder,
-        _meta: &FFProbeData,
+        meta: &FFProbeData,
     ) -> Vec<String> {
         let mut args = Vec::new();
 
@@ -444,21 +1091,75 @@ impl CommandBuilder {
         args.push("-i".to_string());
         args.push(input.to_string_lossy().to_string());
 
-        // Map all streams: video, audio, and subtitles
-        // -map 0:v:0 = first video stream
-        // -map 0:a? = all audio streams (? makes it optional)
-        // -map 0:s? = all subtitle streams (? makes it optional)
+        // Map streams: main video stream, then audio and subtitles
+        // -map 0:v:0 = first video stream, unless the track plan names the main one
+        // With a track plan each kept track is mapped by its source index,
+        // otherwise -map 0:a? / 0:s? take all of them (? makes it optional)
+        // An audio plan lists every output audio stream, compatibility tracks included
+        let container = Container::from_path(output).unwrap_or(Container::Matroska);
         args.push("-map".to_string());
-        args.push("0:v:0".to_string());
-        args.push("-map".to_string());
-        args.push("0:a?".to_string());
-        args.push("-map".to_string());
-        args.push("0:s?".to_string());
+        match params.tracks.as_ref().and_then(|t| t.video) {
+            Some(index) => args.push(format!("0:{}", index)),
+            None => args.push("0:v:0".to_string()),
+        }
+        let audio_maps: Option<Vec<i32>> = if !params.audio.is_empty() {
+            Some(params.audio.iter().map(|a| a.source_index).collect())
+        } else {
+            params.tracks.as_ref().map(|t| t.audio.clone())
+        };
+        match audio_maps {
+            Some(indexes) => {
+                for index in indexes {
+                    args.push("-map".to_string());
+                    args.push(format!("0:{}", index));
+                }
+            }
+            None => {
+                args.push("-map".to_string());
+                args.push("0:a?".to_string());
+            }
+        }
+        let subtitle_maps: Option<Vec<i32>> = match &params.subtitles {
+            Some(plan) => Some(plan.streams.iter().map(|s| s.source_index).collect()),
+            None => params.tracks.as_ref().map(|t| t.subtitles.clone()),
+        };
+        match subtitle_maps {
+            Some(indexes) => {
+                for index in indexes {
+                    args.push("-map".to_string());
+                    args.push(format!("0:{}", index));
+                }
+            }
+            None => {
+                args.push("-map".to_string());
+                args.push("0:s?".to_string());
+            }
+        }
+
+        // Cover art is copied as extra video streams after the main one (WebM can't hold it),
+        // font and other attachments only fit in Matroska
+        let attached_pictures: &[i32] = match &params.tracks {
+            Some(tracks) if container != Container::WebM => &tracks.attached_pictures,
+            _ => &[],
+        };
+        for index in attached_pictures {
+            args.push("-map".to_string());
+            args.push(format!("0:{}", index));
+        }
+        let copy_attachments = params.tracks.is_some() && container == Container::Matroska;
+        if copy_attachments {
+            args.push("-map".to_string());
+            args.push("0:t?".to_string());
+        }
 
-        // Build filter chain for format conversion
         // Format filter must come before encoder to ensure correct pixel format
-        let filter_chain = format!("format={}", params.pixel_format);
-        args.push("-vf".to_string());
+        // (only on the main stream when cover art is copied - copied streams can't be filtered)
+        let filter_chain = self.video_filters(params).join(",");
+        if attached_pictures.is_empty() {
+            args.push("-vf".to_string());
+        } else {
+            args.push("-filter:v:0".to_string());
+        }
         args.push(filter_chain);
 
         // Video codec and encoder-specific parameters
@@ -493,6 +1194,26 @@ impl CommandBuilder {
                     svt_params.push(format!("film-grain={}", grain));
                 }
 
+                // Limit SVT-AV1 level of parallelism to the job's thread budget
+                if let Some(threads) = params.threads {
+                    svt_params.push(format!("lp={}", threads));
+                }
+
+                // HDR10 static metadata from the source
+                if let Some(color) = &params.color {
+                    if let Some(md) = &color.mastering_display {
+                        svt_params.push(format!("mastering-display={}", md.svt_param()));
+                    }
+                    if let Some(cll) = &color.content_light {
+                        svt_params.push(format!("content-light={},{}", cll.max_cll, cll.max_fall));
+                    }
+                }
+
+                // Extra options from the encoding profile go last so they can override ours
+                if let Some(extra) = &params.extra_encoder_params {
+                    svt_params.push(extra.clone());
+                }
+
                 // Add svtav1-params if we have any
                 if !svt_params.is_empty() {
                     args.push("-svtav1-params".to_string());
@@ -519,6 +1240,18 @@ impl CommandBuilder {
                     args.push("-denoise-noise-level".to_string());
                     args.push(grain.to_string());
                 }
+
+                // Thread budget for this job
+                if let Some(threads) = params.threads {
+                    args.push("-threads".to_string());
+                    args.push(threads.to_string());
+                }
+
+                // Extra options from the encoding profile
+                if let Some(extra) = &params.extra_encoder_params {
+                    args.push("-aom-params".to_string());
+                    args.push(extra.clone());
+                }
             }
             AV1Encoder::LibRav1e => {
                 // librav1e: Use quantizer mode (similar to CRF)
@@ -534,20 +1267,124 @@ impl CommandBuilder {
                 // Pixel format for output
                 args.push("-pix_fmt".to_string());
                 args.push(params.pixel_format.clone());
+
+                // Thread budget for this job
+                if let Some(threads) = params.threads {
+                    args.push("-threads".to_string());
+                    args.push(threads.to_string());
+                }
             }
         }
 
-        // Copy audio streams without re-encoding
-        args.push("-c:a".to_string());
-        args.push("copy".to_string());
+        // Signal the source's colour description (HDR10 needs bt2020/smpte2084)
+        if let Some(color) = &params.color {
+            for (flag, value) in [
+                ("-color_primaries", &color.primaries),
+                ("-color_trc", &color.transfer),
+                ("-colorspace", &color.matrix),
+            ] {
+                if let Some(value) = value {
+                    args.push(flag.to_string());
+                    args.push(value.clone());
+                }
+            }
+        }
 
-        // Copy subtitle streams without re-encoding
-        args.push("-c:s".to_string());
-        args.push("copy".to_string());
+        // Base layer only: keep the encoder from carrying Dolby Vision metadata over
+        if params.strip_dolby_vision {
+            args.push("-dolbyvision".to_string());
+            args.push("0".to_string());
+        }
+
+        // Cover art and attachments are copied as-is
+        for n in 1..=attached_pictures.len() {
+            args.push(format!("-c:v:{}", n));
+            args.push("copy".to_string());
+        }
+        if copy_attachments {
+            args.push("-c:t".to_string());
+            args.push("copy".to_string());
+        }
+
+        // Audio: copied without re-encoding unless the audio plan says otherwise
+        if params.audio.is_empty() {
+            args.push("-c:a".to_string());
+            args.push("copy".to_string());
+        }
+        for (n, output) in params.audio.iter().enumerate() {
+            match &output.action {
+                AudioAction::Copy => {
+                    args.push(format!("-c:a:{}", n));
+                    args.push("copy".to_string());
+                }
+                AudioAction::Encode { codec, bitrate_kbps, channels } => {
+                    args.push(format!("-c:a:{}", n));
+                    args.push(codec.ffmpeg_name().to_string());
+                    args.push(format!("-b:a:{}", n));
+                    args.push(format!("{}k", bitrate_kbps));
+                    args.push(format!("-ac:a:{}", n));
+                    args.push(channels.to_string());
+                    if *codec == AudioCodec::Opus && *channels > 2 {
+                        // Surround Opus needs the Vorbis channel mapping family
+                        args.push(format!("-mapping_family:a:{}", n));
+                        args.push("1".to_string());
+                    }
+                }
+            }
+            if output.compatibility {
+                // Named and non-default so players still pick the lossless original
+                args.push(format!("-metadata:s:a:{}", n));
+                args.push(format!("title={}", output.describe()));
+                args.push(format!("-disposition:a:{}", n));
+                args.push("0".to_string());
+            }
+        }
+
+        // Subtitles: the plan converts codecs the container can't hold per stream.
+        // Without one, everything is copied except MP4 text subtitles (mov_text),
+        // which can't be stored in Matroska and are converted to SRT
+        match &params.subtitles {
+            Some(plan) => {
+                for (n, stream) in plan.streams.iter().enumerate() {
+                    args.push(format!("-c:s:{}", n));
+                    args.push(stream.encoder.unwrap_or("copy").to_string());
+                }
+            }
+            None => {
+                let has_mov_text = meta.streams.iter()
+                    .any(|s| s.codec_name.as_deref() == Some("mov_text"));
+                args.push("-c:s".to_string());
+                if container == Container::Matroska && has_mov_text {
+                    args.push("srt".to_string());
+                } else {
+                    args.push("copy".to_string());
+                }
+            }
+        }
+
+        // Muxer from the output container (temp names like .tmp.av1.m4v don't map to it reliably)
+        args.push("-f".to_string());
+        args.push(container.muxer().to_string());
+        if container == Container::Mp4 {
+            // Index at the front so players can start streaming before the whole file is read
+            args.push("-movflags".to_string());
+            args.push("+faststart".to_string());
+        }
 
         // Output file
         args.push(output.to_string_lossy().to_string());
 
+        // Subtitles extracted to sidecar files are extra outputs of the same run
+        for extracted in params.subtitles.iter().flat_map(|p| &p.extracted) {
+            args.push("-map".to_string());
+            args.push(format!("0:{}", extracted.source_index));
+            args.push("-c:s".to_string());
+            args.push(extracted.encoder.unwrap_or("copy").to_string());
+            args.push("-f".to_string());
+            args.push(extracted.muxer.to_string());
+            args.push(extracted.temp_path.to_string_lossy().to_string());
+        }
+
         args
     }
 
@@ -929,6 +1766,51 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_cancel_running_job() {
+        // This test verifies that cancel_job stops a registered FFmpeg process
+        
+        let config = create_test_config("ffmpeg", "ffprobe");
+        
+        let manager = match FFmpegManager::new(&config).await {
+            Ok(m) => m,
+            Err(_) => {
+                println!("FFmpeg not available, skipping cancel test");
+                return;
+            }
+        };
+        
+        // Nothing is registered before the encode starts
+        assert!(!manager.cancel_job("job-1"));
+        
+        let encode = manager.execute_ffmpeg_with_progress(
+            "job-1",
+            vec!["-re".to_string(), "-f".to_string(), "lavfi".to_string(), "-i".to_string(), "testsrc=duration=600:size=320x240:rate=5".to_string(), "-f".to_string(), "null".to_string(), "-".to_string()],
+            Some(60),
+            Some(600.0),
+            |_| {},
+        );
+        let cancel = async {
+            // Wait for the child to be registered, then cancel it
+            for _ in 0..50 {
+                if manager.job_pid("job-1").is_some() {
+                    return manager.cancel_job("job-1");
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+            }
+            false
+        };
+        
+        let (result, cancel_sent) = tokio::join!(encode, cancel);
+        assert!(cancel_sent, "cancel should reach the running FFmpeg process");
+        let result = result.expect("a cancelled encode is not an error");
+        assert!(result.cancelled);
+        assert!(!result.success);
+        
+        // The registry entry is gone once FFmpeg has exited
+        assert_eq!(manager.job_pid("job-1"), None);
+    }
+
     // Helper to create test FFProbeData for command builder tests
     fn create_test_ffprobe_data() -> FFProbeData {
         use crate::ffprobe::{FFProbeStream, FFProbeFormat};
@@ -951,6 +1833,11 @@ mod tests {
                     color_transfer: None,
                     color_primaries: None,
                     color_space: None,
+                    field_order: None,
+                    channels: None,
+                    channel_layout: None,
+                    profile: None,
+                    side_data_list: None,
                 },
                 FFProbeStream {
                     index: 1,
@@ -968,6 +1855,11 @@ mod tests {
                     color_transfer: None,
                     color_primaries: None,
                     color_space: None,
+                    field_order: None,
+                    channels: None,
+                    channel_layout: None,
+                    profile: None,
+                    side_data_list: None,
                 },
                 FFProbeStream {
                     index: 2,
@@ -985,6 +1877,11 @@ mod tests {
                     color_transfer: None,
                     color_primaries: None,
                     color_space: None,
+                    field_order: None,
+                    channels: None,
+                    channel_layout: None,
+                    profile: None,
+                    side_data_list: None,
                 },
             ],
             format: FFProbeFormat {
@@ -993,6 +1890,7 @@ mod tests {
                 tags: None,
                 muxing_app: None,
                 writing_library: None,
+                duration: None,
             },
         }
     }
@@ -1020,6 +1918,16 @@ mod tests {
             film_grain,
             bit_depth,
             pixel_format: pixel_format.to_string(),
+            threads: None,
+            tracks: None,
+            audio: Vec::new(),
+            subtitles: None,
+            strip_dolby_vision: false,
+            color: None,
+            crop: None,
+            scan_type: None,
+            scale: None,
+            extra_encoder_params: None,
         }
     }
 
@@ -1298,4 +2206,326 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_thread_budget_per_encoder() {
+        let builder = CommandBuilder::new();
+        let input = Path::new("/input/test.mkv");
+        let output = Path::new("/output/test.mkv");
+        let meta = create_test_ffprobe_data();
+
+        let mut params = create_test_encoding_params(24, 6, None, None, "yuv420p10le");
+
+        // No budget: encoder decides, no thread arguments emitted
+        for encoder in [AV1Encoder::SvtAv1, AV1Encoder::LibAom, AV1Encoder::LibRav1e] {
+            let args = builder.build_encode_command(input, output, &params, &encoder, &meta);
+            assert!(!args.iter().any(|a| a == "-threads" || a.contains("lp=")));
+        }
+
+        params.threads = Some(8);
+
+        let svt = builder.build_encode_command(input, output, &params, &AV1Encoder::SvtAv1, &meta);
+        assert!(svt.windows(2).any(|w| w[0] == "-svtav1-params" && w[1].split(':').any(|p| p == "lp=8")));
+
+        for encoder in [AV1Encoder::LibAom, AV1Encoder::LibRav1e] {
+            let args = builder.build_encode_command(input, output, &params, &encoder, &meta);
+            assert!(args.windows(2).any(|w| w[0] == "-threads" && w[1] == "8"));
+        }
+    }
+
+    #[test]
+    fn test_muxer_follows_output_container() {
+        let builder = CommandBuilder::new();
+        let input = Path::new("/input/test.mp4");
+        let params = create_test_encoding_params(24, 6, None, None, "yuv420p");
+        let mut meta = create_test_ffprobe_data();
+        meta.streams[2].codec_name = Some("mov_text".to_string());
+
+        let mp4 = builder.build_encode_command(input, Path::new("/tmp/test.tmp.av1.m4v"), &params, &AV1Encoder::SvtAv1, &meta);
+        assert!(mp4.windows(2).any(|w| w[0] == "-f" && w[1] == "mp4"));
+        assert!(mp4.windows(2).any(|w| w[0] == "-movflags" && w[1] == "+faststart"));
+        assert!(mp4.windows(2).any(|w| w[0] == "-c:s" && w[1] == "copy"));
+        assert_eq!(mp4.last().map(String::as_str), Some("/tmp/test.tmp.av1.m4v"));
+
+        // MP4 text subtitles are converted when the output becomes Matroska
+        let mkv = builder.build_encode_command(input, Path::new("/tmp/test.tmp.av1.mkv"), &params, &AV1Encoder::SvtAv1, &meta);
+        assert!(mkv.windows(2).any(|w| w[0] == "-f" && w[1] == "matroska"));
+        assert!(mkv.windows(2).any(|w| w[0] == "-c:s" && w[1] == "srt"));
+        assert!(!mkv.iter().any(|a| a == "-movflags"));
+    }
+
+    #[test]
+    fn test_track_plan_maps_kept_streams() {
+        use crate::tracks::TrackPlan;
+
+        let builder = CommandBuilder::new();
+        let input = Path::new("/input/test.mkv");
+        let output = Path::new("/output/test.mkv");
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(24, 6, None, None, "yuv420p");
+
+        // Without a plan all audio and subtitle streams are mapped
+        let args = builder.build_encode_command(input, output, &params, &AV1Encoder::SvtAv1, &meta);
+        assert!(args.windows(2).any(|w| w[0] == "-map" && w[1] == "0:a?"));
+
+        params.tracks = Some(TrackPlan { audio: vec![1], subtitles: Vec::new(), dropped: Vec::new(), ..Default::default() });
+        let args = builder.build_encode_command(input, output, &params, &AV1Encoder::SvtAv1, &meta);
+        let maps: Vec<&str> = args.windows(2)
+            .filter(|w| w[0] == "-map")
+            .map(|w| w[1].as_str())
+            .collect();
+        assert_eq!(maps, vec!["0:v:0", "0:1", "0:t?"]);
+    }
+
+    #[test]
+    fn test_audio_plan_sets_per_stream_codecs() {
+        use crate::audio::{AudioAction, AudioCodec, AudioOutput};
+        use crate::tracks::TrackPlan;
+
+        let builder = CommandBuilder::new();
+        let input = Path::new("/input/test.mkv");
+        let output = Path::new("/output/test.mkv");
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(24, 6, None, None, "yuv420p");
+        params.tracks = Some(TrackPlan { audio: vec![1, 2], subtitles: vec![3], dropped: Vec::new(), ..Default::default() });
+        params.audio = vec![
+            AudioOutput { source_index: 1, action: AudioAction::Copy, compatibility: false },
+            AudioOutput { source_index: 2, action: AudioAction::Copy, compatibility: false },
+            AudioOutput {
+                source_index: 1,
+                action: AudioAction::Encode { codec: AudioCodec::Opus, bitrate_kbps: 384, channels: 6 },
+                compatibility: true,
+            },
+        ];
+
+        let args = builder.build_encode_command(input, output, &params, &AV1Encoder::SvtAv1, &meta);
+        let maps: Vec<&str> = args.windows(2)
+            .filter(|w| w[0] == "-map")
+            .map(|w| w[1].as_str())
+            .collect();
+        assert_eq!(maps, vec!["0:v:0", "0:1", "0:2", "0:1", "0:3", "0:t?"]);
+        let has = |flag: &str, value: &str| args.windows(2).any(|w| w[0] == flag && w[1] == value);
+        assert!(!args.contains(&"-c:a".to_string()));
+        assert!(has("-c:a:0", "copy"));
+        assert!(has("-c:a:2", "libopus"));
+        assert!(has("-b:a:2", "384k"));
+        assert!(has("-mapping_family:a:2", "1"));
+        assert!(has("-disposition:a:2", "0"));
+        assert!(has("-metadata:s:a:2", "title=Opus 5.1 384k"));
+    }
+
+    #[test]
+    fn test_crop_comes_before_format_filter() {
+        use crate::crop::CropRect;
+
+        let builder = CommandBuilder::new();
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(24, 6, None, None, "yuv420p10le");
+        params.crop = Some(CropRect { width: 1920, height: 800, x: 0, y: 140 });
+
+        let args = builder.build_encode_command(Path::new("/input/test.mkv"), Path::new("/output/test.mkv"),
+                                                &params, &AV1Encoder::SvtAv1, &meta);
+        assert!(args.windows(2).any(|w| w[0] == "-vf" && w[1] == "crop=1920:800:0:140,format=yuv420p10le"));
+    }
+
+    #[test]
+    fn test_deinterlace_comes_before_crop() {
+        use crate::crop::CropRect;
+        use crate::interlace::ScanType;
+
+        let builder = CommandBuilder::new();
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(24, 6, None, None, "yuv420p");
+        params.scan_type = Some(ScanType::Telecined);
+        params.crop = Some(CropRect { width: 704, height: 480, x: 8, y: 0 });
+
+        let args = builder.build_encode_command(Path::new("/input/test.mkv"), Path::new("/output/test.mkv"),
+                                                &params, &AV1Encoder::SvtAv1, &meta);
+        let vf = args.windows(2).find(|w| w[0] == "-vf").map(|w| w[1].as_str());
+        assert_eq!(vf, Some("fieldmatch=order=auto:combmatch=full,bwdif=mode=send_frame:deint=interlaced,decimate,crop=704:480:8:0,format=yuv420p"));
+    }
+
+    #[test]
+    fn test_profile_encoder_options() {
+        let builder = CommandBuilder::new();
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(24, 6, None, Some(8), "yuv420p10le");
+        params.extra_encoder_params = Some("enable-qm=1:qm-min=0".to_string());
+        let (input, output) = (Path::new("/input/test.mkv"), Path::new("/output/test.mkv"));
+
+        let args = builder.build_encode_command(input, output, &params, &AV1Encoder::SvtAv1, &meta);
+        assert!(args.windows(2).any(|w| w[0] == "-svtav1-params" && w[1] == "film-grain=8:enable-qm=1:qm-min=0"));
+
+        let args = builder.build_encode_command(input, output, &params, &AV1Encoder::LibAom, &meta);
+        assert!(args.windows(2).any(|w| w[0] == "-aom-params" && w[1] == "enable-qm=1:qm-min=0"));
+    }
+
+    #[test]
+    fn test_scale_comes_after_crop() {
+        use crate::crop::CropRect;
+        use crate::scale::ScaleTarget;
+
+        let builder = CommandBuilder::new();
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(30, 6, None, None, "yuv420p10le");
+        params.crop = Some(CropRect { width: 3840, height: 1600, x: 0, y: 280 });
+        params.scale = Some(ScaleTarget { width: 1920, height: 800 });
+
+        let args = builder.build_encode_command(Path::new("/input/test.mkv"), Path::new("/output/test.mkv"),
+                                                &params, &AV1Encoder::SvtAv1, &meta);
+        let vf = args.windows(2).find(|w| w[0] == "-vf").map(|w| w[1].as_str());
+        assert_eq!(vf, Some("crop=3840:1600:0:280,scale=1920:800:flags=lanczos,format=yuv420p10le"));
+    }
+
+    #[test]
+    fn test_cover_art_is_copied() {
+        use crate::tracks::TrackPlan;
+
+        let builder = CommandBuilder::new();
+        let input = Path::new("/input/test.mp4");
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(24, 6, None, None, "yuv420p");
+        params.tracks = Some(TrackPlan { video: Some(1), attached_pictures: vec![0], audio: vec![2], ..Default::default() });
+
+        let args = builder.build_encode_command(input, Path::new("/tmp/test.tmp.av1.mp4"), &params, &AV1Encoder::SvtAv1, &meta);
+        let maps: Vec<&str> = args.windows(2)
+            .filter(|w| w[0] == "-map")
+            .map(|w| w[1].as_str())
+            .collect();
+        // MP4 holds cover art but no attachments
+        assert_eq!(maps, vec!["0:1", "0:2", "0:0"]);
+        assert!(args.windows(2).any(|w| w[0] == "-c:v:1" && w[1] == "copy"));
+        assert!(args.windows(2).any(|w| w[0] == "-filter:v:0" && w[1] == "format=yuv420p"));
+        assert!(!args.contains(&"-vf".to_string()));
+
+        // WebM can't hold cover art
+        let args = builder.build_encode_command(input, Path::new("/tmp/test.tmp.av1.webm"), &params, &AV1Encoder::SvtAv1, &meta);
+        assert!(!args.contains(&"0:0".to_string()));
+        assert!(args.contains(&"-vf".to_string()));
+    }
+
+    #[test]
+    fn test_hdr10_metadata_is_passed_through() {
+        use crate::color::{Chromaticity, ColorMetadata, ContentLightLevel, MasteringDisplay, Rational};
+
+        let builder = CommandBuilder::new();
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(20, 3, None, None, "yuv420p10le");
+        let xy = |x, y| Chromaticity { x: Rational { num: x, den: 50000 }, y: Rational { num: y, den: 50000 } };
+        params.color = Some(ColorMetadata {
+            primaries: Some("bt2020".to_string()),
+            transfer: Some("smpte2084".to_string()),
+            matrix: Some("bt2020nc".to_string()),
+            mastering_display: Some(MasteringDisplay {
+                red: xy(34000, 16000),
+                green: xy(13250, 34500),
+                blue: xy(7500, 3000),
+                white_point: xy(15635, 16450),
+                min_luminance: Rational { num: 50, den: 10000 },
+                max_luminance: Rational { num: 1000, den: 1 },
+            }),
+            content_light: Some(ContentLightLevel { max_cll: 1000, max_fall: 400 }),
+        });
+
+        let args = builder.build_encode_command(Path::new("/input/hdr.mkv"), Path::new("/output/hdr.mkv"),
+                                                &params, &AV1Encoder::SvtAv1, &meta);
+        let has = |flag: &str, value: &str| args.windows(2).any(|w| w[0] == flag && w[1] == value);
+        assert!(has("-color_primaries", "bt2020"));
+        assert!(has("-color_trc", "smpte2084"));
+        assert!(has("-colorspace", "bt2020nc"));
+        let svt = args.windows(2).find(|w| w[0] == "-svtav1-params").map(|w| w[1].clone()).unwrap();
+        assert!(svt.contains("mastering-display=G(0.2650,0.6900)B(0.1500,0.0600)R(0.6800,0.3200)WP(0.3127,0.3290)L(1000.0000,0.0050)"));
+        assert!(svt.contains("content-light=1000,400"));
+
+        // libaom gets the colour tags but has no static metadata options
+        let args = builder.build_encode_command(Path::new("/input/hdr.mkv"), Path::new("/output/hdr.mkv"),
+                                                &params, &AV1Encoder::LibAom, &meta);
+        assert!(args.windows(2).any(|w| w[0] == "-color_trc" && w[1] == "smpte2084"));
+    }
+
+    #[test]
+    fn test_subtitle_plan_converts_and_extracts() {
+        use crate::subtitles::{ExtractedSubtitle, SubtitlePlan, SubtitleStream};
+
+        let builder = CommandBuilder::new();
+        let input = Path::new("/input/test.mp4");
+        let output = Path::new("/tmp/test.tmp.av1.mp4");
+        let meta = create_test_ffprobe_data();
+        let mut params = create_test_encoding_params(24, 6, None, None, "yuv420p");
+        params.subtitles = Some(SubtitlePlan {
+            streams: vec![SubtitleStream { source_index: 2, encoder: Some("mov_text") }],
+            extracted: vec![ExtractedSubtitle {
+                source_index: 3,
+                encoder: None,
+                muxer: "sup",
+                temp_path: PathBuf::from("/tmp/test.tmp.av1.s3.sup"),
+                final_path: PathBuf::from("/input/test.3.sup"),
+            }],
+            dropped: Vec::new(),
+        });
+
+        let args = builder.build_encode_command(input, output, &params, &AV1Encoder::SvtAv1, &meta);
+        assert!(args.windows(2).any(|w| w[0] == "-c:s:0" && w[1] == "mov_text"));
+        assert!(!args.contains(&"0:s?".to_string()));
+        // The sidecar is a second output after the main one
+        let main = args.iter().position(|a| a == "/tmp/test.tmp.av1.mp4").unwrap();
+        assert_eq!(&args[main + 1..], &["-map", "0:3", "-c:s", "copy", "-f", "sup", "/tmp/test.tmp.av1.s3.sup"]);
+    }
+
+    #[test]
+    fn test_filter_listed() {
+        let filters = "\
+Filters:
+  T.. = Timeline support
+ ... libvmaf           VV->V      Calculate the VMAF between two video streams.
+ TS. ssim              VV->V      Calculate the SSIM between two video streams.";
+        assert!(filter_listed(filters, "libvmaf"));
+        assert!(filter_listed(filters, "ssim"));
+        assert!(!filter_listed(filters, "xpsnr"));
+        assert!(!filter_listed(filters, "VV->V"));
+    }
+
+    fn sample_result(success: bool, cancelled: bool, stderr: &str) -> FFmpegResult {
+        FFmpegResult {
+            success,
+            exit_code: Some(if success { 0 } else { 1 }),
+            stdout: String::new(),
+            stderr: stderr.to_string(),
+            cancelled,
+        }
+    }
+
+    #[test]
+    fn test_failed_sample_is_left_out() {
+        let ok = sample_run(Ok(sample_result(true, false, "crop=1920:800:0:140")), "cropdetect").unwrap();
+        assert_eq!(ok.map(|r| r.stderr), Some("crop=1920:800:0:140".to_string()));
+        // A bad seek point or a timeout doesn't end the pass
+        assert!(sample_run(Ok(sample_result(false, false, "Invalid data found")), "cropdetect").unwrap().is_none());
+        assert!(sample_run(Err(anyhow!("FFmpeg process timed out")), "cropdetect").unwrap().is_none());
+        // A cancel does
+        let cancelled = sample_run(Ok(sample_result(false, true, "")), "cropdetect").unwrap_err();
+        assert!(cancelled.is::<Cancelled>());
+    }
+
+    #[test]
+    fn test_quality_score_from_partly_failing_segments() {
+        let runs = vec![
+            Ok(sample_result(true, false, "[Parsed_libvmaf_2 @ 0x55d1] VMAF score: 95.500000")),
+            Err(anyhow!("FFmpeg process timed out after 420 seconds. Process was killed.")),
+            Ok(sample_result(false, false, "Error while filtering: Invalid argument")),
+            Ok(sample_result(true, false, "[Parsed_libvmaf_2 @ 0x55d1] VMAF score: 92.500000")),
+        ];
+        let scores: Vec<f64> = runs.into_iter()
+            .map(|run| segment_score(QualityMetric::Vmaf, run, "VMAF"))
+            .collect::<Result<Vec<_>>>()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .collect();
+        let score = QualityScore::from_samples(QualityMetric::Vmaf, &scores).unwrap();
+        assert_eq!((score.mean, score.min, score.samples), (94.0, 92.5, 2));
+
+        let cancelled = segment_score(QualityMetric::Vmaf, Ok(sample_result(false, true, "")), "VMAF");
+        assert!(cancelled.unwrap_err().is::<Cancelled>());
+    }
 }
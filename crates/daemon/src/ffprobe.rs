@@ -2,6 +2,7 @@ use std::path::Path;
 use std::collections::HashMap;
 use anyhow::{Context, Result};
 use serde::Deserialize;
+use crate::color::{ContentLightLevel, MasteringDisplay};
 use crate::config::TranscodeConfig;
 use crate::ffmpeg_native::FFmpegManager;
 use tokio::process::Command;
@@ -25,6 +26,17 @@ pub struct FFProbeFormat {
     pub muxing_app: Option<String>,
     #[serde(rename = "writing_library")]
     pub writing_library: Option<String>,
+    /// Container duration in seconds (ffprobe reports it as a string)
+    pub duration: Option<String>,
+}
+
+impl FFProbeFormat {
+    /// Container duration in seconds, if ffprobe reported a usable value
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.duration.as_deref()
+            .and_then(|d| d.parse::<f64>().ok())
+            .filter(|d| *d > 0.0)
+    }
 }
 
 /// Stream-level metadata from ffprobe
@@ -55,6 +67,21 @@ pub struct FFProbeStream {
     pub color_primaries: Option<String>,
     #[serde(rename = "color_space")]
     pub color_space: Option<String>,
+    /// Video field order ("progressive", "tt", "bb", "tb", "bt")
+    #[serde(default)]
+    pub field_order: Option<String>,
+    /// Audio channel count
+    #[serde(default)]
+    pub channels: Option<i32>,
+    /// Audio channel layout (e.g. "5.1(side)")
+    #[serde(default)]
+    pub channel_layout: Option<String>,
+    /// Codec profile (e.g. "DTS-HD MA" for DTS)
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// Per-stream side data (e.g. the "DOVI configuration record" of Dolby Vision streams)
+    #[serde(default)]
+    pub side_data_list: Option<Vec<HashMap<String, serde_json::Value>>>,
 }
 
 /// Run ffprobe directly and parse the JSON output
@@ -151,6 +178,55 @@ pub async fn probe_file_native(ffmpeg_mgr: &FFmpegManager, file_path: &Path) ->
 }
 
 
+/// Frame side data type of HDR10+ dynamic metadata (SMPTE ST 2094-40)
+pub const HDR10_PLUS_SIDE_DATA: &str = "HDR Dynamic Metadata SMPTE2094-40 (HDR10+)";
+
+#[derive(Debug, Deserialize)]
+struct FFProbeFrames {
+    #[serde(default)]
+    frames: Vec<FFProbeFrame>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FFProbeFrame {
+    #[serde(default)]
+    side_data_list: Vec<HashMap<String, serde_json::Value>>,
+}
+
+/// Side data types on the first `frames` frames of the main video stream
+///
+/// Dynamic metadata such as HDR10+ only shows up per frame, so this decodes
+/// the start of the file; keep `frames` small.
+pub async fn probe_frame_side_data(ffmpeg_mgr: &FFmpegManager, file_path: &Path, frames: u32) -> Result<Vec<String>> {
+    let args = vec![
+        "-v".to_string(),
+        "error".to_string(),
+        "-select_streams".to_string(),
+        "V:0".to_string(), // first video stream that isn't cover art
+        "-read_intervals".to_string(),
+        format!("%+#{}", frames),
+        "-show_frames".to_string(),
+        "-show_entries".to_string(),
+        "frame=side_data_list".to_string(),
+        "-print_format".to_string(),
+        "json".to_string(),
+        file_path.to_string_lossy().to_string(),
+    ];
+
+    let output_str = ffmpeg_mgr.execute_ffprobe_raw(args).await
+        .with_context(|| format!("Failed to probe frames of: {}", file_path.display()))?;
+    let data: FFProbeFrames = serde_json::from_str(&output_str)
+        .with_context(|| format!("Failed to parse ffprobe frame JSON for: {}", file_path.display()))?;
+
+    let mut types: Vec<String> = data.frames.iter()
+        .flat_map(|f| &f.side_data_list)
+        .filter_map(|sd| sd.get("side_data_type")?.as_str().map(str::to_string))
+        .collect();
+    types.sort();
+    types.dedup();
+    Ok(types)
+}
+
 /// Bit depth of video content
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BitDepth {
@@ -226,6 +302,38 @@ impl FFProbeStream {
         false
     }
     
+    /// Cover art stored as a video stream (`attached_pic` disposition)
+    pub fn is_attached_picture(&self) -> bool {
+        self.disposition.as_ref()
+            .and_then(|d| d.get("attached_pic"))
+            .is_some_and(|v| *v != 0)
+    }
+
+    /// First side data entry of a type (e.g. "Mastering display metadata")
+    pub fn side_data(&self, side_data_type: &str) -> Option<&HashMap<String, serde_json::Value>> {
+        self.side_data_list.as_ref()?
+            .iter()
+            .find(|sd| sd.get("side_data_type").and_then(|t| t.as_str()) == Some(side_data_type))
+    }
+
+    /// Dolby Vision profile from the "DOVI configuration record" side data
+    pub fn dolby_vision_profile(&self) -> Option<u8> {
+        self.side_data("DOVI configuration record")?
+            .get("dv_profile")?
+            .as_u64()
+            .and_then(|p| u8::try_from(p).ok())
+    }
+
+    /// HDR10 mastering display colour volume from the stream's side data
+    pub fn mastering_display(&self) -> Option<MasteringDisplay> {
+        MasteringDisplay::from_side_data(self.side_data("Mastering display metadata")?)
+    }
+
+    /// HDR10 MaxCLL/MaxFALL from the stream's side data
+    pub fn content_light_level(&self) -> Option<ContentLightLevel> {
+        ContentLightLevel::from_side_data(self.side_data("Content light level metadata")?)
+    }
+
     /// Check if content has Dolby Vision metadata
     /// Dolby Vision can cause corruption with QSV AV1 encoding and should be stripped
     pub fn has_dolby_vision(&self) -> bool {
@@ -404,6 +512,11 @@ mod tests {
                 color_transfer: Some(color_transfer.clone()),
                 color_primaries: None,
                 color_space: None,
+                field_order: None,
+                channels: None,
+                channel_layout: None,
+                profile: None,
+                side_data_list: None,
             };
 
             prop_assert!(
@@ -435,6 +548,11 @@ mod tests {
                 color_transfer: None,
                 color_primaries: None,
                 color_space: None,
+                field_order: None,
+                channels: None,
+                channel_layout: None,
+                profile: None,
+                side_data_list: None,
             };
 
             prop_assert!(
@@ -466,6 +584,11 @@ mod tests {
                 color_transfer: None,
                 color_primaries: None,
                 color_space: None,
+                field_order: None,
+                channels: None,
+                channel_layout: None,
+                profile: None,
+                side_data_list: None,
             };
 
             prop_assert!(
@@ -506,6 +629,11 @@ mod tests {
                     },
                     color_primaries: None,
                     color_space: None,
+                    field_order: None,
+                    channels: None,
+                    channel_layout: None,
+                    profile: None,
+                    side_data_list: None,
                 };
                 streams.push(stream);
             }
@@ -518,6 +646,7 @@ mod tests {
                     tags: None,
                     muxing_app: None,
                     writing_library: None,
+                    duration: None,
                 },
             };
 
@@ -560,6 +689,11 @@ mod tests {
                 color_transfer,
                 color_primaries: None,
                 color_space: None,
+                field_order: None,
+                channels: None,
+                channel_layout: None,
+                profile: None,
+                side_data_list: None,
             };
 
             prop_assert!(
@@ -593,6 +727,11 @@ mod tests {
                 color_transfer: Some(color_transfer.clone()),
                 color_primaries: None,
                 color_space: None,
+                field_order: None,
+                channels: None,
+                channel_layout: None,
+                profile: None,
+                side_data_list: None,
             };
 
             prop_assert!(
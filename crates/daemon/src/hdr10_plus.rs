@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+use crate::ffmpeg_native::AV1Encoder;
+
+/// What to do with HDR10+ sources (SMPTE ST 2094-40 dynamic metadata)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Hdr10PlusPolicy {
+    /// Leave HDR10+ files untouched
+    Skip,
+    /// Encode with a warning; the output keeps only the HDR10 static metadata
+    #[default]
+    Warn,
+    /// Carry the dynamic metadata through if the encoder supports it (libaom),
+    /// otherwise encode as with `warn`
+    Preserve,
+}
+
+/// How an HDR10+ source was handled, recorded on the job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Hdr10PlusHandling {
+    Skipped,
+    /// Encoded as plain HDR10, the dynamic metadata is gone
+    MetadataLost,
+    /// Dynamic metadata found in the output
+    Preserved,
+}
+
+impl std::fmt::Display for Hdr10PlusHandling {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Hdr10PlusHandling::Skipped => "skipped",
+            Hdr10PlusHandling::MetadataLost => "dynamic metadata lost (plain HDR10)",
+            Hdr10PlusHandling::Preserved => "dynamic metadata preserved",
+        };
+        f.write_str(text)
+    }
+}
+
+impl Hdr10PlusPolicy {
+    /// Expected handling of an HDR10+ source with the given encoder
+    ///
+    /// `Preserved` still has to be confirmed on the output.
+    pub fn decide(self, encoder: &AV1Encoder) -> Hdr10PlusHandling {
+        match self {
+            Hdr10PlusPolicy::Skip => Hdr10PlusHandling::Skipped,
+            Hdr10PlusPolicy::Preserve if encoder.passes_hdr10_plus() => Hdr10PlusHandling::Preserved,
+            Hdr10PlusPolicy::Preserve | Hdr10PlusPolicy::Warn => Hdr10PlusHandling::MetadataLost,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decide() {
+        assert_eq!(Hdr10PlusPolicy::Skip.decide(&AV1Encoder::LibAom), Hdr10PlusHandling::Skipped);
+        assert_eq!(Hdr10PlusPolicy::Warn.decide(&AV1Encoder::LibAom), Hdr10PlusHandling::MetadataLost);
+        assert_eq!(Hdr10PlusPolicy::Preserve.decide(&AV1Encoder::LibAom), Hdr10PlusHandling::Preserved);
+        // SVT-AV1 can't carry it, so preserve falls back to encoding without it
+        assert_eq!(Hdr10PlusPolicy::Preserve.decide(&AV1Encoder::SvtAv1), Hdr10PlusHandling::MetadataLost);
+    }
+
+    #[test]
+    fn test_policy_from_json() {
+        let policy: Hdr10PlusPolicy = serde_json::from_str("\"preserve\"").unwrap();
+        assert_eq!(policy, Hdr10PlusPolicy::Preserve);
+        assert_eq!(Hdr10PlusPolicy::default(), Hdr10PlusPolicy::Warn);
+    }
+}
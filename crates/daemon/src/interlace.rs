@@ -0,0 +1,252 @@
+use serde::{Deserialize, Serialize};
+use crate::ffprobe::FFProbeStream;
+
+/// Whether interlaced or telecined sources get deinterlaced
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeinterlacePolicy {
+    /// Check suspicious sources with idet and deinterlace (bwdif) or inverse
+    /// telecine (fieldmatch/decimate) them as needed
+    #[default]
+    Auto,
+    /// Encode every source as-is
+    Off,
+}
+
+/// Scan type of the source as detected by idet, recorded on the job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanType {
+    Progressive,
+    /// Interlaced, top field first
+    InterlacedTff,
+    /// Interlaced, bottom field first
+    InterlacedBff,
+    /// Film hard-telecined to 29.97i (3:2 pulldown)
+    Telecined,
+}
+
+impl std::fmt::Display for ScanType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            ScanType::Progressive => "progressive",
+            ScanType::InterlacedTff => "interlaced, top field first (bwdif)",
+            ScanType::InterlacedBff => "interlaced, bottom field first (bwdif)",
+            ScanType::Telecined => "telecined (inverse telecine to 23.976)",
+        };
+        f.write_str(text)
+    }
+}
+
+impl ScanType {
+    /// Filters that turn the source into progressive frames, in order
+    pub fn filters(self) -> Vec<String> {
+        match self {
+            ScanType::Progressive => Vec::new(),
+            ScanType::InterlacedTff => vec!["bwdif=mode=send_frame:parity=tff:deint=all".to_string()],
+            ScanType::InterlacedBff => vec!["bwdif=mode=send_frame:parity=bff:deint=all".to_string()],
+            // Match fields back into film frames, deinterlace what fieldmatch couldn't
+            // match (e.g. video-based credits), then drop the duplicate of every 5
+            ScanType::Telecined => vec![
+                "fieldmatch=order=auto:combmatch=full".to_string(),
+                "bwdif=mode=send_frame:deint=interlaced".to_string(),
+                "decimate".to_string(),
+            ],
+        }
+    }
+}
+
+/// Frame counts from idet's "Multi frame detection" and "Repeated Fields" summaries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IdetCounts {
+    pub tff: u64,
+    pub bff: u64,
+    pub progressive: u64,
+    pub undetermined: u64,
+    /// Frames whose top field repeats the previous frame's
+    pub repeated_top: u64,
+    /// Frames whose bottom field repeats the previous frame's
+    pub repeated_bottom: u64,
+    /// Frames without a repeated field
+    pub repeated_neither: u64,
+}
+
+/// Fewer determined frames than this and the idet result is ignored
+const MIN_DETERMINED_FRAMES: u64 = 100;
+
+/// Share of frames with a repeated field that 3:2 pulldown produces (nominally
+/// two out of every five)
+const PULLDOWN_REPEAT_RATE: std::ops::RangeInclusive<f64> = 0.3..=0.5;
+
+/// `key: value` pairs of the last line of FFmpeg's stderr containing `marker`
+fn summary_values<'a>(stderr: &'a str, marker: &str) -> Option<Vec<(&'a str, u64)>> {
+    let line = stderr.lines().rev().find(|l| l.contains(marker))?;
+    let (_, summary) = line.split_once(marker)?;
+    let mut values = Vec::new();
+    let mut tokens = summary.split_whitespace();
+    while let (Some(key), Some(value)) = (tokens.next(), tokens.next()) {
+        values.push((key, value.parse().ok()?));
+    }
+    Some(values)
+}
+
+impl IdetCounts {
+    /// Parse the last "Multi frame detection" and "Repeated Fields" lines of
+    /// FFmpeg's stderr (None without frame detection counts)
+    ///
+    /// The multi frame counts look at neighbouring frames too, so they are less
+    /// noisy than the single frame ones.
+    pub fn parse(stderr: &str) -> Option<Self> {
+        let mut counts = IdetCounts::default();
+        for (key, value) in summary_values(stderr, "Multi frame detection:")? {
+            match key {
+                "TFF:" => counts.tff = value,
+                "BFF:" => counts.bff = value,
+                "Progressive:" => counts.progressive = value,
+                "Undetermined:" => counts.undetermined = value,
+                _ => {}
+            }
+        }
+        for (key, value) in summary_values(stderr, "Repeated Fields:").unwrap_or_default() {
+            match key {
+                "Neither:" => counts.repeated_neither = value,
+                "Top:" => counts.repeated_top = value,
+                "Bottom:" => counts.repeated_bottom = value,
+                _ => {}
+            }
+        }
+        Some(counts)
+    }
+
+    /// Sum with the counts of another sample
+    pub fn add(&mut self, other: &IdetCounts) {
+        self.tff += other.tff;
+        self.bff += other.bff;
+        self.progressive += other.progressive;
+        self.undetermined += other.undetermined;
+        self.repeated_top += other.repeated_top;
+        self.repeated_bottom += other.repeated_bottom;
+        self.repeated_neither += other.repeated_neither;
+    }
+
+    /// Whether fields repeat at the rate 3:2 pulldown repeats them
+    fn has_pulldown_cadence(&self) -> bool {
+        let repeated = self.repeated_top + self.repeated_bottom;
+        let total = repeated + self.repeated_neither;
+        total >= MIN_DETERMINED_FRAMES && PULLDOWN_REPEAT_RATE.contains(&(repeated as f64 / total as f64))
+    }
+
+    /// Scan type suggested by the counts (None if too few frames were determined)
+    ///
+    /// Mostly combed frames mean interlaced video. Hard telecine combs two out
+    /// of every five frames and repeats a field in two out of five, so an NTSC
+    /// source with a sizeable minority of combed frames is treated as telecined
+    /// film only when its fields repeat at that rate. Otherwise it is interlaced
+    /// video with still or slow scenes and only gets deinterlaced.
+    pub fn scan_type(&self, ntsc_rate: bool) -> Option<ScanType> {
+        let interlaced = self.tff + self.bff;
+        let determined = interlaced + self.progressive;
+        if determined < MIN_DETERMINED_FRAMES {
+            return None;
+        }
+        let ratio = interlaced as f64 / determined as f64;
+        let interlaced_type = if self.tff >= self.bff { ScanType::InterlacedTff } else { ScanType::InterlacedBff };
+        Some(if ratio >= 0.8 {
+            interlaced_type
+        } else if ntsc_rate && ratio >= 0.2 {
+            if self.has_pulldown_cadence() { ScanType::Telecined } else { interlaced_type }
+        } else if ratio >= 0.5 {
+            interlaced_type
+        } else {
+            ScanType::Progressive
+        })
+    }
+}
+
+/// Whether a video stream could be interlaced or telecined and needs an idet pass
+///
+/// MPEG-2 and VC-1 (DVD, broadcast, early Blu-ray) are always checked since
+/// hard telecine is usually flagged progressive; other codecs only when ffprobe
+/// doesn't report them as progressive.
+pub fn worth_checking(stream: &FFProbeStream) -> bool {
+    if matches!(stream.codec_name.as_deref(), Some("mpeg2video" | "vc1" | "mpeg1video")) {
+        return true;
+    }
+    stream.field_order.as_deref() != Some("progressive")
+}
+
+/// Whether a stream runs at 29.97 or 30 fps, the rate telecined film ends up at
+pub fn is_ntsc_rate(stream: &FFProbeStream) -> bool {
+    let Some((num, den)) = stream.r_frame_rate.as_deref().and_then(|r| r.split_once('/')) else {
+        return false;
+    };
+    match (num.parse::<f64>(), den.parse::<f64>()) {
+        (Ok(num), Ok(den)) if den > 0.0 => (num / den - 29.97).abs() < 0.1,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDET_STDERR: &str = "\
+[Parsed_idet_0 @ 0x5581] Repeated Fields: Neither:   249 Top:     1 Bottom:     0
+[Parsed_idet_0 @ 0x5581] Single frame detection: TFF:   180 BFF:     0 Progressive:    40 Undetermined:    30
+[Parsed_idet_0 @ 0x5581] Multi frame detection: TFF:   231 BFF:     0 Progressive:    12 Undetermined:     7";
+
+    #[test]
+    fn test_parse_multi_frame_counts() {
+        let counts = IdetCounts::parse(IDET_STDERR).unwrap();
+        assert_eq!(counts, IdetCounts {
+            tff: 231,
+            bff: 0,
+            progressive: 12,
+            undetermined: 7,
+            repeated_top: 1,
+            repeated_bottom: 0,
+            repeated_neither: 249,
+        });
+        assert_eq!(IdetCounts::parse("frame=  250 fps=0.0"), None);
+    }
+
+    #[test]
+    fn test_scan_type_from_counts() {
+        let interlaced = IdetCounts::parse(IDET_STDERR).unwrap();
+        assert_eq!(interlaced.scan_type(true), Some(ScanType::InterlacedTff));
+
+        // 3:2 pulldown: two combed frames and two repeated fields out of five
+        let telecined = IdetCounts {
+            bff: 200,
+            progressive: 300,
+            repeated_top: 100,
+            repeated_bottom: 100,
+            repeated_neither: 300,
+            ..Default::default()
+        };
+        assert_eq!(telecined.scan_type(true), Some(ScanType::Telecined));
+        // PAL has no telecine
+        assert_eq!(telecined.scan_type(false), Some(ScanType::Progressive));
+
+        let progressive = IdetCounts { tff: 3, bff: 1, progressive: 700, undetermined: 20, ..Default::default() };
+        assert_eq!(progressive.scan_type(true), Some(ScanType::Progressive));
+        assert_eq!(IdetCounts { tff: 10, ..Default::default() }.scan_type(true), None);
+    }
+
+    #[test]
+    fn test_mixed_motion_interlaced_is_not_telecined() {
+        // Interlaced video with still scenes: plenty of clean frames but no field cadence
+        let counts = IdetCounts::parse("\
+[Parsed_idet_0 @ 0x5581] Repeated Fields: Neither:   731 Top:     9 Bottom:    10
+[Parsed_idet_0 @ 0x5581] Single frame detection: TFF:   260 BFF:     4 Progressive:   380 Undetermined:   106
+[Parsed_idet_0 @ 0x5581] Multi frame detection: TFF:   301 BFF:     0 Progressive:   402 Undetermined:    47").unwrap();
+        assert_eq!(counts.scan_type(true), Some(ScanType::InterlacedTff));
+    }
+
+    #[test]
+    fn test_filters() {
+        assert!(ScanType::Progressive.filters().is_empty());
+        assert_eq!(ScanType::InterlacedBff.filters(), vec!["bwdif=mode=send_frame:parity=bff:deint=all"]);
+        assert_eq!(ScanType::Telecined.filters().last().map(String::as_str), Some("decimate"));
+    }
+}
@@ -3,6 +3,16 @@ use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io::Write;
+use crate::failure::{FailureKind, JobFailure};
+use crate::test_clip::ApprovalDecision;
+use crate::tracks::DroppedTrack;
+use crate::dolby_vision::DolbyVisionInfo;
+use crate::hdr10_plus::Hdr10PlusHandling;
+use crate::crop::CropRect;
+use crate::interlace::ScanType;
+use crate::scale::ScaleTarget;
+use crate::verify::QualityScore;
 
 /// Status of a transcoding job
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -13,6 +23,37 @@ pub enum JobStatus {
     Success,
     Failed,
     Skipped,
+    /// REMUX test clip encoded, waiting for the user's approval decision
+    #[serde(rename = "awaiting_approval")]
+    AwaitingApproval,
+}
+
+/// One run of a job by a worker, kept as history on the job
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobAttempt {
+    pub started_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Job status the attempt ended with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<JobStatus>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Why the attempt failed (only set for failures)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<JobFailure>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoder: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crf: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<u8>,
+    /// FFmpeg exit code, if FFmpeg ran to completion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Last lines of FFmpeg's stderr for failed encodes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stderr_tail: Option<String>,
 }
 
 /// Represents a transcoding job
@@ -80,6 +121,48 @@ pub struct Job {
     /// Whether test clip was approved by user
     #[serde(skip_serializing_if = "Option::is_none")]
     pub test_clip_approved: Option<bool>,
+    /// Path to the encoded test clip awaiting review (paired with test_clip_path)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_clip_encoded_path: Option<PathBuf>,
+    /// When the job was parked waiting for a test clip decision
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_requested_at: Option<DateTime<Utc>>,
+    /// Decisions received for this job's test clips, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub test_clip_decisions: Vec<ApprovalDecision>,
+    /// Paused by the user: a running encode is stopped (SIGSTOP), a pending job is held
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub paused: bool,
+    /// Every worker run of this job, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attempts: Vec<JobAttempt>,
+    /// A pending job that failed before is not started again until this time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_at: Option<DateTime<Utc>>,
+    /// Why the job failed or was rejected (see `reason` for the message)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failure: Option<JobFailure>,
+    /// Tracks left out of the output (track selection, container limits, extra video)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dropped_tracks: Vec<DroppedTrack>,
+    /// How a Dolby Vision source was handled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dolby_vision: Option<DolbyVisionInfo>,
+    /// How an HDR10+ source was handled (None = no HDR10+ found)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hdr10_plus: Option<Hdr10PlusHandling>,
+    /// Crop applied to remove letterbox/pillarbox bars (None = full picture)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crop: Option<CropRect>,
+    /// Scan type found by idet (None = not checked)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_type: Option<ScanType>,
+    /// Output size when downscaled by `max_resolution` (None = native)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<ScaleTarget>,
+    /// Perceptual quality of the encode vs. the source (None = not measured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality_score: Option<QualityScore>,
 }
 
 impl Job {
@@ -114,28 +197,201 @@ impl Job {
             encoder_used: None,
             test_clip_path: None,
             test_clip_approved: None,
+            test_clip_encoded_path: None,
+            approval_requested_at: None,
+            test_clip_decisions: Vec::new(),
+            paused: false,
+            attempts: Vec::new(),
+            retry_at: None,
+            failure: None,
+            dropped_tracks: Vec::new(),
+            dolby_vision: None,
+            hdr10_plus: None,
+            crop: None,
+            scan_type: None,
+            scale: None,
+            quality_score: None,
         }
     }
+
+    /// Record the start of a new attempt (earlier reasons stay in the attempt history)
+    pub fn begin_attempt(&mut self) {
+        self.retry_at = None;
+        self.reason = None;
+        self.failure = None;
+        self.attempts.push(JobAttempt {
+            started_at: Utc::now(),
+            finished_at: None,
+            outcome: None,
+            reason: None,
+            failure: None,
+            encoder: None,
+            crf: None,
+            preset: None,
+            exit_code: None,
+            stderr_tail: None,
+        });
+    }
+
+    /// The attempt that has started but not finished yet
+    pub fn current_attempt_mut(&mut self) -> Option<&mut JobAttempt> {
+        self.attempts.last_mut().filter(|a| a.finished_at.is_none())
+    }
+
+    /// Mark the job as failed, recording the failure on the job and the current attempt
+    pub fn fail(&mut self, failure: JobFailure) {
+        self.record_failure(JobStatus::Failed, failure);
+    }
+
+    /// Mark the job as failed after FFmpeg exited with an error, keeping the exit
+    /// code and the end of stderr on the current attempt
+    pub fn fail_ffmpeg_exit(&mut self, exit_code: Option<i32>, stderr: &str) {
+        if let Some(attempt) = self.current_attempt_mut() {
+            attempt.exit_code = exit_code;
+            attempt.stderr_tail = Some(stderr_tail(stderr));
+        }
+        self.fail(JobFailure::from_ffmpeg_exit(exit_code, stderr));
+    }
+
+    /// Mark the job as skipped because of a permanent rejection such as the size gate
+    pub fn reject(&mut self, failure: JobFailure) {
+        self.record_failure(JobStatus::Skipped, failure);
+    }
+
+    fn record_failure(&mut self, status: JobStatus, failure: JobFailure) {
+        self.status = status;
+        self.reason = Some(failure.to_string());
+        self.finished_at = Some(Utc::now());
+        if let Some(attempt) = self.current_attempt_mut() {
+            attempt.failure = Some(failure.clone());
+        }
+        self.failure = Some(failure);
+    }
+
+    /// Kind of the job's failure, if it failed or was rejected
+    pub fn failure_kind(&self) -> Option<FailureKind> {
+        self.failure.as_ref().map(JobFailure::kind)
+    }
+
+    /// Close the current attempt (no-op if none is in progress)
+    pub fn finish_attempt(&mut self, outcome: JobStatus, reason: Option<String>) {
+        if let Some(attempt) = self.current_attempt_mut() {
+            attempt.finished_at = Some(Utc::now());
+            attempt.outcome = Some(outcome);
+            attempt.reason = reason;
+        }
+    }
+
+    /// Number of attempts that ended in failure (cancellations don't count)
+    pub fn failed_attempts(&self) -> u32 {
+        self.attempts.iter()
+            .filter_map(|a| a.failure.as_ref())
+            .filter(|f| f.kind() != FailureKind::Cancelled)
+            .count() as u32
+    }
+
+    /// Whether a pending job may start now (not waiting for a retry backoff)
+    pub fn is_retry_due(&self, now: DateTime<Utc>) -> bool {
+        self.retry_at.is_none_or(|at| at <= now)
+    }
+}
+
+/// Last lines of a tool's stderr, kept on the attempt for diagnosis
+pub fn stderr_tail(stderr: &str) -> String {
+    const TAIL_LINES: usize = 20;
+    let lines: Vec<&str> = stderr.lines().filter(|l| !l.trim().is_empty()).collect();
+    lines[lines.len().saturating_sub(TAIL_LINES)..].join("\n")
 }
 
 /// Save a job to disk as a JSON file
+///
+/// The file is written atomically (temp file, fsync, rename) so a crash or a
+/// concurrent reader never sees a partially written job.
 pub fn save_job(job: &Job, dir: &Path) -> Result<()> {
     // Ensure directory exists
     fs::create_dir_all(dir)
         .with_context(|| format!("Failed to create job state directory: {}", dir.display()))?;
 
     let file_path = dir.join(format!("{}.json", job.id));
+    let tmp_path = dir.join(format!(".{}.json.tmp", job.id));
     let json = serde_json::to_string_pretty(job)
         .context("Failed to serialize job to JSON")?;
 
-    fs::write(&file_path, json)
-        .with_context(|| format!("Failed to write job file: {}", file_path.display()))?;
+    let mut file = fs::File::create(&tmp_path)
+        .with_context(|| format!("Failed to create job file: {}", tmp_path.display()))?;
+    file.write_all(json.as_bytes())
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("Failed to write job file: {}", tmp_path.display()))?;
+    drop(file);
+
+    fs::rename(&tmp_path, &file_path)
+        .with_context(|| format!("Failed to rename job file: {} -> {}", tmp_path.display(), file_path.display()))?;
+
+    // Persist the rename itself; not all platforms can open a directory, so this is best effort
+    if let Ok(dir_handle) = fs::File::open(dir) {
+        dir_handle.sync_all().ok();
+    }
 
     Ok(())
 }
 
+/// Directory corrupt job files are moved to (a subdirectory so job loading ignores it)
+pub fn corrupt_dir(dir: &Path) -> PathBuf {
+    dir.join("corrupt")
+}
+
+/// Load a single job file
+///
+/// A file that can't be parsed is reported as missing, so one damaged file
+/// doesn't stop the daemon. With `quarantine` it is also moved to `corrupt/`;
+/// only the daemon, which owns the directory, should ask for that.
+pub fn load_job_file(path: &Path, quarantine: bool) -> Result<Option<Job>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        // Deleted or replaced between listing the directory and reading it
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to read job file: {}", path.display()));
+        }
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(job) => Ok(Some(job)),
+        Err(e) if quarantine => {
+            log::warn!("Corrupt job file {}: {}", path.display(), e);
+            quarantine_job_file(path);
+            Ok(None)
+        }
+        Err(e) => {
+            log::debug!("Skipping unreadable job file {}: {}", path.display(), e);
+            Ok(None)
+        }
+    }
+}
+
+/// Move a corrupt job file out of the way, keeping it for inspection
+fn quarantine_job_file(path: &Path) {
+    let Some(dir) = path.parent() else {
+        return;
+    };
+    let quarantine_dir = corrupt_dir(dir);
+    if let Err(e) = fs::create_dir_all(&quarantine_dir) {
+        log::warn!("Failed to create {}: {}", quarantine_dir.display(), e);
+        return;
+    }
+
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("job");
+    let target = quarantine_dir.join(format!("{}.{}.json", stem, Utc::now().format("%Y%m%dT%H%M%S")));
+    match fs::rename(path, &target) {
+        Ok(()) => log::warn!("Moved corrupt job file to {}", target.display()),
+        Err(e) => log::warn!("Failed to move corrupt job file {} to {}: {}", path.display(), target.display(), e),
+    }
+}
+
 /// Load all jobs from the job state directory
-pub fn load_all_jobs(dir: &Path) -> Result<Vec<Job>> {
+/// Corrupt job files are skipped, and quarantined if asked (see `load_job_file`),
+/// instead of failing the load
+pub fn load_all_jobs(dir: &Path, quarantine: bool) -> Result<Vec<Job>> {
     if !dir.exists() {
         return Ok(Vec::new());
     }
@@ -148,18 +404,112 @@ pub fn load_all_jobs(dir: &Path) -> Result<Vec<Job>> {
         let entry = entry.context("Failed to read directory entry")?;
         let path = entry.path();
 
-        // Only process .json files
+        // Only process .json files (temp files from in-progress writes end in .tmp)
         if path.extension().and_then(|s| s.to_str()) == Some("json") {
-            let content = fs::read_to_string(&path)
-                .with_context(|| format!("Failed to read job file: {}", path.display()))?;
-
-            let job: Job = serde_json::from_str(&content)
-                .with_context(|| format!("Failed to parse job JSON: {}", path.display()))?;
-
-            jobs.push(job);
+            if let Some(job) = load_job_file(&path, quarantine)? {
+                jobs.push(job);
+            }
         }
     }
 
     Ok(jobs)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("av1d-job-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_save_job_leaves_no_temp_file() {
+        let dir = temp_dir();
+        let job = Job::new(PathBuf::from("/media/movie.mkv"));
+        save_job(&job, &dir).unwrap();
+        save_job(&job, &dir).unwrap();
+
+        let names: Vec<String> = fs::read_dir(&dir).unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, vec![format!("{}.json", job.id)]);
+        assert_eq!(load_all_jobs(&dir, false).unwrap()[0].id, job.id);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_corrupt_job_file_is_quarantined() {
+        let dir = temp_dir();
+        let good = Job::new(PathBuf::from("/media/good.mkv"));
+        save_job(&good, &dir).unwrap();
+        // Truncated write from an older version or a crash
+        fs::write(dir.join("truncated.json"), "{\"id\": \"trunc").unwrap();
+
+        // Readers like the TUI skip it and leave it alone
+        let jobs = load_all_jobs(&dir, false).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert!(dir.join("truncated.json").exists());
+        assert!(!corrupt_dir(&dir).exists());
+
+        let jobs = load_all_jobs(&dir, true).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, good.id);
+
+        assert!(!dir.join("truncated.json").exists());
+        let quarantined: Vec<_> = fs::read_dir(corrupt_dir(&dir)).unwrap().collect();
+        assert_eq!(quarantined.len(), 1);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_attempt_history() {
+        let mut job = Job::new(PathBuf::from("/media/movie.mkv"));
+        job.begin_attempt();
+        job.fail(JobFailure::Io { message: "disk full".to_string() });
+        assert_eq!(job.failure_kind(), Some(FailureKind::Io));
+        job.finish_attempt(job.status, job.reason.clone());
+        // Closing twice must not touch the finished attempt
+        job.finish_attempt(JobStatus::Pending, None);
+
+        job.retry_at = Some(Utc::now() + chrono::Duration::minutes(10));
+        assert!(!job.is_retry_due(Utc::now()));
+        job.begin_attempt();
+        assert!(job.retry_at.is_none());
+        assert!(job.failure.is_none());
+        job.finish_attempt(JobStatus::Success, None);
+
+        assert_eq!(job.attempts.len(), 2);
+        assert_eq!(job.failed_attempts(), 1);
+        assert_eq!(job.attempts[0].outcome, Some(JobStatus::Failed));
+        assert_eq!(job.attempts[0].reason.as_deref(), Some("I/O error: disk full"));
+
+        // Jobs written before attempts existed still load
+        let json = serde_json::to_string(&Job::new(PathBuf::from("/media/old.mkv"))).unwrap();
+        assert!(!json.contains("attempts"));
+        let old: Job = serde_json::from_str(&json).unwrap();
+        assert!(old.attempts.is_empty());
+    }
+
+    #[test]
+    fn test_disk_full_encode_is_retryable_io_attempt() {
+        let mut job = Job::new(PathBuf::from("/media/movie.mkv"));
+        job.begin_attempt();
+        let stderr = "\
+frame= 5120 fps= 24 q=-0.0 size=  812032kB time=00:03:33.33 bitrate=31190.1kbits/s speed=1.0x
+[matroska @ 0x55d1] Error writing packet: No space left on device
+Conversion failed!";
+        job.fail_ffmpeg_exit(Some(228), stderr);
+        job.finish_attempt(job.status, job.reason.clone());
+
+        let attempt = &job.attempts[0];
+        assert_eq!(attempt.failure.as_ref().map(JobFailure::kind), Some(FailureKind::Io));
+        assert_eq!(attempt.exit_code, Some(228));
+        assert_eq!(attempt.stderr_tail.as_deref(), Some(stderr));
+        assert!(crate::retry::RetryPolicy::default().retry_delay(FailureKind::Io, job.failed_attempts()).is_some());
+    }
+}
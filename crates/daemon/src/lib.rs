@@ -7,13 +7,41 @@ pub mod ffmpeg_native;
 pub mod sidecar;
 pub mod quality;
 pub mod test_clip;
+pub mod progress;
+pub mod control;
+pub mod schedule;
+pub mod load;
+pub mod store;
+pub mod retry;
+pub mod failure;
+pub mod container;
+pub mod tracks;
+pub mod audio;
+pub mod subtitles;
+pub mod dolby_vision;
+pub mod color;
+pub mod hdr10_plus;
+pub mod crop;
+pub mod interlace;
+pub mod scale;
+pub mod profile;
+pub mod verify;
 
 // Re-export commonly used types and functions
-pub use config::TranscodeConfig;
-pub use job::{Job, JobStatus};
+pub use config::{TranscodeConfig, ApprovalTimeoutPolicy};
+pub use job::{Job, JobAttempt, JobStatus};
 pub use ffprobe::{FFProbeData, FFProbeFormat, FFProbeStream, BitDepth};
 pub use classifier::WebSourceDecision;
 pub use ffmpeg_native::{FFmpegManager, AV1Encoder, FFmpegVersion, CommandBuilder, ValidationResult, FFmpegResult};
 pub use quality::{QualityCalculator, EncodingParams};
 pub use test_clip::{TestClipWorkflow, TestClipInfo, ApprovalDecision};
+pub use progress::EncodeProgress;
+pub use control::DaemonState;
+pub use schedule::{EncodingSchedule, TimeWindow};
+pub use load::LoadThrottle;
 
+pub use store::{JobStore, JobStoreBackend, JsonDirStore, SqliteStore};
+pub use retry::RetryPolicy;
+pub use failure::{FailureKind, JobFailure};
+pub use container::{Container, ContainerPolicy};
+pub use tracks::{TrackSelection, TrackPlan, DroppedTrack};
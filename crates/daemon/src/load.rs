@@ -0,0 +1,341 @@
+use std::collections::HashSet;
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Host load limits that make the daemon back off while the machine is busy
+///
+/// All thresholds are optional; with none set the daemon ignores host load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoadThrottle {
+    /// Defer new jobs while the 1-minute load average per core is above this
+    /// (includes the daemon's own encodes, like `make -l`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_load_per_core: Option<f64>,
+    /// Defer new jobs while available memory is below this many MB
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_available_memory_mb: Option<u64>,
+    /// Treat the host as busy (e.g. Plex/Jellyfin transcoding) while other processes use
+    /// more than this percentage of total CPU: new jobs are deferred and running FFmpeg
+    /// processes get `busy_nice` priority
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_foreign_cpu_percent: Option<f32>,
+    /// Niceness given to FFmpeg while the host is busy (default: 19)
+    #[serde(default = "default_busy_nice")]
+    pub busy_nice: i32,
+    /// Also move FFmpeg to the idle I/O class while the host is busy (Linux, default: true)
+    #[serde(default = "default_busy_ionice_idle")]
+    pub busy_ionice_idle: bool,
+    /// Seconds between load checks (default: 30)
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+fn default_busy_nice() -> i32 {
+    19
+}
+
+fn default_busy_ionice_idle() -> bool {
+    true
+}
+
+fn default_check_interval_secs() -> u64 {
+    30
+}
+
+impl Default for LoadThrottle {
+    fn default() -> Self {
+        Self {
+            max_load_per_core: None,
+            min_available_memory_mb: None,
+            max_foreign_cpu_percent: None,
+            busy_nice: default_busy_nice(),
+            busy_ionice_idle: default_busy_ionice_idle(),
+            check_interval_secs: default_check_interval_secs(),
+        }
+    }
+}
+
+/// One measurement of host load
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadSample {
+    /// 1-minute load average divided by the number of cores
+    pub load_per_core: f64,
+    /// Available memory in MB
+    pub available_memory_mb: u64,
+    /// CPU used by processes other than the daemon's FFmpeg, as a percentage of all cores
+    pub foreign_cpu_percent: f32,
+}
+
+/// What the daemon should do about the current host load
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadVerdict {
+    /// Why new jobs must wait (empty = they may start)
+    pub defer_reasons: Vec<String>,
+    /// Running FFmpeg processes should run at lowered priority
+    pub throttle_running: bool,
+}
+
+impl LoadThrottle {
+    /// Whether any threshold is configured
+    pub fn is_enabled(&self) -> bool {
+        self.max_load_per_core.is_some()
+            || self.min_available_memory_mb.is_some()
+            || self.max_foreign_cpu_percent.is_some()
+    }
+
+    /// Check that thresholds and priorities are usable
+    pub fn validate(&self) -> Result<()> {
+        if let Some(load) = self.max_load_per_core {
+            if load.is_nan() || load <= 0.0 {
+                return Err(anyhow!("max_load_per_core must be greater than 0 (got {})", load));
+            }
+        }
+        if let Some(percent) = self.max_foreign_cpu_percent {
+            if percent.is_nan() || percent <= 0.0 || percent > 100.0 {
+                return Err(anyhow!("max_foreign_cpu_percent must be between 0 and 100 (got {})", percent));
+            }
+        }
+        if !(-20..=19).contains(&self.busy_nice) {
+            return Err(anyhow!("busy_nice must be between -20 and 19 (got {})", self.busy_nice));
+        }
+        Ok(())
+    }
+
+    /// Compare a sample against the configured thresholds
+    pub fn evaluate(&self, sample: &LoadSample) -> LoadVerdict {
+        let mut verdict = LoadVerdict::default();
+
+        if let Some(max) = self.max_load_per_core {
+            if sample.load_per_core > max {
+                verdict.defer_reasons.push(format!(
+                    "load {:.2}/core above {:.2}", sample.load_per_core, max
+                ));
+            }
+        }
+        if let Some(min) = self.min_available_memory_mb {
+            if sample.available_memory_mb < min {
+                verdict.defer_reasons.push(format!(
+                    "{} MB available memory below {} MB", sample.available_memory_mb, min
+                ));
+            }
+        }
+        if let Some(max) = self.max_foreign_cpu_percent {
+            if sample.foreign_cpu_percent > max {
+                verdict.defer_reasons.push(format!(
+                    "other processes using {:.0}% CPU (limit {:.0}%)", sample.foreign_cpu_percent, max
+                ));
+                verdict.throttle_running = true;
+            }
+        }
+
+        verdict
+    }
+}
+
+/// Samples host load with sysinfo
+///
+/// Per-process CPU usage is measured between two samples, so the first sample
+/// reports no foreign CPU use.
+pub struct LoadMonitor {
+    system: System,
+    cores: usize,
+}
+
+impl LoadMonitor {
+    pub fn new() -> Self {
+        let cores = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Self {
+            system: System::new(),
+            cores,
+        }
+    }
+
+    /// Measure current load; `own_pids` (the daemon's FFmpeg processes) don't count as foreign
+    pub fn sample(&mut self, own_pids: &HashSet<u32>) -> LoadSample {
+        self.system.refresh_memory();
+        self.system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            ProcessRefreshKind::new().with_cpu(),
+        );
+
+        let daemon_pid = std::process::id();
+        let foreign_cpu: f32 = self.system.processes()
+            .values()
+            // Threads are listed alongside processes on Linux; their time is already in the process
+            .filter(|p| p.thread_kind().is_none())
+            .filter(|p| {
+                let pid = p.pid().as_u32();
+                pid != daemon_pid && !own_pids.contains(&pid)
+            })
+            .map(|p| p.cpu_usage())
+            .sum();
+
+        LoadSample {
+            load_per_core: System::load_average().one / self.cores as f64,
+            available_memory_mb: self.system.available_memory() / (1024 * 1024),
+            foreign_cpu_percent: (foreign_cpu / self.cores as f32).clamp(0.0, 100.0),
+        }
+    }
+}
+
+impl Default for LoadMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// CPU and I/O scheduling priority for a process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessPriority {
+    pub nice: i32,
+    /// Idle I/O class (only does I/O when no one else needs the disk)
+    pub io_idle: bool,
+}
+
+impl ProcessPriority {
+    /// Priority of the daemon itself (what FFmpeg children start with)
+    pub fn inherited() -> Self {
+        Self {
+            nice: current_nice(),
+            io_idle: false,
+        }
+    }
+}
+
+/// Niceness of the current process
+#[cfg(unix)]
+fn current_nice() -> i32 {
+    // SAFETY: getpriority has no memory-safety preconditions
+    unsafe { libc::getpriority(libc::PRIO_PROCESS as _, 0) }
+}
+
+#[cfg(not(unix))]
+fn current_nice() -> i32 {
+    0
+}
+
+/// Apply a priority to every thread of a process
+///
+/// Linux schedules threads individually, so each task of the process is changed.
+/// Raising priority back up may require CAP_SYS_NICE.
+#[cfg(unix)]
+pub fn set_process_priority(pid: u32, priority: ProcessPriority) -> Result<()> {
+    let tids = process_threads(pid);
+    let mut last_error = None;
+    let mut applied = 0;
+
+    for tid in tids {
+        // SAFETY: setpriority has no memory-safety preconditions
+        let result = unsafe { libc::setpriority(libc::PRIO_PROCESS as _, tid as libc::id_t, priority.nice) };
+        if result != 0 {
+            last_error = Some(std::io::Error::last_os_error());
+            continue;
+        }
+        #[cfg(target_os = "linux")]
+        if let Err(e) = set_io_priority(tid, priority.io_idle) {
+            last_error = Some(e);
+            continue;
+        }
+        applied += 1;
+    }
+
+    match last_error {
+        Some(e) if applied == 0 => Err(anyhow!("Failed to set priority of process {}: {}", pid, e)),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(unix))]
+pub fn set_process_priority(_pid: u32, _priority: ProcessPriority) -> Result<()> {
+    Ok(())
+}
+
+/// Thread IDs of a process (just the process itself where threads can't be listed)
+#[cfg(unix)]
+fn process_threads(pid: u32) -> Vec<u32> {
+    let task_dir = format!("/proc/{}/task", pid);
+    match std::fs::read_dir(task_dir) {
+        Ok(entries) => entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().to_str().and_then(|s| s.parse().ok()))
+            .collect(),
+        Err(_) => vec![pid],
+    }
+}
+
+/// Set the I/O class of a thread: idle, or back to the default derived from niceness
+#[cfg(target_os = "linux")]
+fn set_io_priority(tid: u32, idle: bool) -> std::io::Result<()> {
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+    const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+    const IOPRIO_CLASS_NONE: libc::c_int = 0;
+    const IOPRIO_CLASS_IDLE: libc::c_int = 3;
+
+    let class = if idle { IOPRIO_CLASS_IDLE } else { IOPRIO_CLASS_NONE };
+    // SAFETY: ioprio_set takes plain integers
+    let result = unsafe {
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid as libc::c_int, class << IOPRIO_CLASS_SHIFT)
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(load_per_core: f64, available_memory_mb: u64, foreign_cpu_percent: f32) -> LoadSample {
+        LoadSample { load_per_core, available_memory_mb, foreign_cpu_percent }
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let throttle = LoadThrottle::default();
+        assert!(!throttle.is_enabled());
+        assert_eq!(throttle.evaluate(&sample(50.0, 0, 100.0)), LoadVerdict::default());
+    }
+
+    #[test]
+    fn test_thresholds_defer_new_jobs() {
+        let throttle = LoadThrottle {
+            max_load_per_core: Some(0.8),
+            min_available_memory_mb: Some(4096),
+            ..Default::default()
+        };
+        assert!(throttle.evaluate(&sample(0.5, 8192, 90.0)).defer_reasons.is_empty());
+
+        let verdict = throttle.evaluate(&sample(1.2, 1024, 0.0));
+        assert_eq!(verdict.defer_reasons.len(), 2);
+        // Load and memory don't renice running encodes
+        assert!(!verdict.throttle_running);
+    }
+
+    #[test]
+    fn test_foreign_cpu_throttles_running_encodes() {
+        let throttle = LoadThrottle {
+            max_foreign_cpu_percent: Some(25.0),
+            ..Default::default()
+        };
+        assert!(!throttle.evaluate(&sample(4.0, 0, 10.0)).throttle_running);
+
+        let verdict = throttle.evaluate(&sample(0.1, 0, 40.0));
+        assert!(verdict.throttle_running);
+        assert_eq!(verdict.defer_reasons.len(), 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(LoadThrottle::default().validate().is_ok());
+        let bad_nice = LoadThrottle { busy_nice: 25, ..Default::default() };
+        assert!(bad_nice.validate().is_err());
+        let bad_percent = LoadThrottle { max_foreign_cpu_percent: Some(150.0), ..Default::default() };
+        assert!(bad_percent.validate().is_err());
+        let bad_load = LoadThrottle { max_load_per_core: Some(0.0), ..Default::default() };
+        assert!(bad_load.validate().is_err());
+    }
+}
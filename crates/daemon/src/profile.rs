@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::classifier::QualityTier;
+
+/// Name of the built-in profile (the tables the daemon always used)
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Encoder settings for outputs at or above a height
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProfileBand {
+    /// Lowest output height this band covers; each tier needs a band at 0 (default: 0)
+    #[serde(default)]
+    pub min_height: u32,
+    /// 0-63, lower = higher quality
+    pub crf: u8,
+    /// 0-13, lower = slower/better (mapped to cpu-used/speed for libaom/rav1e)
+    pub preset: u8,
+    /// Film grain synthesis level, 0-50 (libaom: denoise noise level)
+    #[serde(default)]
+    pub film_grain: Option<u8>,
+    /// SVT-AV1-PSY `tune`, only passed to the PSY fork (default: 3, like the built-in bands)
+    #[serde(default = "default_tune")]
+    pub tune: Option<u8>,
+    /// Extra `key=value:key=value` options appended to `-svtav1-params`
+    #[serde(default)]
+    pub svtav1_params: Option<String>,
+    /// Extra `key=value:key=value` options passed to libaom as `-aom-params`
+    #[serde(default)]
+    pub aom_params: Option<String>,
+}
+
+fn default_tune() -> Option<u8> {
+    Some(3)
+}
+
+impl ProfileBand {
+    fn new(min_height: u32, crf: u8, preset: u8, film_grain: Option<u8>) -> Self {
+        ProfileBand {
+            min_height,
+            crf,
+            preset,
+            film_grain,
+            tune: default_tune(),
+            svtav1_params: None,
+            aom_params: None,
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.crf > 63 {
+            return Err(anyhow!("crf must be 0-63 (got {})", self.crf));
+        }
+        if self.preset > 13 {
+            return Err(anyhow!("preset must be 0-13 (got {})", self.preset));
+        }
+        if let Some(grain) = self.film_grain.filter(|g| *g > 50) {
+            return Err(anyhow!("film_grain must be 0-50 (got {})", grain));
+        }
+        for (name, options) in [("svtav1_params", &self.svtav1_params), ("aom_params", &self.aom_params)] {
+            if let Some(options) = options {
+                validate_options(options).with_context(|| format!("{} \"{}\"", name, options))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check a `key=value:key=value` option string
+fn validate_options(options: &str) -> Result<()> {
+    for option in options.split(':') {
+        match option.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !value.is_empty() && !option.contains(char::is_whitespace) => {}
+            _ => return Err(anyhow!("\"{}\" is not a key=value option", option)),
+        }
+    }
+    Ok(())
+}
+
+/// Resolution bands for each quality tier
+///
+/// Tiers left out of a configured profile use the built-in bands.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncodingProfile {
+    #[serde(default = "default_remux_bands")]
+    pub remux: Vec<ProfileBand>,
+    #[serde(default = "default_web_dl_bands")]
+    pub web_dl: Vec<ProfileBand>,
+    #[serde(default = "default_low_quality_bands")]
+    pub low_quality: Vec<ProfileBand>,
+}
+
+/// REMUX: quality-first, film grain synthesis to keep the grain
+fn default_remux_bands() -> Vec<ProfileBand> {
+    vec![ProfileBand::new(0, 18, 3, Some(8)), ProfileBand::new(2160, 20, 3, Some(8))]
+}
+
+/// WEB-DL: conservative re-encoding of already encoded sources
+fn default_web_dl_bands() -> Vec<ProfileBand> {
+    vec![ProfileBand::new(0, 26, 5, None), ProfileBand::new(2160, 28, 5, None)]
+}
+
+/// LOW-QUALITY: size reduction is fine
+fn default_low_quality_bands() -> Vec<ProfileBand> {
+    vec![ProfileBand::new(0, 30, 6, None)]
+}
+
+impl Default for EncodingProfile {
+    fn default() -> Self {
+        Self {
+            remux: default_remux_bands(),
+            web_dl: default_web_dl_bands(),
+            low_quality: default_low_quality_bands(),
+        }
+    }
+}
+
+impl EncodingProfile {
+    /// Settings for a tier at an output height: the band with the highest
+    /// `min_height` that still covers it
+    pub fn band(&self, tier: &QualityTier, height: i32) -> ProfileBand {
+        let bands = match tier {
+            QualityTier::Remux => &self.remux,
+            QualityTier::WebDl => &self.web_dl,
+            QualityTier::LowQuality => &self.low_quality,
+        };
+        bands.iter()
+            .filter(|b| i64::from(b.min_height) <= i64::from(height))
+            .max_by_key(|b| b.min_height)
+            // validate() guarantees a band at 0; fall back to the lowest one regardless
+            .or_else(|| bands.iter().min_by_key(|b| b.min_height))
+            .cloned()
+            // A tier left without bands (profile not validated) uses the built-in ones
+            .unwrap_or_else(|| EncodingProfile::default().band(tier, height))
+    }
+
+    /// Check every band's values and that each tier covers every height
+    pub fn validate(&self) -> Result<()> {
+        for (name, bands) in [("remux", &self.remux), ("web_dl", &self.web_dl), ("low_quality", &self.low_quality)] {
+            if !bands.iter().any(|b| b.min_height == 0) {
+                return Err(anyhow!("{}: needs a band with min_height 0", name));
+            }
+            for band in bands {
+                if bands.iter().filter(|b| b.min_height == band.min_height).count() > 1 {
+                    return Err(anyhow!("{}: more than one band with min_height {}", name, band.min_height));
+                }
+                band.validate()
+                    .with_context(|| format!("{}: band at min_height {}", name, band.min_height))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check the configured profiles and that `active` names one of them (or the built-in one)
+pub fn validate_profiles(profiles: &HashMap<String, EncodingProfile>, active: &str) -> Result<()> {
+    if active != DEFAULT_PROFILE && !profiles.contains_key(active) {
+        return Err(anyhow!("encoding_profile \"{}\" is not defined in profiles", active));
+    }
+    for (name, profile) in profiles {
+        profile.validate().with_context(|| format!("profile \"{}\"", name))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_profile_matches_built_in_tables() {
+        let profile = EncodingProfile::default();
+        assert_eq!(profile.band(&QualityTier::Remux, 1080).crf, 18);
+        assert_eq!(profile.band(&QualityTier::Remux, 2160).crf, 20);
+        assert_eq!(profile.band(&QualityTier::Remux, 2160).film_grain, Some(8));
+        assert_eq!(profile.band(&QualityTier::WebDl, 720).preset, 5);
+        assert_eq!(profile.band(&QualityTier::LowQuality, 2160).crf, 30);
+        assert!(profile.validate().is_ok());
+    }
+
+    #[test]
+    fn test_profile_from_toml() {
+        let profile: EncodingProfile = toml::from_str(r#"
+            [[low_quality]]
+            crf = 32
+            preset = 8
+
+            [[low_quality]]
+            min_height = 1440
+            crf = 34
+            preset = 8
+            svtav1_params = "enable-qm=1:qm-min=0"
+        "#).unwrap();
+        assert_eq!(profile.band(&QualityTier::LowQuality, 1080).crf, 32);
+        let uhd = profile.band(&QualityTier::LowQuality, 2160);
+        assert_eq!((uhd.crf, uhd.svtav1_params.as_deref()), (34, Some("enable-qm=1:qm-min=0")));
+        // Same tune as the built-in bands unless given
+        assert_eq!(uhd.tune, Some(3));
+        // Tiers not given keep the built-in bands
+        assert_eq!(profile.remux, default_remux_bands());
+        assert!(profile.validate().is_ok());
+    }
+
+    #[test]
+    fn test_validation() {
+        let mut profile = EncodingProfile::default();
+        profile.web_dl[0].crf = 70;
+        assert!(profile.validate().is_err());
+
+        let mut profile = EncodingProfile::default();
+        profile.low_quality[0].min_height = 720;
+        assert!(profile.validate().is_err());
+
+        let mut profile = EncodingProfile::default();
+        profile.remux[1].aom_params = Some("enable-tpl-model".to_string());
+        assert!(profile.validate().is_err());
+
+        let profiles = HashMap::from([("archive".to_string(), EncodingProfile::default())]);
+        assert!(validate_profiles(&profiles, "archive").is_ok());
+        assert!(validate_profiles(&profiles, DEFAULT_PROFILE).is_ok());
+        assert!(validate_profiles(&profiles, "missing").is_err());
+    }
+
+    #[test]
+    fn test_tier_without_bands_uses_built_in_band() {
+        let profile = EncodingProfile { web_dl: Vec::new(), ..Default::default() };
+        assert!(profile.validate().is_err());
+        assert_eq!(profile.band(&QualityTier::WebDl, 2160), ProfileBand::new(2160, 28, 5, None));
+    }
+}
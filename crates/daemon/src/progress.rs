@@ -0,0 +1,247 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Live encode progress for a running job
+///
+/// Built from FFmpeg's `-progress` key=value output and persisted to
+/// `{job_state_dir}/progress/{job_id}.json` so the TUI can show exact
+/// percent-complete and ETA instead of guessing from temp file growth.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncodeProgress {
+    /// Frames encoded so far
+    pub frame: Option<u64>,
+    /// Current encoding speed in frames per second
+    pub fps: Option<f64>,
+    /// Current output bitrate as reported by FFmpeg (e.g. "2456.3kbits/s")
+    pub bitrate: Option<String>,
+    /// Bytes written to the output so far
+    pub total_size: Option<u64>,
+    /// Position in the source reached by the encoder, in seconds
+    pub out_time_secs: Option<f64>,
+    /// Encoding speed relative to realtime (e.g. 0.45 = 0.45x)
+    pub speed: Option<f64>,
+    /// Source duration in seconds, if known
+    pub duration_secs: Option<f64>,
+    /// Percent complete (0-100), if the duration is known
+    pub percent: Option<f64>,
+    /// Estimated seconds remaining, if duration and speed are known
+    pub eta_secs: Option<u64>,
+    /// FFmpeg reported `progress=end`
+    pub finished: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl EncodeProgress {
+    fn empty(duration_secs: Option<f64>) -> Self {
+        EncodeProgress {
+            frame: None,
+            fps: None,
+            bitrate: None,
+            total_size: None,
+            out_time_secs: None,
+            speed: None,
+            duration_secs,
+            percent: None,
+            eta_secs: None,
+            finished: false,
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Recompute percent and ETA from out_time, duration and speed
+    fn update_estimates(&mut self) {
+        let (Some(duration), Some(out_time)) = (self.duration_secs, self.out_time_secs) else {
+            return;
+        };
+        if duration <= 0.0 {
+            return;
+        }
+
+        self.percent = Some((out_time / duration * 100.0).clamp(0.0, 100.0));
+
+        // speed is realtime multiplier: remaining media time / speed = remaining wall time
+        if let Some(speed) = self.speed.filter(|s| *s > 0.0) {
+            let remaining = (duration - out_time).max(0.0);
+            self.eta_secs = Some((remaining / speed).round() as u64);
+        }
+    }
+}
+
+/// Incremental parser for FFmpeg `-progress` output
+///
+/// FFmpeg writes blocks of `key=value` lines, each terminated by
+/// `progress=continue` (or `progress=end` for the last block).
+pub struct ProgressParser {
+    current: EncodeProgress,
+}
+
+impl ProgressParser {
+    /// Create a parser; `duration_secs` enables percent and ETA calculation
+    pub fn new(duration_secs: Option<f64>) -> Self {
+        ProgressParser {
+            current: EncodeProgress::empty(duration_secs),
+        }
+    }
+
+    /// Feed one line of output; returns a snapshot when a progress block completes
+    pub fn feed_line(&mut self, line: &str) -> Option<EncodeProgress> {
+        let (key, value) = line.trim().split_once('=')?;
+        let value = value.trim();
+
+        match key.trim() {
+            "frame" => self.current.frame = value.parse().ok(),
+            "fps" => self.current.fps = value.parse().ok(),
+            "bitrate" => {
+                self.current.bitrate = (value != "N/A").then(|| value.to_string());
+            }
+            "total_size" => self.current.total_size = value.parse().ok(),
+            // out_time_ms is also in microseconds (long-standing FFmpeg quirk)
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(us) = value.parse::<i64>() {
+                    self.current.out_time_secs = Some(us.max(0) as f64 / 1_000_000.0);
+                }
+            }
+            "out_time" if self.current.out_time_secs.is_none() => {
+                self.current.out_time_secs = parse_timestamp(value);
+            }
+            "speed" => {
+                self.current.speed = value.trim_end_matches('x').trim().parse().ok();
+            }
+            "progress" => {
+                self.current.finished = value == "end";
+                self.current.updated_at = Utc::now();
+                self.current.update_estimates();
+                return Some(self.current.clone());
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Parse an FFmpeg timestamp ("HH:MM:SS.micro") into seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let parts: Vec<&str> = value.split(':').collect();
+    if parts.len() != 3 {
+        return None;
+    }
+    let hours: f64 = parts[0].parse().ok()?;
+    let minutes: f64 = parts[1].parse().ok()?;
+    let seconds: f64 = parts[2].parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Directory holding progress files (a subdirectory so job loading ignores it)
+pub fn progress_dir(job_state_dir: &Path) -> PathBuf {
+    job_state_dir.join("progress")
+}
+
+/// Path of the progress file for a job
+pub fn progress_path(job_state_dir: &Path, job_id: &str) -> PathBuf {
+    progress_dir(job_state_dir).join(format!("{}.json", job_id))
+}
+
+/// Write progress for a job atomically (readers never see a partial file)
+pub fn save_progress(job_state_dir: &Path, job_id: &str, progress: &EncodeProgress) -> Result<()> {
+    let dir = progress_dir(job_state_dir);
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create progress directory: {}", dir.display()))?;
+
+    let path = progress_path(job_state_dir, job_id);
+    let tmp_path = dir.join(format!(".{}.json.tmp", job_id));
+    let json = serde_json::to_string_pretty(progress)
+        .context("Failed to serialize progress")?;
+    fs::write(&tmp_path, json)
+        .with_context(|| format!("Failed to write progress file: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("Failed to rename progress file: {}", path.display()))?;
+    Ok(())
+}
+
+/// Load progress for a job, if the daemon has reported any
+pub fn load_progress(job_state_dir: &Path, job_id: &str) -> Option<EncodeProgress> {
+    let content = fs::read_to_string(progress_path(job_state_dir, job_id)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Remove the progress file for a job (ignores a missing file)
+pub fn remove_progress(job_state_dir: &Path, job_id: &str) {
+    let path = progress_path(job_state_dir, job_id);
+    if path.exists() {
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!("Failed to remove progress file {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_block(parser: &mut ProgressParser, block: &str) -> Option<EncodeProgress> {
+        let mut last = None;
+        for line in block.lines() {
+            if let Some(p) = parser.feed_line(line) {
+                last = Some(p);
+            }
+        }
+        last
+    }
+
+    #[test]
+    fn test_parse_progress_block() {
+        let mut parser = ProgressParser::new(Some(1000.0));
+        let block = "frame=2400\n\
+                     fps=23.5\n\
+                     stream_0_0_q=28.0\n\
+                     bitrate=2456.3kbits/s\n\
+                     total_size=30720000\n\
+                     out_time_us=100000000\n\
+                     out_time_ms=100000000\n\
+                     out_time=00:01:40.000000\n\
+                     dup_frames=0\n\
+                     drop_frames=0\n\
+                     speed=0.5x\n\
+                     progress=continue";
+
+        let progress = feed_block(&mut parser, block).expect("block should produce a snapshot");
+        assert_eq!(progress.frame, Some(2400));
+        assert_eq!(progress.fps, Some(23.5));
+        assert_eq!(progress.bitrate.as_deref(), Some("2456.3kbits/s"));
+        assert_eq!(progress.total_size, Some(30720000));
+        assert_eq!(progress.out_time_secs, Some(100.0));
+        assert_eq!(progress.speed, Some(0.5));
+        assert_eq!(progress.percent, Some(10.0));
+        // 900s of media left at 0.5x realtime
+        assert_eq!(progress.eta_secs, Some(1800));
+        assert!(!progress.finished);
+    }
+
+    #[test]
+    fn test_no_snapshot_until_block_complete() {
+        let mut parser = ProgressParser::new(None);
+        assert!(parser.feed_line("frame=10").is_none());
+        assert!(parser.feed_line("not a progress line").is_none());
+        let progress = parser.feed_line("progress=end").unwrap();
+        assert_eq!(progress.frame, Some(10));
+        assert!(progress.finished);
+        // No duration: no percent or ETA
+        assert_eq!(progress.percent, None);
+        assert_eq!(progress.eta_secs, None);
+    }
+
+    #[test]
+    fn test_unavailable_values() {
+        let mut parser = ProgressParser::new(Some(60.0));
+        let block = "bitrate=N/A\nout_time=00:00:30.000000\nspeed=N/A\nprogress=continue";
+        let progress = feed_block(&mut parser, block).unwrap();
+        assert_eq!(progress.bitrate, None);
+        assert_eq!(progress.speed, None);
+        assert_eq!(progress.out_time_secs, Some(30.0));
+        assert_eq!(progress.percent, Some(50.0));
+        assert_eq!(progress.eta_secs, None);
+    }
+}
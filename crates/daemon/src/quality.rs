@@ -1,6 +1,14 @@
 use crate::classifier::{QualityTier, SourceClassification};
 use crate::ffmpeg_native::AV1Encoder;
 use crate::ffprobe::{FFProbeData, BitDepth};
+use crate::tracks::TrackPlan;
+use crate::audio::{AudioOutput, AudioPolicy};
+use crate::subtitles::SubtitlePlan;
+use crate::color::ColorMetadata;
+use crate::crop::CropRect;
+use crate::interlace::ScanType;
+use crate::scale::{ResolutionPolicy, ScaleTarget};
+use crate::profile::EncodingProfile;
 
 /// Encoding parameters for software AV1 encoding
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -11,15 +19,43 @@ pub struct EncodingParams {
     pub film_grain: Option<u8>,
     pub bit_depth: BitDepth,
     pub pixel_format: String,
+    /// Encoder thread budget for this job (None = let the encoder decide)
+    pub threads: Option<u32>,
+    /// Audio/subtitle tracks to map (None = all of them)
+    pub tracks: Option<TrackPlan>,
+    /// Every output audio stream in order (empty = copy the mapped audio tracks as-is)
+    pub audio: Vec<AudioOutput>,
+    /// Subtitle handling for the output container (None = map per `tracks` and copy)
+    pub subtitles: Option<SubtitlePlan>,
+    /// Keep the encoder from writing Dolby Vision metadata (base layer only)
+    pub strip_dolby_vision: bool,
+    /// Source colour description and HDR10 metadata to signal in the output
+    pub color: Option<ColorMetadata>,
+    /// Crop applied ahead of the format conversion (None = keep the full picture)
+    pub crop: Option<CropRect>,
+    /// Detected scan type; interlaced and telecined sources get deinterlace filters
+    pub scan_type: Option<ScanType>,
+    /// Downscaled output size (None = native resolution)
+    pub scale: Option<ScaleTarget>,
+    /// Extra `key=value:...` options for the encoder from the profile
+    /// (`-svtav1-params` for SVT-AV1, `-aom-params` for libaom)
+    pub extra_encoder_params: Option<String>,
 }
 
 /// Quality calculator for determining CRF, preset, and encoding parameters
-pub struct QualityCalculator;
+pub struct QualityCalculator {
+    profile: EncodingProfile,
+}
 
 impl QualityCalculator {
-    /// Create a new quality calculator
+    /// Create a new quality calculator using the built-in encoding profile
     pub fn new() -> Self {
-        QualityCalculator
+        Self::with_profile(EncodingProfile::default())
+    }
+
+    /// Create a quality calculator that takes its settings from a profile
+    pub fn with_profile(profile: EncodingProfile) -> Self {
+        QualityCalculator { profile }
     }
 
     /// Calculate encoding parameters for a source with quality-first decision logging
@@ -46,7 +82,7 @@ impl QualityCalculator {
     ) -> EncodingParams {
         // Get video stream for analysis
         let video_stream = meta.streams.iter()
-            .find(|s| s.codec_type.as_deref() == Some("video"));
+            .find(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture());
 
         // Detect bit depth
         let bit_depth = video_stream
@@ -65,81 +101,73 @@ impl QualityCalculator {
             .and_then(|s| s.height)
             .unwrap_or(1080);
 
-        // Calculate CRF based on tier and resolution
-        let crf = self.calculate_crf(&classification.tier, height);
-
-        // Calculate preset based on tier
-        let preset = self.calculate_preset(&classification.tier);
-
-        // Determine film-grain parameter (REMUX only)
-        let film_grain = if matches!(classification.tier, QualityTier::Remux) {
-            Some(8)
-        } else {
-            None
+        let mut params = EncodingParams {
+            crf: 0,
+            preset: 0,
+            tune: None,
+            film_grain: None,
+            bit_depth,
+            pixel_format,
+            threads: None,
+            tracks: None,
+            audio: Vec::new(),
+            subtitles: None,
+            strip_dolby_vision: false,
+            color: video_stream.and_then(ColorMetadata::from_stream),
+            crop: None,
+            scan_type: None,
+            scale: None,
+            extra_encoder_params: None,
         };
+        // CRF, preset, film grain and encoder options from the profile band for this tier and resolution
+        self.apply_band(&mut params, &classification.tier, encoder, height);
+        params
+    }
 
-        // Determine tune parameter (SVT-AV1-PSY only)
-        let tune = if matches!(encoder, AV1Encoder::SvtAv1Psy) {
-            Some(3)
-        } else {
-            None
+    /// Take CRF, preset, film grain, tune and extra encoder options from the
+    /// profile band for a tier and output height
+    fn apply_band(&self, params: &mut EncodingParams, tier: &QualityTier, encoder: &AV1Encoder, height: i32) {
+        let band = self.profile.band(tier, height);
+        params.crf = band.crf;
+        params.preset = band.preset;
+        params.film_grain = band.film_grain;
+        // tune only exists in SVT-AV1-PSY
+        params.tune = band.tune.filter(|_| matches!(encoder, AV1Encoder::SvtAv1Psy));
+        params.extra_encoder_params = match encoder {
+            AV1Encoder::SvtAv1Psy | AV1Encoder::SvtAv1 => band.svtav1_params.clone(),
+            AV1Encoder::LibAom => band.aom_params.clone(),
+            AV1Encoder::LibRav1e => None,
         };
-
-        EncodingParams {
-            crf,
-            preset,
-            tune,
-            film_grain,
-            bit_depth,
-            pixel_format,
-        }
     }
 
-    /// Calculate CRF value based on quality tier and resolution
-    fn calculate_crf(&self, tier: &QualityTier, height: i32) -> u8 {
-        match tier {
-            QualityTier::Remux => {
-                // REMUX: Quality-first, preserve everything
-                if height >= 2160 {
-                    // 4K: CRF 20
-                    20
-                } else {
-                    // 1080p and below: CRF 18
-                    18
-                }
-            }
-            QualityTier::WebDl => {
-                // WEB-DL: Conservative re-encoding
-                if height >= 2160 {
-                    // 4K: CRF 28
-                    28
-                } else {
-                    // 1080p and below: CRF 26
-                    26
-                }
-            }
-            QualityTier::LowQuality => {
-                // LOW-QUALITY: Size reduction OK
-                30
-            }
-        }
+    /// Plan the output audio streams for a source using its tier's audio rule
+    pub fn audio_plan(
+        &self,
+        tier: &QualityTier,
+        policy: &AudioPolicy,
+        meta: &FFProbeData,
+        kept_audio: &[i32],
+    ) -> Vec<AudioOutput> {
+        policy.rule(tier).plan(&meta.streams, kept_audio)
     }
 
-    /// Calculate preset value based on quality tier
-    fn calculate_preset(&self, tier: &QualityTier) -> u8 {
-        match tier {
-            QualityTier::Remux => {
-                // REMUX: Slower preset for maximum quality (preset 3)
-                3
-            }
-            QualityTier::WebDl => {
-                // WEB-DL: Balanced preset (preset 5)
-                5
-            }
-            QualityTier::LowQuality => {
-                // LOW-QUALITY: Faster preset (preset 6)
-                6
-            }
+    /// Cap the output resolution at the tier's limit
+    ///
+    /// `width`x`height` is the picture going into the scaler (after any crop).
+    /// When it gets downscaled the profile band (CRF, preset, ...) is
+    /// re-selected for the output height.
+    pub fn apply_max_resolution(
+        &self,
+        params: &mut EncodingParams,
+        tier: &QualityTier,
+        encoder: &AV1Encoder,
+        policy: &ResolutionPolicy,
+        width: i32,
+        height: i32,
+    ) {
+        params.scale = policy.target(tier, width, height);
+        if let Some(scale) = params.scale {
+            self.apply_band(params, tier, encoder, scale.height);
         }
     }
 
@@ -153,7 +181,7 @@ impl QualityCalculator {
         use log::info;
 
         let video_stream = meta.streams.iter()
-            .find(|s| s.codec_type.as_deref() == Some("video"));
+            .find(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture());
         
         let resolution = video_stream
             .and_then(|s| s.width.zip(s.height))
@@ -318,6 +346,11 @@ mod tests {
                 color_transfer: None,
                 color_primaries: None,
                 color_space: None,
+                field_order: None,
+                channels: None,
+                channel_layout: None,
+                profile: None,
+                side_data_list: None,
             }],
             format: FFProbeFormat {
                 format_name: "matroska,webm".to_string(),
@@ -325,10 +358,25 @@ mod tests {
                 tags: None,
                 muxing_app: None,
                 writing_library: None,
+                duration: None,
             },
         }
     }
 
+    #[test]
+    fn test_downscaled_output_uses_output_height_crf() {
+        let calculator = QualityCalculator::new();
+        let classification = create_test_classification(QualityTier::WebDl);
+        let meta = create_test_metadata(3840, 2160, BitDepth::Bit10);
+        let mut params = calculator.calculate_params_internal(&classification, &meta, &AV1Encoder::SvtAv1);
+        assert_eq!(params.crf, 28);
+
+        let policy = ResolutionPolicy { web_dl: Some(1080), ..Default::default() };
+        calculator.apply_max_resolution(&mut params, &QualityTier::WebDl, &AV1Encoder::SvtAv1, &policy, 3840, 2160);
+        assert_eq!(params.scale, Some(ScaleTarget { width: 1920, height: 1080 }));
+        assert_eq!(params.crf, 26);
+    }
+
     // Helper to create test classification
     fn create_test_classification(tier: QualityTier) -> SourceClassification {
         SourceClassification {
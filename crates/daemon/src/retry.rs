@@ -0,0 +1,136 @@
+use anyhow::{anyhow, Result};
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use crate::failure::FailureKind;
+
+/// When failed jobs are automatically put back in the queue
+///
+/// Only failures of the kinds in `retry_on` are retried, with exponential
+/// backoff between attempts. Size gate rejections and cancellations are never retried.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts per job including the first one (1 = never retry, default: 3)
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Failure kinds that are retried (default: ["io", "replace_failed"])
+    #[serde(default = "default_retry_on")]
+    pub retry_on: Vec<FailureKind>,
+    /// Delay before the first retry in seconds (default: 600)
+    #[serde(default = "default_initial_backoff_secs")]
+    pub initial_backoff_secs: u64,
+    /// Factor applied to the delay after each further failure (default: 2.0)
+    #[serde(default = "default_backoff_multiplier")]
+    pub backoff_multiplier: f64,
+    /// Upper bound for the delay in seconds (default: 21600 = 6 hours)
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_retry_on() -> Vec<FailureKind> {
+    vec![FailureKind::Io, FailureKind::ReplaceFailed]
+}
+
+fn default_initial_backoff_secs() -> u64 {
+    600 // 10 minutes
+}
+
+fn default_backoff_multiplier() -> f64 {
+    2.0
+}
+
+fn default_max_backoff_secs() -> u64 {
+    21600 // 6 hours
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_max_attempts(),
+            retry_on: default_retry_on(),
+            initial_backoff_secs: default_initial_backoff_secs(),
+            backoff_multiplier: default_backoff_multiplier(),
+            max_backoff_secs: default_max_backoff_secs(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Check that the backoff settings are usable
+    pub fn validate(&self) -> Result<()> {
+        if let Some(kind) = self.retry_on.iter().find(|k| k.is_permanent()) {
+            return Err(anyhow!("retry_on must not contain \"{}\" (it is a permanent decision)", kind.label()));
+        }
+        if self.backoff_multiplier.is_nan() || self.backoff_multiplier < 1.0 {
+            return Err(anyhow!("backoff_multiplier must be at least 1.0 (got {})", self.backoff_multiplier));
+        }
+        if self.max_backoff_secs < self.initial_backoff_secs {
+            return Err(anyhow!("max_backoff_secs ({}) must not be less than initial_backoff_secs ({})",
+                               self.max_backoff_secs, self.initial_backoff_secs));
+        }
+        Ok(())
+    }
+
+    /// Delay before retrying a job whose latest attempt failed with `kind`,
+    /// or None if the job should stay failed
+    /// `failed_attempts` includes the attempt that just failed
+    pub fn retry_delay(&self, kind: FailureKind, failed_attempts: u32) -> Option<Duration> {
+        if failed_attempts == 0 || failed_attempts >= self.max_attempts || !self.retry_on.contains(&kind) {
+            return None;
+        }
+
+        let exponent = (failed_attempts - 1).min(32) as i32;
+        let secs = (self.initial_backoff_secs as f64 * self.backoff_multiplier.powi(exponent))
+            .min(self.max_backoff_secs as f64);
+        Some(Duration::seconds(secs as i64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_grows_and_is_capped() {
+        let policy = RetryPolicy {
+            max_attempts: 6,
+            retry_on: vec![FailureKind::Io],
+            initial_backoff_secs: 60,
+            backoff_multiplier: 3.0,
+            max_backoff_secs: 600,
+        };
+        assert_eq!(policy.retry_delay(FailureKind::Io, 1), Some(Duration::seconds(60)));
+        assert_eq!(policy.retry_delay(FailureKind::Io, 2), Some(Duration::seconds(180)));
+        assert_eq!(policy.retry_delay(FailureKind::Io, 3), Some(Duration::seconds(540)));
+        assert_eq!(policy.retry_delay(FailureKind::Io, 4), Some(Duration::seconds(600)));
+        // Sixth attempt would exceed max_attempts
+        assert_eq!(policy.retry_delay(FailureKind::Io, 6), None);
+    }
+
+    #[test]
+    fn test_only_configured_kinds_are_retried() {
+        let policy = RetryPolicy::default();
+        assert!(policy.retry_delay(FailureKind::Io, 1).is_some());
+        assert!(policy.retry_delay(FailureKind::EncoderCrashed, 1).is_none());
+        assert!(policy.retry_delay(FailureKind::SizeGate, 1).is_none());
+
+        let never = RetryPolicy { max_attempts: 1, ..Default::default() };
+        assert!(never.retry_delay(FailureKind::Io, 1).is_none());
+    }
+
+    #[test]
+    fn test_policy_from_json() {
+        let policy: RetryPolicy = serde_json::from_str(r#"{"retry_on": ["io", "encoder_crashed"], "max_attempts": 5}"#).unwrap();
+        assert_eq!(policy.retry_on, vec![FailureKind::Io, FailureKind::EncoderCrashed]);
+        assert_eq!(policy.initial_backoff_secs, 600);
+        assert!(policy.validate().is_ok());
+
+        let bad = RetryPolicy { backoff_multiplier: 0.5, ..Default::default() };
+        assert!(bad.validate().is_err());
+        let permanent = RetryPolicy { retry_on: vec![FailureKind::SizeGate], ..Default::default() };
+        assert!(permanent.validate().is_err());
+    }
+}
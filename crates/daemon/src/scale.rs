@@ -0,0 +1,115 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::classifier::QualityTier;
+
+/// Output size after downscaling
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScaleTarget {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl ScaleTarget {
+    /// FFmpeg filter for this size (`scale=w:h:flags=lanczos`)
+    pub fn filter(&self) -> String {
+        format!("scale={}:{}:flags=lanczos", self.width, self.height)
+    }
+}
+
+impl std::fmt::Display for ScaleTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+/// Per-tier output resolution cap (default: keep every tier at native resolution)
+///
+/// Each limit is a height like 1080 and stands for the 16:9 box around it
+/// (1920x1080), so scope and 4:3 sources of that class aren't scaled further.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolutionPolicy {
+    #[serde(default)]
+    pub remux: Option<u32>,
+    #[serde(default)]
+    pub web_dl: Option<u32>,
+    #[serde(default)]
+    pub low_quality: Option<u32>,
+}
+
+/// Smallest height accepted as a limit
+const MIN_MAX_HEIGHT: u32 = 144;
+
+impl ResolutionPolicy {
+    /// Height limit for a quality tier (None = native)
+    pub fn max_height(&self, tier: &QualityTier) -> Option<u32> {
+        match tier {
+            QualityTier::Remux => self.remux,
+            QualityTier::WebDl => self.web_dl,
+            QualityTier::LowQuality => self.low_quality,
+        }
+    }
+
+    /// Check that every limit is usable
+    pub fn validate(&self) -> Result<()> {
+        for (name, limit) in [("remux", self.remux), ("web_dl", self.web_dl), ("low_quality", self.low_quality)] {
+            if let Some(height) = limit.filter(|h| *h < MIN_MAX_HEIGHT) {
+                return Err(anyhow!("{}: max height must be at least {} (got {})", name, MIN_MAX_HEIGHT, height));
+            }
+        }
+        Ok(())
+    }
+
+    /// Output size for a `width`x`height` picture in a tier (None = no scaling needed)
+    ///
+    /// Keeps the aspect ratio and rounds to even sizes for 4:2:0.
+    pub fn target(&self, tier: &QualityTier, width: i32, height: i32) -> Option<ScaleTarget> {
+        let max_height = self.max_height(tier)? as f64;
+        let max_width = (max_height * 16.0 / 9.0).round();
+        if width <= 0 || height <= 0 || (width as f64 <= max_width && height as f64 <= max_height) {
+            return None;
+        }
+
+        let factor = (max_width / width as f64).min(max_height / height as f64);
+        let even = |v: f64| ((v / 2.0).round() as i32 * 2).max(2);
+        Some(ScaleTarget {
+            width: even(width as f64 * factor),
+            height: even(height as f64 * factor),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cap_low_quality(height: u32) -> ResolutionPolicy {
+        ResolutionPolicy { low_quality: Some(height), ..Default::default() }
+    }
+
+    #[test]
+    fn test_native_by_default() {
+        assert_eq!(ResolutionPolicy::default().target(&QualityTier::LowQuality, 3840, 2160), None);
+        // Other tiers keep their resolution
+        assert_eq!(cap_low_quality(1080).target(&QualityTier::Remux, 3840, 2160), None);
+    }
+
+    #[test]
+    fn test_downscales_into_16_9_box() {
+        let policy = cap_low_quality(1080);
+        assert_eq!(policy.target(&QualityTier::LowQuality, 3840, 2160), Some(ScaleTarget { width: 1920, height: 1080 }));
+        // Scope is limited by the width
+        assert_eq!(policy.target(&QualityTier::LowQuality, 3840, 1600), Some(ScaleTarget { width: 1920, height: 800 }));
+        assert_eq!(policy.target(&QualityTier::LowQuality, 1920, 800), None);
+        assert_eq!(cap_low_quality(720).target(&QualityTier::LowQuality, 1440, 1080).unwrap().filter(),
+                   "scale=960:720:flags=lanczos");
+    }
+
+    #[test]
+    fn test_policy_from_json() {
+        let policy: ResolutionPolicy = serde_json::from_str(r#"{"low_quality": 1080}"#).unwrap();
+        assert_eq!(policy.max_height(&QualityTier::LowQuality), Some(1080));
+        assert_eq!(policy.max_height(&QualityTier::WebDl), None);
+        assert!(policy.validate().is_ok());
+        assert!(cap_low_quality(100).validate().is_err());
+    }
+}
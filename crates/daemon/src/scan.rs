@@ -1,4 +1,4 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
 use walkdir::WalkDir;
 use crate::config::TranscodeConfig;
@@ -8,6 +8,18 @@ use log::{debug, info, warn};
 /// Media file extensions to consider for transcoding
 const MEDIA_EXTENSIONS: &[&str] = &["mkv", "mp4", "m4v", "avi", "mov", "webm"];
 
+/// Whether a file is a backup of an original taken while a transcoded file
+/// replaces it (`Movie.orig.mkv`, see `container::backup_path`)
+fn is_original_backup(path: &Path) -> bool {
+    let is_media = path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| MEDIA_EXTENSIONS.contains(&e.to_lowercase().as_str()));
+    let stem_is_backup = path.file_stem()
+        .and_then(|s| s.to_str())
+        .is_some_and(|s| s.ends_with(".orig"));
+    is_media && stem_is_backup
+}
+
 /// Result of scanning a file
 #[derive(Debug, Clone)]
 pub enum ScanResult {
@@ -98,6 +110,15 @@ pub async fn scan_library(cfg: &TranscodeConfig) -> Result<Vec<ScanResult>> {
                 continue;
             }
 
+            // Skip backups of originals taken while a transcoded file replaces them
+            if is_original_backup(&path) {
+                results.push(ScanResult::Skipped(
+                    path.clone(),
+                    "backup of original during replacement".to_string(),
+                ));
+                continue;
+            }
+
             // Check skip markers
             if sidecar::has_skip_marker(&path)? {
                 results.push(ScanResult::Skipped(
@@ -204,3 +225,21 @@ pub async fn scan_library(cfg: &TranscodeConfig) -> Result<Vec<ScanResult>> {
     info!("=====================");
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container;
+
+    #[test]
+    fn test_only_exact_backup_names_are_skipped() {
+        let source = Path::new("/media/Movie.mkv");
+        assert!(is_original_backup(&container::backup_path(source)));
+        assert!(is_original_backup(Path::new("/media/Clip.orig.MP4")));
+
+        assert!(!is_original_backup(source));
+        assert!(!is_original_backup(Path::new("/media/Movie.orig.cut.mkv")));
+        assert!(!is_original_backup(Path::new("/media/Show.S01E01.orig.mkv.part")));
+        assert!(!is_original_backup(Path::new("/media/Movie.original.mkv")));
+    }
+}
@@ -0,0 +1,197 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{Datelike, Duration, Local, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Time-of-day windows in which the daemon may encode
+///
+/// With no windows configured the daemon encodes around the clock. Times are
+/// local to the host running the daemon.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EncodingSchedule {
+    /// Windows in which new jobs may start (empty = always)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub windows: Vec<TimeWindow>,
+    /// Pause (SIGSTOP) running encodes outside the windows instead of letting them finish
+    #[serde(default)]
+    pub pause_outside_windows: bool,
+}
+
+/// A daily time range, e.g. `{"days": ["sat", "sun"], "start": "00:00", "end": "24:00"}`
+///
+/// A window whose end is before its start runs past midnight (22:00-06:00);
+/// `days` are the days on which the window starts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeWindow {
+    /// Days the window applies to (empty = every day)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    /// Start time, "HH:MM"
+    pub start: String,
+    /// End time, "HH:MM" ("24:00" = end of day)
+    pub end: String,
+}
+
+impl EncodingSchedule {
+    /// Whether any windows are configured
+    pub fn is_restricted(&self) -> bool {
+        !self.windows.is_empty()
+    }
+
+    /// Whether jobs may run at the given local time
+    pub fn is_open_at(&self, now: NaiveDateTime) -> bool {
+        !self.is_restricted() || self.windows.iter().any(|w| w.contains(now))
+    }
+
+    /// Whether jobs may run right now
+    pub fn is_open_now(&self) -> bool {
+        self.is_open_at(Local::now().naive_local())
+    }
+
+    /// Check that every window has valid times
+    pub fn validate(&self) -> Result<()> {
+        for (idx, window) in self.windows.iter().enumerate() {
+            window.bounds()
+                .with_context(|| format!("Invalid schedule window #{}", idx + 1))?;
+        }
+        Ok(())
+    }
+}
+
+impl TimeWindow {
+    /// Start and end as offsets from midnight (end may be 24h)
+    fn bounds(&self) -> Result<(Duration, Duration)> {
+        let start = parse_clock_time(&self.start)?;
+        let end = parse_clock_time(&self.end)?;
+        if start == end {
+            return Err(anyhow!("start and end are both {} (use 00:00-24:00 for a full day)", self.start));
+        }
+        Ok((start, end))
+    }
+
+    fn applies_on(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    /// Whether the window contains the given local time
+    /// Invalid windows never contain anything (config validation reports them)
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let Ok((start, end)) = self.bounds() else {
+            return false;
+        };
+        let since_midnight = now.time().signed_duration_since(NaiveTime::MIN);
+
+        if start < end {
+            self.applies_on(now.weekday()) && since_midnight >= start && since_midnight < end
+        } else {
+            // Runs past midnight: the evening part belongs to today's window,
+            // the early-morning part to the window that started yesterday
+            (self.applies_on(now.weekday()) && since_midnight >= start)
+                || (self.applies_on(now.weekday().pred()) && since_midnight < end)
+        }
+    }
+}
+
+/// Parse "HH:MM" into an offset from midnight ("24:00" is allowed as end of day)
+fn parse_clock_time(value: &str) -> Result<Duration> {
+    let (hours, minutes) = value.trim().split_once(':')
+        .ok_or_else(|| anyhow!("invalid time '{}', expected HH:MM", value))?;
+    let hours: i64 = hours.parse()
+        .with_context(|| format!("invalid hour in '{}'", value))?;
+    let minutes: i64 = minutes.parse()
+        .with_context(|| format!("invalid minute in '{}'", value))?;
+
+    let valid = ((0..24).contains(&hours) && (0..60).contains(&minutes))
+        || (hours == 24 && minutes == 0);
+    if !valid {
+        return Err(anyhow!("time '{}' is out of range (00:00-24:00)", value));
+    }
+    Ok(Duration::hours(hours) + Duration::minutes(minutes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(date: (i32, u32, u32), hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap()
+            .and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn window(days: Vec<Weekday>, start: &str, end: &str) -> TimeWindow {
+        TimeWindow { days, start: start.to_string(), end: end.to_string() }
+    }
+
+    // 2024-06-07 is a Friday
+    const FRIDAY: (i32, u32, u32) = (2024, 6, 7);
+    const SATURDAY: (i32, u32, u32) = (2024, 6, 8);
+
+    #[test]
+    fn test_unrestricted_schedule_is_always_open() {
+        let schedule = EncodingSchedule::default();
+        assert!(!schedule.is_restricted());
+        assert!(schedule.is_open_at(at(FRIDAY, 12, 0)));
+    }
+
+    #[test]
+    fn test_overnight_window() {
+        let schedule = EncodingSchedule {
+            windows: vec![window(vec![], "22:00", "06:00")],
+            pause_outside_windows: false,
+        };
+        assert!(schedule.is_open_at(at(FRIDAY, 23, 30)));
+        assert!(schedule.is_open_at(at(SATURDAY, 5, 59)));
+        assert!(!schedule.is_open_at(at(SATURDAY, 6, 0)));
+        assert!(!schedule.is_open_at(at(FRIDAY, 18, 0)));
+        assert!(schedule.is_open_at(at(FRIDAY, 22, 0)));
+    }
+
+    #[test]
+    fn test_window_days_refer_to_start_day() {
+        // Friday night only: Saturday early morning is still inside, Saturday night is not
+        let schedule = EncodingSchedule {
+            windows: vec![window(vec![Weekday::Fri], "22:00", "06:00")],
+            pause_outside_windows: true,
+        };
+        assert!(schedule.is_open_at(at(FRIDAY, 23, 0)));
+        assert!(schedule.is_open_at(at(SATURDAY, 3, 0)));
+        assert!(!schedule.is_open_at(at(SATURDAY, 23, 0)));
+        assert!(!schedule.is_open_at(at(FRIDAY, 3, 0)));
+    }
+
+    #[test]
+    fn test_full_day_weekend_window() {
+        let schedule = EncodingSchedule {
+            windows: vec![
+                window(vec![Weekday::Sat, Weekday::Sun], "00:00", "24:00"),
+                window(vec![], "01:00", "05:00"),
+            ],
+            pause_outside_windows: false,
+        };
+        assert!(schedule.is_open_at(at(SATURDAY, 14, 0)));
+        assert!(schedule.is_open_at(at(SATURDAY, 23, 59)));
+        assert!(!schedule.is_open_at(at(FRIDAY, 14, 0)));
+        assert!(schedule.is_open_at(at(FRIDAY, 2, 0)));
+    }
+
+    #[test]
+    fn test_schedule_from_json() {
+        let json = r#"{"windows": [{"days": ["mon", "Tuesday"], "start": "20:00", "end": "07:30"}], "pause_outside_windows": true}"#;
+        let schedule: EncodingSchedule = serde_json::from_str(json).unwrap();
+        assert_eq!(schedule.windows[0].days, vec![Weekday::Mon, Weekday::Tue]);
+        assert!(schedule.pause_outside_windows);
+        assert!(schedule.validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_windows_rejected() {
+        for (start, end) in [("25:00", "06:00"), ("22:00", "6"), ("22:60", "06:00"), ("08:00", "08:00"), ("24:00", "24:30")] {
+            let schedule = EncodingSchedule {
+                windows: vec![window(vec![], start, end)],
+                pause_outside_windows: false,
+            };
+            assert!(schedule.validate().is_err(), "{}-{} should be rejected", start, end);
+            assert!(!schedule.is_open_at(at(FRIDAY, 12, 0)));
+        }
+    }
+}
@@ -106,7 +106,7 @@ pub fn write_conversion_report(
     
     // Find video stream
     let video_stream = report.source_meta.streams.iter()
-        .find(|s| s.codec_type.as_deref() == Some("video"));
+        .find(|s| s.codec_type.as_deref() == Some("video") && !s.is_attached_picture());
     
     if let Some(vs) = video_stream {
         content.push_str("Video Stream:\n");
@@ -244,10 +244,30 @@ pub fn write_conversion_report(
     if let Some(grain) = report.encoding_params.film_grain {
         content.push_str(&format!("Film Grain:       {} (grain synthesis enabled)\n", grain));
     }
+
+    if let Some(ref extra) = report.encoding_params.extra_encoder_params {
+        content.push_str(&format!("Encoder Options:  {} (from encoding profile)\n", extra));
+    }
     
     content.push_str("\nFilter Chain:\n");
-    content.push_str(&format!("  1. format={}  (pixel format conversion)\n", 
-        report.encoding_params.pixel_format));
+    let mut step = 1;
+    if let Some(scan_type) = report.encoding_params.scan_type {
+        for filter in scan_type.filters() {
+            content.push_str(&format!("  {}. {}  ({})\n", step, filter, scan_type));
+            step += 1;
+        }
+    }
+    if let Some(crop) = &report.encoding_params.crop {
+        content.push_str(&format!("  {}. {}  (letterbox crop to {}x{})\n",
+            step, crop.filter(), crop.width, crop.height));
+        step += 1;
+    }
+    if let Some(scale) = &report.encoding_params.scale {
+        content.push_str(&format!("  {}. {}  (downscale to {})\n", step, scale.filter(), scale));
+        step += 1;
+    }
+    content.push_str(&format!("  {}. format={}  (pixel format conversion)\n", 
+        step, report.encoding_params.pixel_format));
     
     content.push_str("\nStream Handling:\n");
     content.push_str("  • Video:     Transcoded to AV1 (QSV hardware acceleration)\n");
@@ -255,7 +275,14 @@ pub fn write_conversion_report(
     content.push_str("  • Subtitles: Copied (no re-encoding)\n");
     content.push_str("  • Chapters:  Preserved\n");
     content.push_str("  • Metadata:  Preserved\n");
-    content.push_str("  • Russian tracks: Removed (audio & subtitles)\n");
+    if report.job.dropped_tracks.is_empty() {
+        content.push_str("  • Dropped:   none (all audio & subtitle tracks kept)\n");
+    } else {
+        content.push_str(&format!("  • Dropped:   {} track(s)\n", report.job.dropped_tracks.len()));
+        for track in &report.job.dropped_tracks {
+            content.push_str(&format!("      - {}\n", track));
+        }
+    }
     content.push_str("\n");
     
     // File Size Comparison
@@ -284,6 +311,9 @@ pub fn write_conversion_report(
         
         content.push_str(&format!("Validation Status: {}\n", 
             if validation.is_valid { "✓ PASSED" } else { "✗ FAILED" }));
+        if let Some(score) = &report.job.quality_score {
+            content.push_str(&format!("Perceptual Quality: {}\n", score));
+        }
         content.push_str("\n");
         
         if !validation.issues.is_empty() {
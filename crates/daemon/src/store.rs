@@ -0,0 +1,359 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension, Params, params_from_iter};
+use serde::{Deserialize, Serialize};
+use crate::config::TranscodeConfig;
+use crate::job::{self, Job, JobStatus};
+
+/// Where job state is persisted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStoreBackend {
+    /// One JSON file per job in `job_state_dir`
+    #[default]
+    Json,
+    /// A single SQLite database with indexed lookups
+    Sqlite,
+}
+
+/// Persistent storage for jobs
+///
+/// Shared by the daemon's tasks and read by the TUI, so implementations must be
+/// safe to use from several threads and processes at once.
+pub trait JobStore: Send + Sync {
+    /// Insert or replace a job
+    fn save(&self, job: &Job) -> Result<()>;
+
+    /// Load a single job by ID
+    fn get(&self, id: &str) -> Result<Option<Job>>;
+
+    /// Load every job
+    fn load_all(&self) -> Result<Vec<Job>>;
+
+    /// Load all jobs whose status is one of `statuses`
+    fn find_by_status(&self, statuses: &[JobStatus]) -> Result<Vec<Job>>;
+
+    /// Load all jobs for a source file
+    fn find_by_source(&self, source_path: &Path) -> Result<Vec<Job>>;
+}
+
+/// The original backend: one pretty-printed JSON file per job
+///
+/// Every lookup reads the whole directory, which gets slow with many jobs.
+pub struct JsonDirStore {
+    dir: PathBuf,
+    /// Move unparseable job files to `corrupt/` instead of just skipping them
+    quarantine_corrupt: bool,
+}
+
+impl JsonDirStore {
+    /// Store that skips unparseable job files and never moves them
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), quarantine_corrupt: false }
+    }
+
+    /// Move unparseable job files to `corrupt/` (for the daemon, which owns the directory)
+    pub fn with_quarantine(mut self) -> Self {
+        self.quarantine_corrupt = true;
+        self
+    }
+}
+
+impl JobStore for JsonDirStore {
+    fn save(&self, job: &Job) -> Result<()> {
+        job::save_job(job, &self.dir)
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Job>> {
+        job::load_job_file(&self.dir.join(format!("{}.json", id)), self.quarantine_corrupt)
+    }
+
+    fn load_all(&self) -> Result<Vec<Job>> {
+        job::load_all_jobs(&self.dir, self.quarantine_corrupt)
+    }
+
+    fn find_by_status(&self, statuses: &[JobStatus]) -> Result<Vec<Job>> {
+        Ok(self.load_all()?.into_iter().filter(|j| statuses.contains(&j.status)).collect())
+    }
+
+    fn find_by_source(&self, source_path: &Path) -> Result<Vec<Job>> {
+        Ok(self.load_all()?.into_iter().filter(|j| j.source_path == source_path).collect())
+    }
+}
+
+/// SQLite backend: each job is stored as JSON with indexed status and source path columns
+pub struct SqliteStore {
+    // rusqlite connections are Send but not Sync
+    conn: Mutex<Connection>,
+}
+
+/// Key in the `meta` table recording that the JSON directory was imported
+const JSON_IMPORTED_KEY: &str = "json_imported_at";
+
+impl SqliteStore {
+    /// Open (or create) the database at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create job store directory: {}", parent.display()))?;
+        }
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open job database: {}", path.display()))?;
+        Self::init(conn)
+            .with_context(|| format!("Failed to initialize job database: {}", path.display()))
+    }
+
+    /// Open a private in-memory database (for tests)
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        // WAL lets the TUI read while the daemon writes; the timeout covers
+        // the short windows where another process holds the write lock
+        conn.busy_timeout(std::time::Duration::from_secs(5))?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS jobs (
+                 id TEXT PRIMARY KEY,
+                 status TEXT NOT NULL,
+                 source_path TEXT NOT NULL,
+                 data TEXT NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS jobs_status ON jobs (status);
+             CREATE INDEX IF NOT EXISTS jobs_source_path ON jobs (source_path);
+             CREATE TABLE IF NOT EXISTS meta (
+                 key TEXT PRIMARY KEY,
+                 value TEXT NOT NULL
+             );",
+        )?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, Connection> {
+        // A panic while holding the lock can't leave the connection half-updated
+        // (statements are atomic), so keep using it
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn query_jobs(&self, sql: &str, params: impl Params) -> Result<Vec<Job>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare_cached(sql)?;
+        let rows = stmt.query_map(params, |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut jobs = Vec::new();
+        for row in rows {
+            let (id, data) = row.context("Failed to read job row")?;
+            jobs.push(parse_job(&id, &data)?);
+        }
+        Ok(jobs)
+    }
+
+    /// One-shot import of a JSON job directory
+    ///
+    /// Runs once per database: later calls return `Ok(0)` without touching the
+    /// directory. Imported files are moved to `{dir}/migrated/` so they stay
+    /// around as a backup but are no longer picked up as jobs.
+    pub fn import_json_dir(&self, dir: &Path) -> Result<usize> {
+        let mut conn = self.conn();
+        let already_imported: Option<String> = conn
+            .query_row("SELECT value FROM meta WHERE key = ?1", [JSON_IMPORTED_KEY], |row| row.get(0))
+            .optional()?;
+        if already_imported.is_some() {
+            return Ok(0);
+        }
+
+        let jobs = job::load_all_jobs(dir, true)
+            .with_context(|| format!("Failed to load JSON jobs for migration from {}", dir.display()))?;
+
+        let tx = conn.transaction()?;
+        for job in &jobs {
+            upsert(&tx, job)?;
+        }
+        tx.execute(
+            "INSERT INTO meta (key, value) VALUES (?1, ?2)",
+            params![JSON_IMPORTED_KEY, chrono::Utc::now().to_rfc3339()],
+        )?;
+        tx.commit().context("Failed to commit job migration")?;
+
+        if !jobs.is_empty() {
+            let migrated_dir = dir.join("migrated");
+            fs::create_dir_all(&migrated_dir)
+                .with_context(|| format!("Failed to create directory: {}", migrated_dir.display()))?;
+            for job in &jobs {
+                let file_name = format!("{}.json", job.id);
+                let from = dir.join(&file_name);
+                if let Err(e) = fs::rename(&from, migrated_dir.join(&file_name)) {
+                    log::warn!("Imported job file {} but could not move it aside: {}", from.display(), e);
+                }
+            }
+        }
+
+        Ok(jobs.len())
+    }
+}
+
+impl JobStore for SqliteStore {
+    fn save(&self, job: &Job) -> Result<()> {
+        upsert(&self.conn(), job)
+            .with_context(|| format!("Failed to save job {}", job.id))
+    }
+
+    fn get(&self, id: &str) -> Result<Option<Job>> {
+        let data: Option<String> = self.conn()
+            .query_row("SELECT data FROM jobs WHERE id = ?1", [id], |row| row.get(0))
+            .optional()?;
+        data.map(|d| parse_job(id, &d)).transpose()
+    }
+
+    fn load_all(&self) -> Result<Vec<Job>> {
+        self.query_jobs("SELECT id, data FROM jobs", [])
+    }
+
+    fn find_by_status(&self, statuses: &[JobStatus]) -> Result<Vec<Job>> {
+        if statuses.is_empty() {
+            return Ok(Vec::new());
+        }
+        let placeholders = vec!["?"; statuses.len()].join(", ");
+        let sql = format!("SELECT id, data FROM jobs WHERE status IN ({})", placeholders);
+        self.query_jobs(&sql, params_from_iter(statuses.iter().map(|s| status_key(*s))))
+    }
+
+    fn find_by_source(&self, source_path: &Path) -> Result<Vec<Job>> {
+        self.query_jobs("SELECT id, data FROM jobs WHERE source_path = ?1", [source_path.to_string_lossy()])
+    }
+}
+
+fn upsert(conn: &Connection, job: &Job) -> Result<()> {
+    let data = serde_json::to_string(job)
+        .context("Failed to serialize job to JSON")?;
+    conn.execute(
+        "INSERT INTO jobs (id, status, source_path, data) VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT (id) DO UPDATE SET
+             status = excluded.status,
+             source_path = excluded.source_path,
+             data = excluded.data",
+        params![job.id, status_key(job.status), job.source_path.to_string_lossy(), data],
+    )?;
+    Ok(())
+}
+
+fn parse_job(id: &str, data: &str) -> Result<Job> {
+    serde_json::from_str(data)
+        .with_context(|| format!("Failed to parse stored job {}", id))
+}
+
+/// Status as stored in the index column (same spelling as in job JSON)
+fn status_key(status: JobStatus) -> String {
+    match serde_json::to_value(status) {
+        Ok(serde_json::Value::String(s)) => s,
+        _ => format!("{:?}", status).to_lowercase(),
+    }
+}
+
+/// Open the job store selected in the configuration
+///
+/// `quarantine_corrupt` moves unparseable JSON job files to `corrupt/`. Only the
+/// daemon sets it; other readers just skip them.
+pub fn open_job_store(cfg: &TranscodeConfig, quarantine_corrupt: bool) -> Result<Arc<dyn JobStore>> {
+    match cfg.job_store {
+        JobStoreBackend::Json if quarantine_corrupt => Ok(Arc::new(JsonDirStore::new(&cfg.job_state_dir).with_quarantine())),
+        JobStoreBackend::Json => Ok(Arc::new(JsonDirStore::new(&cfg.job_state_dir))),
+        JobStoreBackend::Sqlite => Ok(Arc::new(SqliteStore::open(&cfg.job_store_path())?)),
+    }
+}
+
+/// Move existing JSON job files into the SQLite store the first time it is used
+///
+/// Returns the number of jobs imported (0 for the JSON backend or once migrated).
+pub fn migrate_json_jobs(cfg: &TranscodeConfig) -> Result<usize> {
+    if cfg.job_store != JobStoreBackend::Sqlite {
+        return Ok(0);
+    }
+    SqliteStore::open(&cfg.job_store_path())?.import_json_dir(&cfg.job_state_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(path: &str, status: JobStatus) -> Job {
+        let mut job = Job::new(PathBuf::from(path));
+        job.status = status;
+        job
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("av1d-store-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_sqlite_save_and_lookup() {
+        let store = SqliteStore::open_in_memory().unwrap();
+        let mut a = job("/media/a.mkv", JobStatus::Pending);
+        let b = job("/media/b.mkv", JobStatus::Success);
+        store.save(&a).unwrap();
+        store.save(&b).unwrap();
+
+        assert_eq!(store.load_all().unwrap().len(), 2);
+        assert_eq!(store.get(&b.id).unwrap().unwrap().source_path, b.source_path);
+        assert!(store.get("missing").unwrap().is_none());
+
+        // Saving again updates the indexed columns
+        a.status = JobStatus::AwaitingApproval;
+        store.save(&a).unwrap();
+        assert_eq!(store.load_all().unwrap().len(), 2);
+        assert!(store.find_by_status(&[JobStatus::Pending]).unwrap().is_empty());
+        let awaiting = store.find_by_status(&[JobStatus::AwaitingApproval, JobStatus::Failed]).unwrap();
+        assert_eq!(awaiting.len(), 1);
+        assert_eq!(awaiting[0].id, a.id);
+
+        let by_source = store.find_by_source(Path::new("/media/b.mkv")).unwrap();
+        assert_eq!(by_source.len(), 1);
+        assert_eq!(by_source[0].id, b.id);
+    }
+
+    #[test]
+    fn test_json_dir_store_lookup() {
+        let dir = temp_dir();
+        let store = JsonDirStore::new(&dir);
+        let a = job("/media/a.mkv", JobStatus::Failed);
+        store.save(&a).unwrap();
+
+        assert_eq!(store.get(&a.id).unwrap().unwrap().id, a.id);
+        assert_eq!(store.find_by_status(&[JobStatus::Failed]).unwrap().len(), 1);
+        assert!(store.find_by_source(Path::new("/media/other.mkv")).unwrap().is_empty());
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_import_json_dir_once() {
+        let dir = temp_dir();
+        let a = job("/media/a.mkv", JobStatus::Pending);
+        let b = job("/media/b.mkv", JobStatus::Success);
+        job::save_job(&a, &dir).unwrap();
+        job::save_job(&b, &dir).unwrap();
+
+        let store = SqliteStore::open(&dir.join("jobs.sqlite3")).unwrap();
+        assert_eq!(store.import_json_dir(&dir).unwrap(), 2);
+        assert_eq!(store.load_all().unwrap().len(), 2);
+
+        // Files are moved aside so the JSON loader no longer sees them
+        assert!(job::load_all_jobs(&dir, false).unwrap().is_empty());
+        assert!(dir.join("migrated").join(format!("{}.json", a.id)).exists());
+
+        // A second run is a no-op even if new JSON files show up
+        job::save_job(&job("/media/c.mkv", JobStatus::Pending), &dir).unwrap();
+        assert_eq!(store.import_json_dir(&dir).unwrap(), 0);
+        assert_eq!(store.load_all().unwrap().len(), 2);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,233 @@
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::container::{Container, OutputPlan};
+use crate::ffprobe::FFProbeStream;
+use crate::tracks::{stream_language, DroppedTrack, TrackKind};
+
+/// What happens to subtitle tracks the output container can't hold as-is
+///
+/// Text subtitles the container accepts in another format are always converted
+/// (e.g. `mov_text` to SRT for MKV, SRT to `mov_text` for MP4).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitlePolicy {
+    /// Convert incompatible text subtitles; drop incompatible bitmap subtitles
+    #[default]
+    Convert,
+    /// Write incompatible subtitles next to the media file as `.srt` (text) or
+    /// `.sup` (PGS); other bitmap formats are dropped
+    Extract,
+}
+
+/// Whether a subtitle codec is text or image based
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubtitleFormat {
+    Text,
+    Bitmap,
+}
+
+impl SubtitleFormat {
+    /// Format of an FFmpeg subtitle codec (None if unknown)
+    pub fn of(codec: &str) -> Option<Self> {
+        match codec {
+            "subrip" | "srt" | "ass" | "ssa" | "webvtt" | "mov_text" | "text" => Some(SubtitleFormat::Text),
+            "hdmv_pgs_subtitle" | "dvd_subtitle" | "dvb_subtitle" | "xsub" => Some(SubtitleFormat::Bitmap),
+            _ => None,
+        }
+    }
+}
+
+impl Container {
+    /// Whether the muxer can store a subtitle codec without conversion
+    pub fn accepts_subtitle(self, codec: &str) -> bool {
+        match self {
+            Container::Matroska => codec != "mov_text",
+            Container::Mp4 => matches!(codec, "mov_text" | "dvd_subtitle"),
+            Container::WebM => codec == "webvtt",
+        }
+    }
+
+    /// FFmpeg encoder for text subtitles in this container
+    pub fn text_subtitle_encoder(self) -> &'static str {
+        match self {
+            Container::Matroska => "srt",
+            Container::Mp4 => "mov_text",
+            Container::WebM => "webvtt",
+        }
+    }
+}
+
+/// A subtitle track muxed into the output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubtitleStream {
+    /// Stream index in the source file
+    pub source_index: i32,
+    /// Encoder to convert with (None = copy)
+    pub encoder: Option<&'static str>,
+}
+
+/// A subtitle track written to its own file next to the media
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedSubtitle {
+    /// Stream index in the source file
+    pub source_index: i32,
+    /// Encoder to write with (None = copy)
+    pub encoder: Option<&'static str>,
+    /// FFmpeg muxer ("srt" or "sup")
+    pub muxer: &'static str,
+    /// Written by the encode, next to the temp output
+    pub temp_path: PathBuf,
+    /// Moved here once the transcoded file is in place
+    pub final_path: PathBuf,
+}
+
+/// Result of checking the kept subtitle tracks against the output container
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubtitlePlan {
+    /// Tracks in the output, in source order
+    pub streams: Vec<SubtitleStream>,
+    pub extracted: Vec<ExtractedSubtitle>,
+    pub dropped: Vec<DroppedTrack>,
+}
+
+/// Sidecar for an extracted track (`movie.mkv`, #3 eng -> `movie.3.eng.srt`)
+pub fn sidecar_path(media: &Path, index: i32, language: Option<&str>, ext: &str) -> PathBuf {
+    let suffix = match language {
+        Some(lang) => format!("{}.{}.{}", index, lang, ext),
+        None => format!("{}.{}", index, ext),
+    };
+    let stem = media.file_stem().and_then(|s| s.to_str()).unwrap_or("unknown");
+    media.with_file_name(format!("{}.{}", stem, suffix))
+}
+
+impl SubtitlePolicy {
+    /// Decide how each kept subtitle track ends up in (or next to) the output
+    ///
+    /// `temp_output` is the encode's temp file; extracted tracks are written
+    /// beside it as `{stem}.tmp.av1.s{index}.{ext}` so orphan cleanup finds them.
+    pub fn plan(
+        self,
+        streams: &[FFProbeStream],
+        kept_subtitles: &[i32],
+        output: &OutputPlan,
+        temp_output: &Path,
+    ) -> SubtitlePlan {
+        let mut plan = SubtitlePlan::default();
+        let container = output.container;
+
+        for stream in kept_subtitles.iter().filter_map(|i| streams.iter().find(|s| s.index == *i)) {
+            let codec = stream.codec_name.as_deref().unwrap_or("unknown");
+            if container.accepts_subtitle(codec) {
+                plan.streams.push(SubtitleStream { source_index: stream.index, encoder: None });
+                continue;
+            }
+
+            let format = SubtitleFormat::of(codec);
+            let extract = match (self, format) {
+                (SubtitlePolicy::Extract, Some(SubtitleFormat::Text)) => Some((Some("srt"), "srt", "srt")),
+                (SubtitlePolicy::Extract, _) if codec == "hdmv_pgs_subtitle" => Some((None, "sup", "sup")),
+                _ => None,
+            };
+            if let Some((encoder, muxer, ext)) = extract {
+                let language = stream_language(stream);
+                plan.extracted.push(ExtractedSubtitle {
+                    source_index: stream.index,
+                    encoder,
+                    muxer,
+                    temp_path: temp_output.with_extension(format!("s{}.{}", stream.index, ext)),
+                    final_path: sidecar_path(&output.final_path, stream.index, language.as_deref(), ext),
+                });
+            } else if format == Some(SubtitleFormat::Text) {
+                plan.streams.push(SubtitleStream {
+                    source_index: stream.index,
+                    encoder: Some(container.text_subtitle_encoder()),
+                });
+            } else {
+                plan.dropped.push(DroppedTrack {
+                    index: stream.index,
+                    kind: TrackKind::Subtitle,
+                    language: stream_language(stream),
+                    codec: stream.codec_name.clone(),
+                    reason: Some(format!("{} can't hold {}", container.muxer(), codec)),
+                });
+            }
+        }
+
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use crate::container::ContainerPolicy;
+
+    fn subtitle(index: i32, codec: &str, lang: &str) -> FFProbeStream {
+        FFProbeStream {
+            index,
+            codec_type: Some("subtitle".to_string()),
+            codec_name: Some(codec.to_string()),
+            width: None,
+            height: None,
+            avg_frame_rate: None,
+            r_frame_rate: None,
+            tags: Some(HashMap::from([("language".to_string(), lang.to_string())])),
+            bit_rate: None,
+            disposition: None,
+            pix_fmt: None,
+            bits_per_raw_sample: None,
+            color_transfer: None,
+            color_primaries: None,
+            color_space: None,
+            field_order: None,
+            channels: None,
+            channel_layout: None,
+            profile: None,
+            side_data_list: None,
+        }
+    }
+
+    fn streams() -> Vec<FFProbeStream> {
+        vec![
+            subtitle(2, "subrip", "eng"),
+            subtitle(3, "hdmv_pgs_subtitle", "eng"),
+            subtitle(4, "mov_text", "fre"),
+            subtitle(5, "dvb_subtitle", "ger"),
+        ]
+    }
+
+    #[test]
+    fn test_mkv_keeps_everything_but_mov_text() {
+        let output = ContainerPolicy::Keep.plan(Path::new("/media/movie.mkv"));
+        let plan = SubtitlePolicy::Convert.plan(&streams(), &[2, 3, 4, 5], &output, Path::new("/tmp/movie.tmp.av1.mkv"));
+        let encoders: Vec<(i32, Option<&str>)> = plan.streams.iter().map(|s| (s.source_index, s.encoder)).collect();
+        assert_eq!(encoders, vec![(2, None), (3, None), (4, Some("srt")), (5, None)]);
+        assert!(plan.dropped.is_empty());
+    }
+
+    #[test]
+    fn test_mp4_converts_text_and_drops_bitmaps() {
+        let output = ContainerPolicy::Keep.plan(Path::new("/media/movie.mp4"));
+        let plan = SubtitlePolicy::Convert.plan(&streams(), &[2, 3, 4, 5], &output, Path::new("/tmp/movie.tmp.av1.mp4"));
+        let encoders: Vec<(i32, Option<&str>)> = plan.streams.iter().map(|s| (s.source_index, s.encoder)).collect();
+        assert_eq!(encoders, vec![(2, Some("mov_text")), (4, None)]);
+        assert_eq!(plan.dropped.iter().map(|d| d.index).collect::<Vec<_>>(), vec![3, 5]);
+        assert_eq!(plan.dropped[0].to_string(), "#3 subtitle eng (hdmv_pgs_subtitle) - mp4 can't hold hdmv_pgs_subtitle");
+        assert!(plan.extracted.is_empty());
+    }
+
+    #[test]
+    fn test_extract_writes_sidecars() {
+        let output = ContainerPolicy::Keep.plan(Path::new("/media/movie.webm"));
+        let plan = SubtitlePolicy::Extract.plan(&streams(), &[2, 3, 5], &output, Path::new("/tmp/movie.tmp.av1.webm"));
+        assert!(plan.streams.is_empty());
+        assert_eq!(plan.extracted.len(), 2);
+        assert_eq!(plan.extracted[0].temp_path, PathBuf::from("/tmp/movie.tmp.av1.s2.srt"));
+        assert_eq!(plan.extracted[0].final_path, PathBuf::from("/media/movie.2.eng.srt"));
+        assert_eq!(plan.extracted[1].muxer, "sup");
+        assert_eq!(plan.extracted[1].encoder, None);
+        // DVB subtitles have no sidecar format
+        assert_eq!(plan.dropped.iter().map(|d| d.index).collect::<Vec<_>>(), vec![5]);
+    }
+}
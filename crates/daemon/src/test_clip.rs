@@ -1,12 +1,14 @@
 use std::path::{Path, PathBuf};
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use crate::classifier::QualityTier;
 use crate::quality::EncodingParams;
 use crate::ffmpeg_native::{FFmpegManager, CommandBuilder};
 use crate::ffprobe::FFProbeData;
 
 /// User decision after reviewing test clip
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ApprovalDecision {
     /// User approves the quality, proceed with full encode
     Approved,
@@ -18,6 +20,31 @@ pub enum ApprovalDecision {
     Rejected,
 }
 
+impl ApprovalDecision {
+    /// Default CRF reduction when the user asks for higher quality
+    pub const DEFAULT_CRF_STEP: u8 = 2;
+    /// Default preset reduction when the user asks for a slower encode
+    pub const DEFAULT_PRESET_STEP: u8 = 1;
+
+    /// Build a decision from a command-file action
+    ///
+    /// Actions: "approve", "lower_crf", "slower_preset", "reject".
+    /// `amount` overrides the default step for the adjusting actions.
+    pub fn from_command(action: &str, amount: Option<u8>) -> Option<Self> {
+        match action {
+            "approve" => Some(ApprovalDecision::Approved),
+            "lower_crf" => Some(ApprovalDecision::LowerCrf(
+                amount.unwrap_or(Self::DEFAULT_CRF_STEP),
+            )),
+            "slower_preset" => Some(ApprovalDecision::SlowerPreset(
+                amount.unwrap_or(Self::DEFAULT_PRESET_STEP),
+            )),
+            "reject" => Some(ApprovalDecision::Rejected),
+            _ => None,
+        }
+    }
+}
+
 /// Information about an extracted test clip
 #[derive(Debug, Clone)]
 pub struct TestClipInfo {
@@ -90,6 +117,7 @@ impl TestClipWorkflow {
         // Execute extraction
         let mut cmd = tokio::process::Command::new(&ffmpeg_mgr.ffmpeg_bin);
         cmd.args(&args);
+        cmd.kill_on_drop(true);
         
         let output = cmd.output().await
             .context("Failed to execute FFmpeg for test clip extraction")?;
@@ -130,14 +158,23 @@ impl TestClipWorkflow {
                 .unwrap_or("test_clip.mkv")
         );
         let encoded_path = self.temp_dir.join(encoded_filename);
+
+        // Re-encodes after a parameter adjustment reuse the same path; FFmpeg
+        // refuses to overwrite without -y, so clear any previous attempt first
+        if encoded_path.exists() {
+            std::fs::remove_file(&encoded_path)
+                .with_context(|| format!("Failed to remove previous encoded test clip {}", encoded_path.display()))?;
+        }
         
         // Build encode command
+        // The clip only holds FFmpeg's default stream picks, so source track indexes don't apply
+        let clip_params = EncodingParams { tracks: None, audio: Vec::new(), subtitles: None, ..params.clone() };
         let builder = CommandBuilder::new();
         let encoder = ffmpeg_mgr.best_encoder();
         let args = builder.build_encode_command(
             &clip_info.clip_path,
             &encoded_path,
-            params,
+            &clip_params,
             encoder,
             meta,
         );
@@ -145,6 +182,7 @@ impl TestClipWorkflow {
         // Execute encoding
         let mut cmd = tokio::process::Command::new(&ffmpeg_mgr.ffmpeg_bin);
         cmd.args(&args);
+        cmd.kill_on_drop(true);
         
         let output = cmd.output().await
             .context("Failed to execute FFmpeg for test clip encoding")?;
@@ -191,6 +229,31 @@ impl TestClipWorkflow {
         adjusted
     }
 
+    /// Apply a sequence of user decisions in order
+    ///
+    /// Used to rebuild the current parameters for a job from the decisions
+    /// persisted with it across daemon restarts.
+    pub fn apply_decisions(
+        &self,
+        params: &EncodingParams,
+        decisions: &[ApprovalDecision],
+    ) -> EncodingParams {
+        decisions
+            .iter()
+            .fold(params.clone(), |acc, decision| self.adjust_parameters(&acc, decision))
+    }
+
+    /// Remove the extracted and encoded files for a test clip, ignoring missing files
+    pub fn cleanup_clip_files(clip_path: Option<&Path>, encoded_path: Option<&Path>) {
+        for path in [clip_path, encoded_path].into_iter().flatten() {
+            if path.exists() {
+                if let Err(e) = std::fs::remove_file(path) {
+                    log::warn!("Failed to remove test clip file {}: {}", path.display(), e);
+                }
+            }
+        }
+    }
+
     /// Calculate test clip duration based on source metadata
     /// 
     /// Returns duration in seconds (30-60 range)
@@ -265,6 +328,16 @@ mod tests {
             film_grain: Some(8),
             bit_depth: BitDepth::Bit10,
             pixel_format: "yuv420p10le".to_string(),
+            threads: None,
+            tracks: None,
+            audio: Vec::new(),
+            subtitles: None,
+            strip_dolby_vision: false,
+            color: None,
+            crop: None,
+            scan_type: None,
+            scale: None,
+            extra_encoder_params: None,
         }
     }
 
@@ -385,6 +458,16 @@ mod tests {
                 film_grain: if has_film_grain { Some(8) } else { None },
                 bit_depth: BitDepth::Bit10,
                 pixel_format: "yuv420p10le".to_string(),
+                threads: None,
+                tracks: None,
+                audio: Vec::new(),
+                subtitles: None,
+                strip_dolby_vision: false,
+                color: None,
+                crop: None,
+                scan_type: None,
+                scale: None,
+                extra_encoder_params: None,
             };
 
             // Simulate approval (no adjustment)
@@ -459,4 +542,32 @@ mod tests {
         let duration3 = TestClipWorkflow::parse_duration("00:00:00").unwrap();
         assert!((duration3 - 0.0).abs() < 0.1);
     }
+
+    #[test]
+    fn test_decision_from_command() {
+        assert_eq!(ApprovalDecision::from_command("approve", None), Some(ApprovalDecision::Approved));
+        assert_eq!(ApprovalDecision::from_command("lower_crf", None), Some(ApprovalDecision::LowerCrf(2)));
+        assert_eq!(ApprovalDecision::from_command("lower_crf", Some(4)), Some(ApprovalDecision::LowerCrf(4)));
+        assert_eq!(ApprovalDecision::from_command("slower_preset", None), Some(ApprovalDecision::SlowerPreset(1)));
+        assert_eq!(ApprovalDecision::from_command("reject", None), Some(ApprovalDecision::Rejected));
+        assert_eq!(ApprovalDecision::from_command("requeue", None), None);
+    }
+
+    #[test]
+    fn test_apply_decisions_accumulates_adjustments() {
+        let workflow = TestClipWorkflow::new(PathBuf::from("/tmp"));
+        let params = create_test_params(18, 3);
+        let decisions = vec![
+            ApprovalDecision::LowerCrf(2),
+            ApprovalDecision::SlowerPreset(1),
+            ApprovalDecision::LowerCrf(2),
+        ];
+
+        let adjusted = workflow.apply_decisions(&params, &decisions);
+        assert_eq!(adjusted.crf, 14);
+        assert_eq!(adjusted.preset, 2);
+
+        // No decisions leaves params untouched
+        assert_eq!(workflow.apply_decisions(&params, &[]), params);
+    }
 }
@@ -0,0 +1,338 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::ffprobe::FFProbeStream;
+
+/// Which audio and subtitle tracks are copied into the output
+///
+/// Languages are matched case-insensitively against the stream's `language`
+/// tag (ISO 639-2 codes such as "eng" or "rus"). With both lists empty every
+/// track is kept.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackSelection {
+    /// Only keep tracks in these languages (empty = any language not dropped)
+    #[serde(default)]
+    pub keep_languages: Vec<String>,
+    /// Drop tracks in these languages
+    #[serde(default)]
+    pub drop_languages: Vec<String>,
+    /// Always keep tracks flagged default or forced (default: true)
+    #[serde(default = "default_true")]
+    pub keep_default_and_forced: bool,
+    /// Always keep tracks without a language or tagged "und" (default: true)
+    #[serde(default = "default_true")]
+    pub keep_undetermined: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for TrackSelection {
+    fn default() -> Self {
+        Self {
+            keep_languages: Vec::new(),
+            drop_languages: Vec::new(),
+            keep_default_and_forced: true,
+            keep_undetermined: true,
+        }
+    }
+}
+
+/// What to do with sources that have more than one real video stream
+/// (multi-angle discs, picture-in-picture commentary, ...)
+///
+/// Only the main video stream is encoded; cover art is not counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtraVideoPolicy {
+    /// Encode the main stream and record the others as dropped
+    #[default]
+    Warn,
+    /// Leave the file untouched
+    Skip,
+}
+
+/// Kind of a selectable track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrackKind {
+    Video,
+    Audio,
+    Subtitle,
+}
+
+/// A track left out of the output, recorded on the job
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DroppedTrack {
+    /// Stream index in the source file
+    pub index: i32,
+    pub kind: TrackKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    /// Why the track was dropped when it wasn't the language rules
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+impl std::fmt::Display for DroppedTrack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self.kind {
+            TrackKind::Video => "video",
+            TrackKind::Audio => "audio",
+            TrackKind::Subtitle => "subtitle",
+        };
+        write!(f, "#{} {} {} ({})", self.index, kind,
+               self.language.as_deref().unwrap_or("und"),
+               self.codec.as_deref().unwrap_or("unknown"))?;
+        if let Some(reason) = &self.reason {
+            write!(f, " - {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// Result of applying a `TrackSelection` to a source's streams
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackPlan {
+    /// Source stream index of the video stream to encode (None = first video stream)
+    pub video: Option<i32>,
+    /// Cover art streams copied through as-is
+    pub attached_pictures: Vec<i32>,
+    /// Source stream indexes of the audio tracks to map, in source order
+    pub audio: Vec<i32>,
+    /// Source stream indexes of the subtitle tracks to map, in source order
+    pub subtitles: Vec<i32>,
+    pub dropped: Vec<DroppedTrack>,
+}
+
+/// Language tag of a stream, lowercased (None if missing or undetermined)
+pub fn stream_language(stream: &FFProbeStream) -> Option<String> {
+    let tags = stream.tags.as_ref()?;
+    let lang = tags.get("language").or_else(|| tags.get("LANGUAGE"))?;
+    let lang = lang.trim().to_lowercase();
+    if lang.is_empty() || lang == "und" {
+        None
+    } else {
+        Some(lang)
+    }
+}
+
+/// Whether a stream has the given disposition flag set (e.g. "default", "forced")
+pub fn has_disposition(stream: &FFProbeStream, flag: &str) -> bool {
+    stream.disposition.as_ref()
+        .and_then(|d| d.get(flag))
+        .is_some_and(|v| *v != 0)
+}
+
+impl TrackSelection {
+    /// Check that no language is both kept and dropped
+    pub fn validate(&self) -> Result<()> {
+        if let Some(lang) = self.keep_languages.iter()
+            .find(|k| self.drop_languages.iter().any(|d| d.eq_ignore_ascii_case(k)))
+        {
+            return Err(anyhow!("language \"{}\" is in both keep_languages and drop_languages", lang));
+        }
+        Ok(())
+    }
+
+    fn keeps(&self, stream: &FFProbeStream) -> bool {
+        if self.keep_default_and_forced
+            && (has_disposition(stream, "default") || has_disposition(stream, "forced"))
+        {
+            return true;
+        }
+        let Some(lang) = stream_language(stream) else {
+            return self.keep_undetermined || self.keep_languages.is_empty();
+        };
+        if self.drop_languages.iter().any(|d| d.eq_ignore_ascii_case(&lang)) {
+            return false;
+        }
+        self.keep_languages.is_empty()
+            || self.keep_languages.iter().any(|k| k.eq_ignore_ascii_case(&lang))
+    }
+
+    /// Decide which video, audio and subtitle streams to keep
+    ///
+    /// The first video stream that isn't cover art is encoded; further video
+    /// streams are dropped. At least one audio track is always kept: if the rules
+    /// drop all of them, the default track (or the first one) stays.
+    pub fn select(&self, streams: &[FFProbeStream]) -> TrackPlan {
+        let mut plan = TrackPlan::default();
+
+        for stream in streams {
+            let kind = match stream.codec_type.as_deref() {
+                Some("video") if stream.is_attached_picture() => {
+                    plan.attached_pictures.push(stream.index);
+                    continue;
+                }
+                Some("video") if plan.video.is_none() => {
+                    plan.video = Some(stream.index);
+                    continue;
+                }
+                Some("video") => {
+                    plan.dropped.push(DroppedTrack {
+                        index: stream.index,
+                        kind: TrackKind::Video,
+                        language: stream_language(stream),
+                        codec: stream.codec_name.clone(),
+                        reason: Some("extra video stream, only the main one is encoded".to_string()),
+                    });
+                    continue;
+                }
+                Some("audio") => TrackKind::Audio,
+                Some("subtitle") => TrackKind::Subtitle,
+                _ => continue,
+            };
+            if self.keeps(stream) {
+                if kind == TrackKind::Audio {
+                    plan.audio.push(stream.index);
+                } else {
+                    plan.subtitles.push(stream.index);
+                }
+            } else {
+                plan.dropped.push(DroppedTrack {
+                    index: stream.index,
+                    kind,
+                    language: stream_language(stream),
+                    codec: stream.codec_name.clone(),
+                    reason: None,
+                });
+            }
+        }
+
+        if plan.audio.is_empty() {
+            let audio = || streams.iter().filter(|s| s.codec_type.as_deref() == Some("audio"));
+            let fallback = audio().find(|s| has_disposition(s, "default")).or_else(|| audio().next());
+            if let Some(stream) = fallback {
+                plan.audio.push(stream.index);
+                plan.dropped.retain(|d| d.index != stream.index);
+            }
+        }
+
+        plan
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn stream(index: i32, codec_type: &str, lang: Option<&str>, disposition: &[&str]) -> FFProbeStream {
+        FFProbeStream {
+            index,
+            codec_type: Some(codec_type.to_string()),
+            codec_name: Some(if codec_type == "audio" { "ac3" } else { "subrip" }.to_string()),
+            width: None,
+            height: None,
+            avg_frame_rate: None,
+            r_frame_rate: None,
+            tags: lang.map(|l| HashMap::from([("language".to_string(), l.to_string())])),
+            bit_rate: None,
+            disposition: Some(disposition.iter().map(|d| (d.to_string(), 1)).collect()),
+            pix_fmt: None,
+            bits_per_raw_sample: None,
+            color_transfer: None,
+            color_primaries: None,
+            color_space: None,
+            field_order: None,
+            channels: None,
+            channel_layout: None,
+            profile: None,
+            side_data_list: None,
+        }
+    }
+
+    #[test]
+    fn test_default_keeps_everything() {
+        let streams = vec![
+            stream(1, "audio", Some("eng"), &[]),
+            stream(2, "audio", Some("rus"), &[]),
+            stream(3, "subtitle", None, &[]),
+        ];
+        let plan = TrackSelection::default().select(&streams);
+        assert_eq!(plan.audio, vec![1, 2]);
+        assert_eq!(plan.subtitles, vec![3]);
+        assert!(plan.dropped.is_empty());
+    }
+
+    #[test]
+    fn test_drop_and_keep_languages() {
+        let streams = vec![
+            stream(1, "audio", Some("eng"), &[]),
+            stream(2, "audio", Some("RUS"), &[]),
+            stream(3, "audio", Some("jpn"), &[]),
+            stream(4, "subtitle", Some("rus"), &["forced"]),
+            stream(5, "subtitle", Some("fre"), &[]),
+            stream(6, "subtitle", Some("und"), &[]),
+        ];
+        let selection = TrackSelection {
+            drop_languages: vec!["rus".to_string()],
+            ..Default::default()
+        };
+        let plan = selection.select(&streams);
+        assert_eq!(plan.audio, vec![1, 3]);
+        // Forced subtitles stay even in a dropped language
+        assert_eq!(plan.subtitles, vec![4, 5, 6]);
+        assert_eq!(plan.dropped.len(), 1);
+        assert_eq!(plan.dropped[0].language.as_deref(), Some("rus"));
+
+        let selection = TrackSelection {
+            keep_languages: vec!["eng".to_string()],
+            keep_default_and_forced: false,
+            ..Default::default()
+        };
+        let plan = selection.select(&streams);
+        assert_eq!(plan.audio, vec![1]);
+        assert_eq!(plan.subtitles, vec![6]);
+        assert_eq!(plan.dropped.len(), 4);
+    }
+
+    #[test]
+    fn test_keeps_one_audio_track() {
+        let streams = vec![
+            stream(1, "audio", Some("rus"), &[]),
+            stream(2, "audio", Some("ukr"), &["default"]),
+        ];
+        let selection = TrackSelection {
+            keep_languages: vec!["eng".to_string()],
+            keep_default_and_forced: false,
+            ..Default::default()
+        };
+        let plan = selection.select(&streams);
+        assert_eq!(plan.audio, vec![2]);
+        assert_eq!(plan.dropped.iter().map(|d| d.index).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn test_video_streams() {
+        let mut cover = stream(0, "video", None, &["attached_pic"]);
+        cover.codec_name = Some("mjpeg".to_string());
+        let streams = vec![
+            cover,
+            stream(1, "video", None, &["default"]),
+            stream(2, "audio", Some("eng"), &[]),
+            stream(3, "video", Some("eng"), &[]),
+        ];
+        let plan = TrackSelection::default().select(&streams);
+        assert_eq!(plan.video, Some(1));
+        assert_eq!(plan.attached_pictures, vec![0]);
+        assert_eq!(plan.audio, vec![2]);
+        assert_eq!(plan.dropped.len(), 1);
+        assert_eq!(plan.dropped[0].kind, TrackKind::Video);
+        assert_eq!(plan.dropped[0].index, 3);
+    }
+
+    #[test]
+    fn test_validate_conflicting_lists() {
+        let selection = TrackSelection {
+            keep_languages: vec!["eng".to_string()],
+            drop_languages: vec!["ENG".to_string()],
+            ..Default::default()
+        };
+        assert!(selection.validate().is_err());
+        assert!(TrackSelection::default().validate().is_ok());
+    }
+}
@@ -0,0 +1,247 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use crate::classifier::QualityTier;
+
+/// Full-reference metric comparing the encode against the source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QualityMetric {
+    /// VMAF 0-100 (needs FFmpeg built with libvmaf)
+    #[default]
+    Vmaf,
+    /// SSIM 0-1
+    Ssim,
+    /// XPSNR of the luma plane in dB (FFmpeg 7.1+)
+    Xpsnr,
+}
+
+impl QualityMetric {
+    /// FFmpeg filter taking `[distorted][reference]`
+    pub fn filter(self) -> &'static str {
+        match self {
+            QualityMetric::Vmaf => "libvmaf",
+            QualityMetric::Ssim => "ssim",
+            QualityMetric::Xpsnr => "xpsnr",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            QualityMetric::Vmaf => "VMAF",
+            QualityMetric::Ssim => "SSIM",
+            QualityMetric::Xpsnr => "XPSNR",
+        }
+    }
+
+    /// Highest score the metric can report (XPSNR has no upper bound)
+    fn max_score(self) -> f64 {
+        match self {
+            QualityMetric::Vmaf => 100.0,
+            QualityMetric::Ssim => 1.0,
+            QualityMetric::Xpsnr => f64::INFINITY,
+        }
+    }
+
+    /// Format a score with the precision that matters for the metric
+    pub fn format_score(self, score: f64) -> String {
+        match self {
+            QualityMetric::Ssim => format!("{:.4}", score),
+            QualityMetric::Vmaf | QualityMetric::Xpsnr => format!("{:.2}", score),
+        }
+    }
+
+    /// Parse the summary the metric filter logs to stderr when it finishes
+    ///
+    /// - libvmaf: `VMAF score: 94.123456`
+    /// - ssim: `SSIM Y:0.987 (18.9) U:... V:... All:0.989 (19.6)` (All is used)
+    /// - xpsnr: `XPSNR average, 240 frames  y: 41.2345  u: ...` (luma is used)
+    pub fn parse_score(self, stderr: &str) -> Option<f64> {
+        let (marker, key) = match self {
+            QualityMetric::Vmaf => ("VMAF score:", "VMAF score:"),
+            QualityMetric::Ssim => ("SSIM Y:", "All:"),
+            QualityMetric::Xpsnr => ("XPSNR average", "y:"),
+        };
+        let line = stderr.lines().rev().find(|l| l.contains(marker))?;
+        let (_, rest) = line.split_once(key)?;
+        rest.split_whitespace().next()?.parse().ok()
+    }
+}
+
+/// Minimum score per quality tier (None = record the score without gating)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TierScores {
+    #[serde(default)]
+    pub remux: Option<f64>,
+    #[serde(default)]
+    pub web_dl: Option<f64>,
+    #[serde(default)]
+    pub low_quality: Option<f64>,
+}
+
+impl TierScores {
+    pub fn for_tier(&self, tier: &QualityTier) -> Option<f64> {
+        match tier {
+            QualityTier::Remux => self.remux,
+            QualityTier::WebDl => self.web_dl,
+            QualityTier::LowQuality => self.low_quality,
+        }
+    }
+}
+
+/// Post-encode perceptual quality check (default: off)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QualityCheck {
+    /// Measure every finished encode before it replaces the original (default: false)
+    #[serde(default)]
+    pub enabled: bool,
+    /// "vmaf", "ssim" or "xpsnr" (default: vmaf)
+    #[serde(default)]
+    pub metric: QualityMetric,
+    /// Segments spread over the file that get measured (default: 4)
+    #[serde(default = "default_samples")]
+    pub samples: u32,
+    /// Length of each segment in seconds (default: 10)
+    #[serde(default = "default_sample_secs")]
+    pub sample_secs: u32,
+    /// Outputs whose mean score falls below the tier's minimum are rejected and the
+    /// original is kept; the scale depends on `metric` (default: no minimum)
+    #[serde(default)]
+    pub min_score: TierScores,
+}
+
+fn default_samples() -> u32 {
+    4
+}
+
+fn default_sample_secs() -> u32 {
+    10
+}
+
+impl Default for QualityCheck {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            metric: QualityMetric::Vmaf,
+            samples: default_samples(),
+            sample_secs: default_sample_secs(),
+            min_score: TierScores::default(),
+        }
+    }
+}
+
+impl QualityCheck {
+    /// Check sample settings and that every minimum fits the metric's scale
+    pub fn validate(&self) -> Result<()> {
+        if self.samples == 0 || self.sample_secs == 0 {
+            return Err(anyhow!("samples and sample_secs must be at least 1"));
+        }
+        let min_scores = [
+            ("remux", self.min_score.remux),
+            ("web_dl", self.min_score.web_dl),
+            ("low_quality", self.min_score.low_quality),
+        ];
+        for (name, min_score) in min_scores {
+            if let Some(score) = min_score.filter(|s| !(0.0..=self.metric.max_score()).contains(s)) {
+                return Err(anyhow!("min_score.{} {} is outside the {} scale", name, score, self.metric.label()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Segment start times spread evenly over the source (only the start when the
+    /// duration is unknown)
+    pub fn sample_starts(&self, duration_secs: Option<f64>) -> Vec<f64> {
+        let Some(duration) = duration_secs.filter(|d| *d > 0.0) else {
+            return vec![0.0];
+        };
+        let length = f64::from(self.sample_secs);
+        (1..=self.samples)
+            .map(|i| duration * f64::from(i) / (f64::from(self.samples) + 1.0) - length / 2.0)
+            .map(|start| start.clamp(0.0, (duration - length).max(0.0)))
+            .collect()
+    }
+}
+
+/// Measured quality of an encode, recorded on the job
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QualityScore {
+    pub metric: QualityMetric,
+    /// Mean over the measured segments (what the minimum is checked against)
+    pub mean: f64,
+    /// Worst segment
+    pub min: f64,
+    pub samples: u32,
+}
+
+impl QualityScore {
+    /// Combine the per-segment scores (None if there are none)
+    pub fn from_samples(metric: QualityMetric, scores: &[f64]) -> Option<Self> {
+        if scores.is_empty() {
+            return None;
+        }
+        Some(QualityScore {
+            metric,
+            mean: scores.iter().sum::<f64>() / scores.len() as f64,
+            min: scores.iter().copied().fold(f64::INFINITY, f64::min),
+            samples: scores.len() as u32,
+        })
+    }
+}
+
+impl std::fmt::Display for QualityScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} (worst of {} segment(s): {})",
+               self.metric.label(), self.metric.format_score(self.mean), self.samples,
+               self.metric.format_score(self.min))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scores() {
+        let vmaf = "[Parsed_libvmaf_2 @ 0x55d1] VMAF score: 94.735212\n";
+        assert_eq!(QualityMetric::Vmaf.parse_score(vmaf), Some(94.735212));
+
+        let ssim = "[Parsed_ssim_2 @ 0x55d1] SSIM Y:0.981234 (17.263) U:0.990000 (20.000) V:0.991000 (20.457) All:0.985512 (18.390)\n";
+        assert_eq!(QualityMetric::Ssim.parse_score(ssim), Some(0.985512));
+
+        let xpsnr = "[Parsed_xpsnr_2 @ 0x55d1] XPSNR average, 240 frames  y: 41.2345  u: 43.1000  v: 43.5000  (minimum: 40.9000)\n";
+        assert_eq!(QualityMetric::Xpsnr.parse_score(xpsnr), Some(41.2345));
+
+        assert_eq!(QualityMetric::Vmaf.parse_score("No such filter: 'libvmaf'"), None);
+    }
+
+    #[test]
+    fn test_sample_starts() {
+        let check = QualityCheck { samples: 3, sample_secs: 10, ..Default::default() };
+        assert_eq!(check.sample_starts(Some(400.0)), vec![95.0, 195.0, 295.0]);
+        assert_eq!(check.sample_starts(None), vec![0.0]);
+        // Short files never seek before the start
+        assert!(check.sample_starts(Some(8.0)).iter().all(|s| *s == 0.0));
+    }
+
+    #[test]
+    fn test_score_summary() {
+        let score = QualityScore::from_samples(QualityMetric::Vmaf, &[95.0, 91.5, 96.5]).unwrap();
+        assert_eq!(score.mean, 94.33333333333333);
+        assert_eq!(score.to_string(), "VMAF 94.33 (worst of 3 segment(s): 91.50)");
+        assert_eq!(QualityScore::from_samples(QualityMetric::Ssim, &[]), None);
+    }
+
+    #[test]
+    fn test_min_score_must_fit_metric() {
+        let mut check = QualityCheck {
+            metric: QualityMetric::Ssim,
+            min_score: TierScores { low_quality: Some(95.0), ..Default::default() },
+            ..Default::default()
+        };
+        assert!(check.validate().is_err());
+        check.metric = QualityMetric::Vmaf;
+        assert!(check.validate().is_ok());
+        assert_eq!(check.min_score.for_tier(&QualityTier::LowQuality), Some(95.0));
+        assert_eq!(check.min_score.for_tier(&QualityTier::Remux), None);
+    }
+}